@@ -3,7 +3,7 @@
 
 use log::{error, trace, warn};
 use pet_fs::path::resolve_symlink;
-use pet_python_utils::executable::new_silent_command;
+use pet_python_utils::executable::{new_silent_command, output_with_timeout};
 use std::path::PathBuf;
 
 #[derive(Debug, serde::Deserialize)]
@@ -46,10 +46,7 @@ impl CondaInfo {
             resolve_symlink(&executable).unwrap_or(executable)
         };
 
-        let result = new_silent_command(&executable)
-            .arg("info")
-            .arg("--json")
-            .output();
+        let result = output_with_timeout(new_silent_command(&executable).arg("info").arg("--json"));
         trace!("Executing Conda: {:?} info --json -a", executable);
         match result {
             Ok(output) => {
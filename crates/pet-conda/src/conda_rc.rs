@@ -4,17 +4,29 @@
 use crate::env_variables::EnvVariables;
 use log::trace;
 use pet_fs::path::expand_path;
+use serde::{Deserialize, Serialize};
 use std::{
-    collections::HashSet,
+    collections::{HashMap, HashSet},
     env, fs,
     path::{Path, PathBuf},
 };
 use yaml_rust2::YamlLoader;
 
+/// An env dir & the rc file it was read from.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CondarcEnvDir {
+    pub env_dir: PathBuf,
+    pub file: PathBuf,
+}
+
 #[derive(Debug)]
 pub struct Condarc {
     pub files: Vec<PathBuf>,
+    /// Env dirs of all the rc files, merged in the order of precedence.
     pub env_dirs: Vec<PathBuf>,
+    /// The rc file that contributed each of the env dirs.
+    pub env_dir_sources: Vec<CondarcEnvDir>,
 }
 
 impl Condarc {
@@ -22,7 +34,7 @@ impl Condarc {
         get_conda_conda_rc(env_vars)
     }
     pub fn from_path(path: &Path) -> Option<Condarc> {
-        get_conda_conda_rc_from_path(&path.to_path_buf())
+        get_conda_conda_rc_from_path(path)
     }
 }
 
@@ -137,16 +149,24 @@ pub fn get_conda_rc_search_paths(env_vars: &EnvVariables) -> Vec<PathBuf> {
         search_paths.append(&mut vec![
             conda_root.join(".condarc"),
             conda_root.join("condarc"),
-            conda_root.join(".condarc.d"),
+            conda_root.join("condarc.d"),
             conda_root.join(".mambarc"),
         ]);
     }
+    if let Some(ref conda_dir) = env_vars.conda_dir {
+        let conda_dir = expand_path(PathBuf::from(conda_dir.clone()));
+        search_paths.append(&mut vec![
+            conda_dir.join(".condarc"),
+            conda_dir.join("condarc"),
+            conda_dir.join("condarc.d"),
+        ]);
+    }
     if let Some(ref xdg_config_home) = env_vars.xdg_config_home {
+        let xdg_conda = PathBuf::from(xdg_config_home.clone()).join("conda");
         search_paths.append(&mut vec![
-            PathBuf::from(xdg_config_home.clone()).join(".condarc"),
-            PathBuf::from(xdg_config_home.clone()).join("condarc"),
-            PathBuf::from(xdg_config_home.clone()).join(".condarc.d"),
-            PathBuf::from(xdg_config_home.clone()).join(".mambarc"),
+            xdg_conda.join(".condarc"),
+            xdg_conda.join("condarc"),
+            xdg_conda.join("condarc.d"),
         ]);
     }
     if let Some(ref home) = env_vars.home {
@@ -168,16 +188,8 @@ pub fn get_conda_rc_search_paths(env_vars: &EnvVariables) -> Vec<PathBuf> {
         search_paths.append(&mut vec![
             conda_prefix.join(".condarc"),
             conda_prefix.join("condarc"),
-            conda_prefix.join(".condarc.d"),
-            conda_prefix.join(".mamabarc"),
-        ]);
-    }
-    if let Some(ref conda_dir) = env_vars.conda_dir {
-        let conda_dir = expand_path(PathBuf::from(conda_dir.clone()));
-        search_paths.append(&mut vec![
-            conda_dir.join(".condarc"),
-            conda_dir.join("condarc"),
-            conda_dir.join(".condarc.d"),
+            conda_prefix.join("condarc.d"),
+            conda_prefix.join(".mambarc"),
         ]);
     }
     if let Some(ref condarc) = env_vars.condarc {
@@ -187,13 +199,35 @@ pub fn get_conda_rc_search_paths(env_vars: &EnvVariables) -> Vec<PathBuf> {
         search_paths.append(&mut vec![expand_path(PathBuf::from(mambarc))]);
     }
 
-    let search_paths: HashSet<_> = search_paths.into_iter().collect();
-    search_paths.into_iter().collect()
+    // The order matters, files later in the search path take precedence.
+    let mut unique = HashSet::new();
+    search_paths
+        .into_iter()
+        .filter(|path| unique.insert(path.clone()))
+        .collect()
 }
 
 // https://github.com/conda/conda/blob/3ae5d7cf6cbe2b0ff9532359456b7244ae1ea5ef/conda/common/configuration.py#L1315
 static POSSIBLE_CONDA_RC_FILES: &[&str] = &[".condarc", "condarc", ".condarc.d"];
 static SUPPORTED_EXTENSIONS: &[&str] = &["yaml", "yml"];
+static ENV_DIRS_KEYS: &[&str] = &["envs_dirs", "envs_path"];
+
+/// Priority markers of items in a list, see
+/// https://docs.conda.io/projects/conda/en/latest/user-guide/configuration/use-condarc.html#precedence
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Marker {
+    Top,
+    Bottom,
+}
+
+/// Env dirs defined in a single rc file.
+#[derive(Debug, Default)]
+struct CondarcFile {
+    file: PathBuf,
+    env_dirs: Vec<(PathBuf, Option<Marker>)>,
+    /// Whether the env dirs were marked with `#!final`, i.e. cannot be overridden by other rc files.
+    is_final: bool,
+}
 
 /**
  * The .condarc file contains a list of directories where conda environments are created.
@@ -204,44 +238,39 @@ static SUPPORTED_EXTENSIONS: &[&str] = &["yaml", "yml"];
  * https://github.com/conda/conda/blob/3ae5d7cf6cbe2b0ff9532359456b7244ae1ea5ef/conda/base/constants.py#L28
  */
 fn get_conda_conda_rc(env_vars: &EnvVariables) -> Option<Condarc> {
-    let mut env_dirs = vec![];
-    let mut files = vec![];
-    for conda_rc in get_conda_rc_search_paths(env_vars).into_iter() {
-        if let Some(ref mut cfg) = get_conda_conda_rc_from_path(&conda_rc) {
-            env_dirs.append(&mut cfg.env_dirs);
-            files.append(&mut cfg.files);
-        }
-    }
+    merge_conda_rc_files(
+        get_conda_rc_search_paths(env_vars)
+            .iter()
+            .flat_map(|conda_rc| read_conda_rc_files(conda_rc))
+            .collect(),
+    )
+}
 
-    if env_dirs.is_empty() && files.is_empty() {
-        None
-    } else {
-        Some(Condarc { env_dirs, files })
-    }
+fn get_conda_conda_rc_from_path(conda_rc: &Path) -> Option<Condarc> {
+    merge_conda_rc_files(read_conda_rc_files(conda_rc))
 }
 
-fn get_conda_conda_rc_from_path(conda_rc: &PathBuf) -> Option<Condarc> {
-    let mut env_dirs = vec![];
-    let mut files = vec![];
+fn read_conda_rc_files(conda_rc: &Path) -> Vec<CondarcFile> {
     if conda_rc.is_file() {
-        if let Some(ref mut cfg) = parse_conda_rc(conda_rc) {
-            env_dirs.append(&mut cfg.env_dirs);
-            files.push(conda_rc.clone());
-        }
-    } else if conda_rc.is_dir() {
-        // There can be different types of conda rc files in the directory.
-        // .condarc, condarc, .condarc.yml, condarc.yaml, etc.
-        // https://github.com/conda/conda/blob/3ae5d7cf6cbe2b0ff9532359456b7244ae1ea5ef/conda/common/configuration.py#L1315
-        // https://conda.io/projects/conda/en/latest/user-guide/configuration/use-condarc.html
-        if let Ok(reader) = fs::read_dir(conda_rc) {
-            for path in reader
-                .filter_map(Result::ok)
-                .map(|e| e.path())
-                .filter(|p| p.is_file())
-            {
+        return parse_conda_rc(conda_rc).into_iter().collect();
+    }
+    if !conda_rc.is_dir() {
+        return vec![];
+    }
+    // There can be different types of conda rc files in the directory.
+    // .condarc, condarc, .condarc.yml, condarc.yaml, etc.
+    // Conda reads them sorted by name, & the later files take precedence.
+    // https://github.com/conda/conda/blob/3ae5d7cf6cbe2b0ff9532359456b7244ae1ea5ef/conda/common/configuration.py#L1315
+    // https://conda.io/projects/conda/en/latest/user-guide/configuration/use-condarc.html
+    let mut paths: Vec<PathBuf> = match fs::read_dir(conda_rc) {
+        Ok(reader) => reader
+            .filter_map(Result::ok)
+            .map(|e| e.path())
+            .filter(|p| p.is_file())
+            .filter(|path| {
                 let file_name = path
                     .file_name()
-                    .unwrap()
+                    .unwrap_or_default()
                     .to_str()
                     .unwrap_or_default()
                     .to_lowercase();
@@ -252,91 +281,249 @@ fn get_conda_conda_rc_from_path(conda_rc: &PathBuf) -> Option<Condarc> {
                     .unwrap_or_default()
                     .to_lowercase();
 
-                if POSSIBLE_CONDA_RC_FILES.contains(&file_name.as_str())
+                POSSIBLE_CONDA_RC_FILES.contains(&file_name.as_str())
                     || SUPPORTED_EXTENSIONS.contains(&extension.as_str())
                     || file_name.contains("condarc")
-                {
-                    if let Some(ref mut cfg) = parse_conda_rc(&path) {
-                        env_dirs.append(&mut cfg.env_dirs);
-                        files.push(path);
-                    }
-                }
-            }
-        }
-    }
-
-    if env_dirs.is_empty() && files.is_empty() {
-        None
-    } else {
-        trace!("conda_rc: {:?} with env_dirs {:?}", conda_rc, env_dirs);
-        Some(Condarc { env_dirs, files })
-    }
+            })
+            .collect(),
+        Err(_) => vec![],
+    };
+    paths.sort();
+    paths
+        .iter()
+        .filter_map(|path| parse_conda_rc(path))
+        .collect()
 }
 
-fn parse_conda_rc(conda_rc: &Path) -> Option<Condarc> {
+fn parse_conda_rc(conda_rc: &Path) -> Option<CondarcFile> {
     let reader = fs::read_to_string(conda_rc).ok()?;
-    if let Some(cfg) = parse_conda_rc_contents(&reader) {
+    let cfg = parse_conda_rc_contents(&reader, &|name| env::var(name).ok());
+    if let Some(ref cfg) = cfg {
         trace!("conda_rc: {:?} with env_dirs {:?}", conda_rc, cfg.env_dirs);
-        Some(Condarc {
-            env_dirs: cfg.env_dirs,
-            files: vec![conda_rc.to_path_buf()],
-        })
     } else {
         trace!("Failed to parse or empty conda_rc: {:?}", conda_rc);
-        Some(Condarc {
-            env_dirs: vec![],
-            files: vec![conda_rc.to_path_buf()],
-        })
     }
+    Some(CondarcFile {
+        file: conda_rc.to_path_buf(),
+        ..cfg.unwrap_or_default()
+    })
 }
 
-fn parse_conda_rc_contents(contents: &str) -> Option<Condarc> {
-    let mut env_dirs = vec![];
+fn parse_conda_rc_contents(
+    contents: &str,
+    get_env_var: &dyn Fn(&str) -> Option<String>,
+) -> Option<CondarcFile> {
+    let docs = YamlLoader::load_from_str(contents).ok()?;
+    let mut cfg = CondarcFile::default();
+    let doc = match docs.first() {
+        Some(doc) => doc,
+        None => return Some(cfg),
+    };
+    for key in ENV_DIRS_KEYS {
+        let items = match doc[*key].as_vec() {
+            Some(items) => items,
+            None => continue,
+        };
+        // The markers are comments, hence not available in the parsed yaml.
+        let (is_final, markers) = get_markers(contents, key);
+        cfg.is_final |= is_final;
+        for item in items {
+            let item_str = item.as_str().unwrap_or_default().trim();
+            if item_str.is_empty() {
+                continue;
+            }
+            // Expand variables in the values
+            // https://docs.conda.io/projects/conda/en/4.13.x/user-guide/configuration/use-condarc.html#expansion-of-environment-variables
+            let env_dir = expand_path(PathBuf::from(expand_env_vars(item_str, get_env_var)));
+            trace!("{}: {:?} parsed as {:?}", key, item_str, env_dir);
+            cfg.env_dirs.push((env_dir, markers.get(item_str).copied()));
+        }
+    }
+    Some(cfg)
+}
 
-    if let Ok(docs) = YamlLoader::load_from_str(contents) {
-        if docs.is_empty() {
-            return Some(Condarc {
-                env_dirs: vec![],
-                files: vec![],
-            });
+/// Merges the env dirs of the rc files, in the same way as conda merges sequence parameters.
+/// Files later in the search path take precedence, unless an earlier file is marked with `#!final`,
+/// & items marked with `#!top` or `#!bottom` are moved to the top or bottom of the list.
+/// https://github.com/conda/conda/blob/3ae5d7cf6cbe2b0ff9532359456b7244ae1ea5ef/conda/common/configuration.py#L783
+fn merge_conda_rc_files(rc_files: Vec<CondarcFile>) -> Option<Condarc> {
+    if rc_files.is_empty() {
+        return None;
+    }
+    let files: Vec<PathBuf> = rc_files.iter().map(|rc| rc.file.clone()).collect();
+    let relevant = match rc_files
+        .iter()
+        .position(|rc| rc.is_final && !rc.env_dirs.is_empty())
+    {
+        Some(index) => {
+            trace!("conda_rc: env_dirs in {:?} are final", rc_files[index].file);
+            &rc_files[..=index]
         }
-        let doc = &docs[0];
-        // Expland variables in some of these
-        // https://docs.conda.io/projects/conda/en/4.13.x/user-guide/configuration/use-condarc.html#expansion-of-environment-variables
-
-        if let Some(items) = doc["envs_dirs"].as_vec() {
-            for item in items {
-                let item_str = item.as_str().unwrap().to_string();
-                if item_str.is_empty() {
-                    continue;
-                }
-                let env_dir = expand_path(PathBuf::from(item_str.trim()));
-                trace!("env_dir: {:?} parsed as {:?}", item_str.trim(), env_dir);
-                env_dirs.push(env_dir);
+        None => &rc_files[..],
+    };
+    let items_with = |rc: &'_ CondarcFile, marker: Option<Marker>| -> Vec<CondarcEnvDir> {
+        rc.env_dirs
+            .iter()
+            .filter(|(_, m)| marker.is_none() || *m == marker)
+            .map(|(env_dir, _)| CondarcEnvDir {
+                env_dir: env_dir.clone(),
+                file: rc.file.clone(),
+            })
+            .collect()
+    };
+    let top: Vec<CondarcEnvDir> = relevant
+        .iter()
+        .flat_map(|rc| items_with(rc, Some(Marker::Top)))
+        .collect();
+    let bottom: Vec<CondarcEnvDir> = relevant
+        .iter()
+        .rev()
+        .flat_map(|rc| items_with(rc, Some(Marker::Bottom)))
+        .collect();
+    let is_marked = |item: &CondarcEnvDir| {
+        top.iter()
+            .chain(bottom.iter())
+            .any(|marked| marked.env_dir == item.env_dir)
+    };
+    let rest: Vec<CondarcEnvDir> = relevant
+        .iter()
+        .rev()
+        .flat_map(|rc| items_with(rc, None))
+        .filter(|item| !is_marked(item))
+        .collect();
+
+    let mut unique = HashSet::new();
+    let env_dir_sources: Vec<CondarcEnvDir> = top
+        .iter()
+        .chain(rest.iter())
+        .chain(bottom.iter())
+        .filter(|item| unique.insert(item.env_dir.clone()))
+        .cloned()
+        .collect();
+    for source in &env_dir_sources {
+        trace!(
+            "conda_rc: env_dir {:?} from {:?}",
+            source.env_dir,
+            source.file
+        );
+    }
+
+    Some(Condarc {
+        files,
+        env_dirs: env_dir_sources
+            .iter()
+            .map(|source| source.env_dir.clone())
+            .collect(),
+        env_dir_sources,
+    })
+}
+
+/// Gets the `#!final` marker of the key & the `#!top`/`#!bottom` markers of its items.
+fn get_markers(contents: &str, key: &str) -> (bool, HashMap<String, Marker>) {
+    let mut is_final = false;
+    let mut markers = HashMap::new();
+    let mut in_key = false;
+    for line in contents.lines() {
+        let trimmed = line.trim();
+        if trimmed.is_empty() || trimmed.starts_with('#') {
+            continue;
+        }
+        if !line.starts_with(char::is_whitespace) && !trimmed.starts_with('-') {
+            in_key = false;
+            if let Some(rest) = line
+                .strip_prefix(key)
+                .and_then(|rest| rest.trim_start().strip_prefix(':'))
+            {
+                in_key = true;
+                is_final = split_comment(rest).1.starts_with("!final");
             }
+            continue;
         }
-        if let Some(items) = doc["envs_path"].as_vec() {
-            for item in items {
-                let item_str = item.as_str().unwrap().to_string();
-                if item_str.is_empty() {
-                    continue;
-                }
-                let env_dir = expand_path(PathBuf::from(item_str.trim()));
-                trace!("env_path: {:?} parsed as {:?}", item_str.trim(), env_dir);
-                env_dirs.push(env_dir);
+        if let Some(item) = trimmed.strip_prefix('-').filter(|_| in_key) {
+            let (value, comment) = split_comment(item);
+            let marker = if comment.starts_with("!top") {
+                Marker::Top
+            } else if comment.starts_with("!bottom") {
+                Marker::Bottom
+            } else {
+                continue;
+            };
+            let value = value.trim_matches(|c| c == '"' || c == '\'');
+            markers.insert(value.to_string(), marker);
+        }
+    }
+    (is_final, markers)
+}
+
+/// Splits a yaml value into the value & the comment (without the leading `#`).
+fn split_comment(value: &str) -> (&str, &str) {
+    let value = value.trim();
+    if let Some(comment) = value.strip_prefix('#') {
+        return ("", comment.trim());
+    }
+    match value.find(" #") {
+        Some(index) => (value[..index].trim(), value[index + 2..].trim()),
+        None => (value, ""),
+    }
+}
+
+/// Expands `$VAR` & `${VAR}`, variables that are not defined are left as is.
+fn expand_env_vars(value: &str, get_env_var: &dyn Fn(&str) -> Option<String>) -> String {
+    let mut expanded = String::new();
+    let mut rest = value;
+    while let Some(index) = rest.find('$') {
+        expanded.push_str(&rest[..index]);
+        let after = &rest[index + 1..];
+        let (name, remainder) = match after.strip_prefix('{') {
+            Some(braced) => match braced.find('}') {
+                Some(end) => (&braced[..end], &braced[end + 1..]),
+                None => ("", after),
+            },
+            None => {
+                let end = after
+                    .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+                    .unwrap_or(after.len());
+                (&after[..end], &after[end..])
+            }
+        };
+        match Some(name)
+            .filter(|name| !name.is_empty())
+            .and_then(get_env_var)
+        {
+            Some(value) => {
+                expanded.push_str(&value);
+                rest = remainder;
+            }
+            None => {
+                expanded.push('$');
+                rest = after;
             }
         }
     }
-    Some(Condarc {
-        env_dirs,
-        files: vec![],
-    })
+    expanded.push_str(rest);
+    expanded
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    fn parse_env_dirs(contents: &str) -> Vec<PathBuf> {
+        parse_conda_rc_contents(contents, &|_| None)
+            .unwrap()
+            .env_dirs
+            .into_iter()
+            .map(|(env_dir, _)| env_dir)
+            .collect()
+    }
+
+    fn create_rc_file(file: &str, contents: &str) -> CondarcFile {
+        CondarcFile {
+            file: PathBuf::from(file),
+            ..parse_conda_rc_contents(contents, &|_| None).unwrap()
+        }
+    }
+
     #[test]
     fn parse_conda_rc() {
         let cfg = r#"
@@ -353,7 +540,7 @@ envs_path:
 "#;
 
         assert_eq!(
-            parse_conda_rc_contents(cfg).unwrap().env_dirs,
+            parse_env_dirs(cfg),
             [
                 PathBuf::from("/Users/username/dev/envs"),
                 PathBuf::from("/opt/conda/envs"),
@@ -373,7 +560,7 @@ envs_dirs:
 "#;
 
         assert_eq!(
-            parse_conda_rc_contents(cfg).unwrap().env_dirs,
+            parse_env_dirs(cfg),
             ["/Users/username/dev/envs", "/opt/conda/envs",].map(PathBuf::from)
         );
 
@@ -388,7 +575,7 @@ envs_path:
 "#;
 
         assert_eq!(
-            parse_conda_rc_contents(cfg).unwrap().env_dirs,
+            parse_env_dirs(cfg),
             [
                 PathBuf::from("/opt/somep lace/envs"),
                 expand_path(PathBuf::from("~/dev/envs2"))
@@ -402,7 +589,96 @@ channels:
 channel_priority: strict
 "#;
 
-        assert!(parse_conda_rc_contents(cfg).unwrap().env_dirs.is_empty(),);
-        assert!(parse_conda_rc_contents(cfg).unwrap().files.is_empty(),);
+        assert!(parse_env_dirs(cfg).is_empty());
+    }
+
+    #[test]
+    fn env_variables_are_expanded() {
+        let cfg = r#"
+envs_dirs:
+  - $SCRATCH/envs
+  - ${SCRATCH}/${USER}-envs
+  - /opt/$UNDEFINED/envs
+"#;
+        let get_env_var = |name: &str| match name {
+            "SCRATCH" => Some("/scratch".to_string()),
+            "USER" => Some("bob".to_string()),
+            _ => None,
+        };
+
+        let env_dirs: Vec<PathBuf> = parse_conda_rc_contents(cfg, &get_env_var)
+            .unwrap()
+            .env_dirs
+            .into_iter()
+            .map(|(env_dir, _)| env_dir)
+            .collect();
+
+        assert_eq!(
+            env_dirs,
+            ["/scratch/envs", "/scratch/bob-envs", "/opt/$UNDEFINED/envs"].map(PathBuf::from)
+        );
+    }
+
+    #[test]
+    fn later_files_take_precedence_and_markers_are_honoured() {
+        let system = create_rc_file(
+            "/etc/conda/.condarc",
+            r#"
+envs_dirs:
+  - /opt/conda/envs
+  - /shared/envs #!top
+"#,
+        );
+        let user = create_rc_file(
+            "/home/bob/.condarc",
+            r#"
+envs_dirs:
+  - /home/bob/envs
+  - /tmp/envs  #!bottom
+"#,
+        );
+
+        let conda_rc = merge_conda_rc_files(vec![system, user]).unwrap();
+
+        assert_eq!(
+            conda_rc.env_dirs,
+            [
+                "/shared/envs",
+                "/home/bob/envs",
+                "/opt/conda/envs",
+                "/tmp/envs"
+            ]
+            .map(PathBuf::from)
+        );
+        assert_eq!(
+            conda_rc.env_dir_sources[1],
+            CondarcEnvDir {
+                env_dir: PathBuf::from("/home/bob/envs"),
+                file: PathBuf::from("/home/bob/.condarc"),
+            }
+        );
+    }
+
+    #[test]
+    fn final_env_dirs_cannot_be_overridden() {
+        let system = create_rc_file(
+            "/etc/conda/condarc.d/scratch.yml",
+            r#"
+envs_dirs:  #!final
+  - /scratch/envs
+"#,
+        );
+        let user = create_rc_file(
+            "/home/bob/.condarc",
+            r#"
+envs_dirs:
+  - /home/bob/envs
+"#,
+        );
+
+        let conda_rc = merge_conda_rc_files(vec![system, user]).unwrap();
+
+        assert_eq!(conda_rc.env_dirs, [PathBuf::from("/scratch/envs")]);
+        assert_eq!(conda_rc.files.len(), 2);
     }
 }
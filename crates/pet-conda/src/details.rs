@@ -0,0 +1,187 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT License.
+
+//! Details of a conda environment that are too expensive to compute during discovery,
+//! such as the size on disk, hence only computed when requested for a specific environment.
+//! Everything is read from the `conda-meta` directory, conda is never spawned.
+
+use crate::utils::is_conda_env;
+use log::trace;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+    time::UNIX_EPOCH,
+};
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CondaEnvironmentDetails {
+    pub prefix: PathBuf,
+    /// Channel Python was installed from, e.g. `conda-forge` or `pkgs/main`.
+    /// If Python is not installed, then this is the channel most packages were installed from.
+    pub channel: Option<String>,
+    /// Channels of the installed packages, the channel with the most packages first.
+    pub channels: Vec<String>,
+    pub package_count: usize,
+    /// Time of the last change to the environment, in seconds since the Unix epoch.
+    pub last_modified: Option<u64>,
+    /// Total size of the files in the environment, in bytes.
+    /// Files hard linked from the package cache are included.
+    pub size: u64,
+}
+
+impl CondaEnvironmentDetails {
+    pub fn from(prefix: &Path) -> Option<Self> {
+        get_conda_environment_details(prefix)
+    }
+}
+
+#[derive(Deserialize, Debug)]
+struct CondaMetaPackage {
+    name: Option<String>,
+    channel: Option<String>,
+}
+
+fn get_conda_environment_details(prefix: &Path) -> Option<CondaEnvironmentDetails> {
+    if !is_conda_env(prefix) {
+        trace!("Not a conda environment {:?}", prefix);
+        return None;
+    }
+    let conda_meta = prefix.join("conda-meta");
+
+    // Every installed package has a json file in the conda-meta directory.
+    let packages: Vec<CondaMetaPackage> = fs::read_dir(&conda_meta)
+        .ok()?
+        .filter_map(Result::ok)
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().unwrap_or_default() == "json")
+        .map(|path| {
+            fs::read_to_string(&path)
+                .ok()
+                .and_then(|contents| serde_json::from_str::<CondaMetaPackage>(&contents).ok())
+                .unwrap_or(CondaMetaPackage {
+                    name: None,
+                    channel: None,
+                })
+        })
+        .collect();
+
+    let mut channel_counts: HashMap<String, usize> = HashMap::new();
+    for channel in packages.iter().filter_map(|p| p.channel.as_deref()) {
+        *channel_counts.entry(get_channel_name(channel)).or_default() += 1;
+    }
+    let mut channels: Vec<(String, usize)> = channel_counts.into_iter().collect();
+    channels.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    let channels: Vec<String> = channels.into_iter().map(|(name, _)| name).collect();
+
+    let channel = packages
+        .iter()
+        .find(|p| p.name.as_deref() == Some("python"))
+        .and_then(|p| p.channel.as_deref())
+        .map(get_channel_name)
+        .or_else(|| get_python_channel_from_history(&conda_meta))
+        .or_else(|| channels.first().cloned());
+
+    // The history file is updated with every change made to the environment.
+    let last_modified = fs::metadata(conda_meta.join("history"))
+        .or_else(|_| fs::metadata(&conda_meta))
+        .and_then(|metadata| metadata.modified())
+        .ok()
+        .and_then(|modified| modified.duration_since(UNIX_EPOCH).ok())
+        .map(|duration| duration.as_secs());
+
+    Some(CondaEnvironmentDetails {
+        prefix: prefix.to_path_buf(),
+        channel,
+        channels,
+        package_count: packages.len(),
+        last_modified,
+        size: get_size(prefix),
+    })
+}
+
+/// Gets the channel of the last installed Python package from the history file.
+/// Sample entries are `+defaults::python-3.10.9-hc0d8a6c_1` & `+conda-forge/osx-arm64::python-3.12.2-hdf0ec26_0_cpython`
+fn get_python_channel_from_history(conda_meta: &Path) -> Option<String> {
+    let history = fs::read_to_string(conda_meta.join("history")).ok()?;
+    history
+        .lines()
+        .rev()
+        .find(|line| line.starts_with('+') && line.contains("::python-"))
+        .and_then(|line| line[1..].split_once("::"))
+        .map(|(channel, _)| get_channel_name(channel))
+}
+
+/// Gets the name of the channel without the platform sub directory, as displayed by conda,
+/// e.g. `https://conda.anaconda.org/conda-forge/osx-arm64` is `conda-forge`
+/// & `https://repo.anaconda.com/pkgs/main/win-64` is `pkgs/main`.
+fn get_channel_name(channel: &str) -> String {
+    let mut channel = channel.trim().trim_end_matches('/');
+    for url in [
+        "https://conda.anaconda.org/",
+        "http://conda.anaconda.org/",
+        "https://repo.anaconda.com/",
+        "http://repo.anaconda.com/",
+    ] {
+        if let Some(name) = channel.strip_prefix(url) {
+            channel = name;
+            break;
+        }
+    }
+    match channel.rsplit_once('/') {
+        Some((name, subdir)) if is_platform_subdir(subdir) => name.to_string(),
+        _ => channel.to_string(),
+    }
+}
+
+fn is_platform_subdir(subdir: &str) -> bool {
+    subdir == "noarch"
+        || ["linux-", "osx-", "win-", "zos-", "emscripten-", "wasi-"]
+            .iter()
+            .any(|platform| subdir.starts_with(platform))
+}
+
+/// Size of all files in the directory, symlinks are not followed.
+fn get_size(path: &Path) -> u64 {
+    let mut size = 0;
+    let mut dirs = vec![path.to_path_buf()];
+    while let Some(dir) = dirs.pop() {
+        if let Ok(reader) = fs::read_dir(&dir) {
+            for entry in reader.filter_map(Result::ok) {
+                match entry.file_type() {
+                    Ok(file_type) if file_type.is_dir() => dirs.push(entry.path()),
+                    Ok(file_type) if file_type.is_file() => {
+                        size += entry.metadata().map(|m| m.len()).unwrap_or_default();
+                    }
+                    _ => {}
+                }
+            }
+        }
+    }
+    size
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn channel_names() {
+        assert_eq!(
+            get_channel_name("https://conda.anaconda.org/conda-forge/osx-arm64"),
+            "conda-forge"
+        );
+        assert_eq!(
+            get_channel_name("https://repo.anaconda.com/pkgs/main/win-64/"),
+            "pkgs/main"
+        );
+        assert_eq!(get_channel_name("conda-forge/noarch"), "conda-forge");
+        assert_eq!(get_channel_name("defaults"), "defaults");
+        assert_eq!(
+            get_channel_name("https://my.mirror.org/channels/internal/linux-64"),
+            "https://my.mirror.org/channels/internal"
+        );
+    }
+}
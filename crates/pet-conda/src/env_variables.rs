@@ -23,6 +23,8 @@ pub struct EnvVariables {
     pub conda: Option<String>,
     pub conda_prefix: Option<String>,
     pub mamba_root_prefix: Option<String>,
+    /// Set by the `mamba`/`micromamba` shell init scripts.
+    pub mamba_exe: Option<String>,
     /// https://docs.conda.io/projects/conda/en/22.11.x/user-guide/configuration/use-condarc.html
     pub conda_envs_path: Option<String>,
     pub condarc: Option<String>,
@@ -50,6 +52,7 @@ impl EnvVariables {
             conda: env.get_env_var("CONDA".to_string()),
             conda_prefix: env.get_env_var("CONDA_PREFIX".to_string()),
             mamba_root_prefix: env.get_env_var("MAMBA_ROOT_PREFIX".to_string()),
+            mamba_exe: env.get_env_var("MAMBA_EXE".to_string()),
             conda_envs_path: env.get_env_var("CONDA_ENVS_PATH".to_string()),
             anaconda_project_envs_path: env.get_env_var("ANACONDA_PROJECT_ENVS_PATH".to_string()),
             project_dir: env.get_env_var("PROJECT_DIR".to_string()),
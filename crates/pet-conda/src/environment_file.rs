@@ -0,0 +1,283 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT License.
+
+//! Conda environments declared in workspace folders, using an `environment.yml`, a `conda-lock.yml`
+//! or a `.conda` directory (created by VS Code for workspace environments).
+//! The declaration is used to associate the conda environment with the workspace folder,
+//! & if the environment has not been created, clients can offer to create it.
+
+use log::trace;
+use pet_core::{
+    manager::EnvManager,
+    python_environment::{PythonEnvironment, PythonEnvironmentKind},
+    reporter::Reporter,
+    telemetry::TelemetryEvent,
+};
+use pet_fs::path::{expand_path, norm_case};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+use yaml_rust2::YamlLoader;
+
+const ENVIRONMENT_FILES: [&str; 2] = ["environment.yml", "environment.yaml"];
+const CONDA_LOCK_FILE: &str = "conda-lock.yml";
+const WORKSPACE_ENV_DIR: &str = ".conda";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CondaEnvironmentFile {
+    pub workspace: PathBuf,
+    /// The `environment.yml`, `conda-lock.yml` or `.conda` directory declaring the environment.
+    pub file: PathBuf,
+    /// Value of the `name:` key in `environment.yml`.
+    pub name: Option<String>,
+    /// Value of the `prefix:` key in `environment.yml`, for `conda-lock.yml` & `.conda` the `.conda` directory in the workspace folder.
+    pub prefix: Option<PathBuf>,
+}
+
+impl CondaEnvironmentFile {
+    pub fn from(workspace: &Path) -> Option<Self> {
+        let declaration = get_conda_environment_file(workspace)?;
+        trace!("Conda environment declared in {:?}", declaration);
+        Some(declaration)
+    }
+
+    /// Whether the conda environment is the one declared in the workspace folder.
+    pub fn matches(&self, env: &PythonEnvironment) -> bool {
+        if env.kind != Some(PythonEnvironmentKind::Conda) {
+            return false;
+        }
+        match (&self.prefix, &env.prefix) {
+            (Some(prefix), Some(env_prefix)) => norm_case(prefix) == norm_case(env_prefix),
+            (None, _) => self.name.is_some() && self.name == env.name,
+            _ => false,
+        }
+    }
+
+    /// Command (program & arguments) to create the environment.
+    /// A `.conda` directory on its own does not declare how the environment is to be created.
+    pub fn get_create_command(&self, conda_exe: &Path) -> Option<Vec<String>> {
+        let file = self.file.to_string_lossy().to_string();
+        let prefix = self
+            .prefix
+            .as_ref()
+            .map(|prefix| prefix.to_string_lossy().to_string());
+        if self.file.ends_with(CONDA_LOCK_FILE) {
+            return Some(vec![
+                "conda-lock".to_string(),
+                "install".to_string(),
+                "--prefix".to_string(),
+                prefix?,
+                file,
+            ]);
+        }
+        if self.file.ends_with(WORKSPACE_ENV_DIR) {
+            return None;
+        }
+        let mut command = vec![
+            conda_exe.to_string_lossy().to_string(),
+            "env".to_string(),
+            "create".to_string(),
+            "--file".to_string(),
+            file,
+        ];
+        if self.name.is_none() {
+            command.extend(["--prefix".to_string(), prefix?]);
+        }
+        Some(command)
+    }
+}
+
+fn get_conda_environment_file(workspace: &Path) -> Option<CondaEnvironmentFile> {
+    let workspace_env = workspace.join(WORKSPACE_ENV_DIR);
+    if let Some(file) = ENVIRONMENT_FILES
+        .iter()
+        .map(|file| workspace.join(file))
+        .find(|file| file.is_file())
+    {
+        let contents = fs::read_to_string(&file).ok()?;
+        let (name, prefix) = parse_environment_file(&contents);
+        let prefix = match (&name, prefix) {
+            // Relative prefixes are relative to the workspace folder, as conda is generally run from there.
+            (_, Some(prefix)) => Some(workspace.join(expand_path(prefix))),
+            // Without a name or prefix, `conda env create --file` fails, hence the environment cannot be created.
+            (_, None) => None,
+        };
+        return Some(CondaEnvironmentFile {
+            workspace: workspace.to_path_buf(),
+            file,
+            name,
+            prefix,
+        });
+    }
+    let file = workspace.join(CONDA_LOCK_FILE);
+    if file.is_file() {
+        return Some(CondaEnvironmentFile {
+            workspace: workspace.to_path_buf(),
+            file,
+            name: None,
+            prefix: Some(workspace_env),
+        });
+    }
+    if workspace_env.is_dir() {
+        return Some(CondaEnvironmentFile {
+            workspace: workspace.to_path_buf(),
+            file: workspace_env.clone(),
+            name: None,
+            prefix: Some(workspace_env),
+        });
+    }
+    None
+}
+
+/// Gets the `name` & `prefix` keys of an `environment.yml` file.
+fn parse_environment_file(contents: &str) -> (Option<String>, Option<PathBuf>) {
+    let docs = match YamlLoader::load_from_str(contents) {
+        Ok(docs) => docs,
+        Err(err) => {
+            trace!("Failed to parse conda environment file: {:?}", err);
+            return (None, None);
+        }
+    };
+    let doc = match docs.first() {
+        Some(doc) => doc,
+        None => return (None, None),
+    };
+    let name = doc["name"]
+        .as_str()
+        .map(|name| name.trim().to_string())
+        .filter(|name| !name.is_empty());
+    let prefix = doc["prefix"]
+        .as_str()
+        .map(|prefix| prefix.trim())
+        .filter(|prefix| !prefix.is_empty())
+        .map(PathBuf::from);
+    (name, prefix)
+}
+
+/// Sets the workspace folder as the project of the conda environments declared in the workspace folders.
+pub struct CondaEnvironmentFileReporter<'a> {
+    reporter: &'a dyn Reporter,
+    declarations: &'a [CondaEnvironmentFile],
+}
+
+impl<'a> CondaEnvironmentFileReporter<'a> {
+    pub fn new(reporter: &'a dyn Reporter, declarations: &'a [CondaEnvironmentFile]) -> Self {
+        CondaEnvironmentFileReporter {
+            reporter,
+            declarations,
+        }
+    }
+}
+
+impl Reporter for CondaEnvironmentFileReporter<'_> {
+    fn report_manager(&self, manager: &EnvManager) {
+        self.reporter.report_manager(manager);
+    }
+    fn report_environment(&self, env: &PythonEnvironment) {
+        match set_project(env, self.declarations) {
+            Some(env) => self.reporter.report_environment(&env),
+            None => self.reporter.report_environment(env),
+        }
+    }
+    fn report_telemetry(&self, event: &TelemetryEvent) {
+        self.reporter.report_telemetry(event);
+    }
+}
+
+/// Gets a copy of the environment with the project set, if the environment is declared in a workspace folder.
+pub fn set_project(
+    env: &PythonEnvironment,
+    declarations: &[CondaEnvironmentFile],
+) -> Option<PythonEnvironment> {
+    if env.project.is_some() {
+        return None;
+    }
+    let declaration = declarations.iter().find(|d| d.matches(env))?;
+    let mut env = env.clone();
+    env.project = Some(norm_case(&declaration.workspace));
+    Some(env)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_name_and_prefix() {
+        let contents = r#"
+name: ml
+prefix: ~/envs/ml
+channels:
+  - conda-forge
+dependencies:
+  - python=3.12
+  - numpy
+"#;
+        assert_eq!(
+            parse_environment_file(contents),
+            (Some("ml".to_string()), Some(PathBuf::from("~/envs/ml")))
+        );
+        assert_eq!(
+            parse_environment_file("dependencies:\n  - python\n"),
+            (None, None)
+        );
+        assert_eq!(parse_environment_file("name: [ml"), (None, None));
+    }
+
+    #[test]
+    fn create_commands() {
+        let conda = PathBuf::from("/conda/bin/conda");
+        let workspace = PathBuf::from("/project");
+        let named = CondaEnvironmentFile {
+            workspace: workspace.clone(),
+            file: workspace.join("environment.yml"),
+            name: Some("ml".to_string()),
+            prefix: None,
+        };
+        assert_eq!(
+            named.get_create_command(&conda),
+            Some(vec![
+                "/conda/bin/conda".to_string(),
+                "env".to_string(),
+                "create".to_string(),
+                "--file".to_string(),
+                "/project/environment.yml".to_string(),
+            ])
+        );
+
+        let lock = CondaEnvironmentFile {
+            workspace: workspace.clone(),
+            file: workspace.join(CONDA_LOCK_FILE),
+            name: None,
+            prefix: Some(workspace.join(WORKSPACE_ENV_DIR)),
+        };
+        assert_eq!(
+            lock.get_create_command(&conda),
+            Some(vec![
+                "conda-lock".to_string(),
+                "install".to_string(),
+                "--prefix".to_string(),
+                "/project/.conda".to_string(),
+                "/project/conda-lock.yml".to_string(),
+            ])
+        );
+
+        // Neither the name nor the prefix is declared.
+        let undeclared = CondaEnvironmentFile {
+            workspace: workspace.clone(),
+            file: workspace.join("environment.yml"),
+            name: None,
+            prefix: None,
+        };
+        assert_eq!(undeclared.get_create_command(&conda), None);
+
+        let env_dir = CondaEnvironmentFile {
+            workspace: workspace.clone(),
+            file: workspace.join(WORKSPACE_ENV_DIR),
+            name: None,
+            prefix: Some(workspace.join(WORKSPACE_ENV_DIR)),
+        };
+        assert_eq!(env_dir.get_create_command(&conda), None);
+    }
+}
@@ -4,6 +4,7 @@
 use crate::{
     conda_rc::{get_conda_rc_search_paths, Condarc},
     env_variables::EnvVariables,
+    mamba::get_mamba_root_prefixes,
     manager::{find_conda_binary, find_mamba_binary},
     utils::{is_conda_env, is_conda_install},
 };
@@ -308,6 +309,7 @@ pub fn get_known_conda_install_locations(
     if let Some(ref conda) = env_vars.conda {
         known_paths.push(expand_path(PathBuf::from(conda)));
     }
+    known_paths.append(&mut get_mamba_root_prefixes(env_vars));
     let app_data = PathBuf::from(env::var("LOCALAPPDATA").unwrap_or_default());
     if let Some(home) = env_vars.clone().home {
         for prefix in [
@@ -411,9 +413,7 @@ pub fn get_known_conda_install_locations(
     if let Some(ref conda_prefix) = env_vars.conda_prefix {
         known_paths.push(expand_path(PathBuf::from(conda_prefix.clone())));
     }
-    if let Some(ref mamba_root_prefix) = env_vars.mamba_root_prefix {
-        known_paths.push(expand_path(PathBuf::from(mamba_root_prefix.clone())));
-    }
+    known_paths.append(&mut get_mamba_root_prefixes(env_vars));
     if let Some(ref conda_dir) = env_vars.conda_dir {
         known_paths.push(expand_path(PathBuf::from(conda_dir.clone())));
     }
@@ -3,7 +3,7 @@
 
 use crate::{
     manager::CondaManager,
-    package::{CondaPackageInfo, Package},
+    package::{get_requested_packages, CondaPackageInfo, Package},
     utils::{is_conda_env, is_conda_install},
 };
 use log::{trace, warn};
@@ -34,8 +34,28 @@ impl CondaEnvironment {
         #[allow(unused_assignments)]
         let name = get_conda_env_name(&self.prefix, &self.prefix, &self.conda_dir);
 
+        // Let users know what these environments are & how to make them usable.
+        let (packages, install_python_command) = if self.executable.is_none() {
+            (
+                Some(get_requested_packages(&self.prefix)),
+                conda_manager.as_ref().map(|manager| {
+                    vec![
+                        manager.executable.to_string_lossy().to_string(),
+                        "install".to_string(),
+                        "--prefix".to_string(),
+                        self.prefix.to_string_lossy().to_string(),
+                        "python".to_string(),
+                    ]
+                }),
+            )
+        } else {
+            (None, None)
+        };
+
         // This is a root env.
         let builder = PythonEnvironmentBuilder::new(Some(PythonEnvironmentKind::Conda))
+            .packages(packages)
+            .install_python_command(install_python_command)
             .executable(self.executable.clone())
             .version(self.version.clone())
             .prefix(Some(self.prefix.clone()))
@@ -157,6 +177,12 @@ pub fn get_conda_installation_used_to_create_conda_env(env_path: &Path) -> Optio
     }
 }
 
+/// Returns the conda installation referenced by the command used to create the conda environment,
+/// unlike `get_conda_installation_used_to_create_conda_env` the installation may no longer exist.
+pub fn get_conda_dir_from_history(env_path: &Path) -> Option<PathBuf> {
+    get_conda_dir_from_cmd(get_conda_creation_line_from_history(env_path)?)
+}
+
 pub fn get_conda_creation_line_from_history(env_path: &Path) -> Option<String> {
     let conda_meta_history = env_path.join("conda-meta").join("history");
     if let Ok(reader) = std::fs::read_to_string(conda_meta_history.clone()) {
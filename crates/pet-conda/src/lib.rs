@@ -2,19 +2,25 @@
 // Licensed under the MIT License.
 
 use conda_info::CondaInfo;
+use conda_rc::CondarcEnvDir;
+use details::CondaEnvironmentDetails;
 use env_variables::EnvVariables;
+use environment_file::{set_project, CondaEnvironmentFile, CondaEnvironmentFileReporter};
 use environment_locations::{
     get_conda_dir_from_exe, get_conda_environment_paths, get_conda_envs_from_environment_txt,
     get_environments,
 };
 use environments::{get_conda_environment_info, CondaEnvironment};
 use log::error;
+use mamba::MambaInstalls;
 use manager::{get_mamba_manager, is_mamba_executable, CondaManager};
 use pet_core::{
     cache::LocatorCache,
     env::PythonEnv,
     os_environment::Environment,
-    python_environment::{PythonEnvironment, PythonEnvironmentKind},
+    python_environment::{
+        PythonEnvironment, PythonEnvironmentBuilder, PythonEnvironmentKind, PythonEnvironmentState,
+    },
     reporter::Reporter,
     Locator, LocatorKind,
 };
@@ -23,17 +29,19 @@ use rayon::prelude::*;
 use serde::{Deserialize, Serialize};
 use std::{
     path::{Path, PathBuf},
-    sync::{Arc, RwLock},
-    thread,
+    sync::{Arc, Mutex, RwLock},
 };
 use telemetry::{get_conda_rcs_and_env_dirs, report_missing_envs};
 use utils::{is_conda_env, is_conda_install};
 
 mod conda_info;
 pub mod conda_rc;
+pub mod details;
 pub mod env_variables;
+pub mod environment_file;
 pub mod environment_locations;
 pub mod environments;
+pub mod mamba;
 pub mod manager;
 pub mod package;
 mod telemetry;
@@ -47,6 +55,8 @@ pub trait CondaLocator: Send + Sync {
         conda_executable: Option<PathBuf>,
     ) -> Option<()>;
     fn get_info_for_telemetry(&self, conda_executable: Option<PathBuf>) -> CondaTelemetryInfo;
+    /// Details of the conda environment, these are expensive to compute & hence only computed on request.
+    fn get_environment_details(&self, prefix: &Path) -> Option<CondaEnvironmentDetails>;
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -55,6 +65,8 @@ pub struct CondaTelemetryInfo {
     pub can_spawn_conda: bool,
     pub conda_rcs: Vec<PathBuf>,
     pub env_dirs: Vec<PathBuf>,
+    /// The conda rc file that contributed each of the env dirs.
+    pub env_dir_sources: Vec<CondarcEnvDir>,
     pub environments_txt: Option<PathBuf>,
     pub environments_txt_exists: Option<bool>,
     pub user_provided_env_found: Option<bool>,
@@ -65,8 +77,11 @@ pub struct Conda {
     pub environments: Arc<LocatorCache<PathBuf, PythonEnvironment>>,
     pub managers: Arc<LocatorCache<PathBuf, CondaManager>>,
     pub mamba_managers: Arc<LocatorCache<PathBuf, CondaManager>>,
+    /// Standalone mamba/micromamba installs, computed once per search.
+    mamba_installs: Arc<Mutex<Option<Arc<MambaInstalls>>>>,
     pub env_vars: EnvVariables,
     conda_executable: Arc<RwLock<Option<PathBuf>>>,
+    workspace_directories: Arc<RwLock<Vec<PathBuf>>>,
 }
 
 impl Conda {
@@ -75,14 +90,17 @@ impl Conda {
             environments: Arc::new(LocatorCache::new()),
             managers: Arc::new(LocatorCache::new()),
             mamba_managers: Arc::new(LocatorCache::new()),
+            mamba_installs: Arc::new(Mutex::new(None)),
             env_vars: EnvVariables::from(env),
             conda_executable: Arc::new(RwLock::new(None)),
+            workspace_directories: Arc::new(RwLock::new(vec![])),
         }
     }
     fn clear(&self) {
         self.environments.clear();
         self.managers.clear();
         self.mamba_managers.clear();
+        self.mamba_installs.lock().unwrap().take();
     }
 }
 
@@ -126,10 +144,15 @@ impl CondaLocator for Conda {
         Some(())
     }
 
+    fn get_environment_details(&self, prefix: &Path) -> Option<CondaEnvironmentDetails> {
+        CondaEnvironmentDetails::from(prefix)
+    }
+
     fn get_info_for_telemetry(&self, conda_executable: Option<PathBuf>) -> CondaTelemetryInfo {
         let can_spawn_conda = CondaInfo::from(conda_executable).is_some();
         let environments = self.environments.values();
-        let (conda_rcs, env_dirs) = get_conda_rcs_and_env_dirs(&self.env_vars, &environments);
+        let (conda_rcs, env_dirs, env_dir_sources) =
+            get_conda_rcs_and_env_dirs(&self.env_vars, &environments);
         let mut environments_txt = None;
         let mut environments_txt_exists = None;
         if let Some(ref home) = self.env_vars.home {
@@ -150,6 +173,7 @@ impl CondaLocator for Conda {
             can_spawn_conda,
             conda_rcs,
             env_dirs,
+            env_dir_sources,
             user_provided_env_found,
             environments_txt,
             environments_txt_exists,
@@ -210,6 +234,117 @@ impl Conda {
         self.managers
             .get_or_insert_with(conda_dir.to_path_buf(), || CondaManager::from(conda_dir))
     }
+
+    fn get_mamba_installs(&self) -> Arc<MambaInstalls> {
+        self.mamba_installs
+            .lock()
+            .unwrap()
+            .get_or_insert_with(|| Arc::new(MambaInstalls::from(&self.env_vars)))
+            .clone()
+    }
+
+    /// Manager of a standalone mamba/micromamba root prefix, the manager is reported the first time it is found.
+    fn get_mamba_root_prefix_manager(
+        &self,
+        root_prefix: &Path,
+        reporter: Option<&dyn Reporter>,
+    ) -> Option<CondaManager> {
+        self.mamba_managers
+            .get_or_insert_with(root_prefix.to_path_buf(), || {
+                let mgr = self
+                    .get_mamba_installs()
+                    .get_root_prefix_manager(root_prefix, &self.env_vars);
+                if let (Some(m), Some(reporter)) = (&mgr, reporter) {
+                    reporter.report_manager(&m.to_manager());
+                }
+                mgr
+            })
+    }
+
+    /// Conda environments declared in the workspace folders.
+    fn get_conda_environment_files(&self) -> Vec<CondaEnvironmentFile> {
+        self.workspace_directories
+            .read()
+            .unwrap()
+            .iter()
+            .filter_map(|workspace| CondaEnvironmentFile::from(workspace))
+            .collect()
+    }
+
+    /// Manager used to create the environments declared in the workspace folders,
+    /// the conda executable provided by the user takes precedence.
+    fn get_manager_for_conda_environment_files(&self) -> Option<CondaManager> {
+        let conda_exe = self.conda_executable.read().unwrap().clone();
+        if let Some(manager) =
+            get_conda_dir_from_exe(&conda_exe).and_then(|conda_dir| self.get_manager(&conda_dir))
+        {
+            return Some(manager);
+        }
+        let mut managers = self.managers.values();
+        managers.extend(self.mamba_managers.values());
+        managers.sort_by(|a, b| a.executable.cmp(&b.executable));
+        managers.into_iter().next()
+    }
+
+    /// Reports the environments declared in the workspace folders that have not been created,
+    /// so that clients can offer to create them.
+    fn report_conda_environment_files_not_created(
+        &self,
+        declarations: &[CondaEnvironmentFile],
+        reporter: &dyn Reporter,
+    ) {
+        let environments = self.environments.values();
+        let manager = self.get_manager_for_conda_environment_files();
+        let conda_exe = manager
+            .as_ref()
+            .map(|manager| manager.executable.clone())
+            .unwrap_or_else(|| PathBuf::from("conda"));
+        for declaration in declarations {
+            if environments.iter().any(|env| declaration.matches(env)) {
+                continue;
+            }
+            let create_command = match declaration.get_create_command(&conda_exe) {
+                Some(command) => command,
+                None => continue,
+            };
+            // Named environments are created in the `envs` directory of the conda install.
+            let prefix = declaration.prefix.clone().or_else(|| {
+                let name = declaration.name.clone()?;
+                manager
+                    .as_ref()
+                    .and_then(|manager| manager.conda_dir.clone())
+                    .or_else(|| self.env_vars.home.as_ref().map(|home| home.join(".conda")))
+                    .map(|conda_dir| conda_dir.join("envs").join(name))
+            });
+            // Environments in the workspace folder are discovered when searching the workspace folder.
+            if prefix.as_ref().is_some_and(|prefix| is_conda_env(prefix)) {
+                continue;
+            }
+            let env = PythonEnvironmentBuilder::new(Some(PythonEnvironmentKind::Conda))
+                .name(declaration.name.clone())
+                .prefix(prefix)
+                .project(Some(declaration.workspace.clone()))
+                .manager(manager.as_ref().map(|manager| manager.to_manager()))
+                .state(Some(PythonEnvironmentState::NotCreated))
+                .create_command(Some(create_command))
+                .build();
+            reporter.report_environment(&env);
+        }
+    }
+
+    /// Environments without a conda install could have been created by a standalone mamba/micromamba.
+    fn get_conda_env_with_mamba_root_prefix_manager(
+        &self,
+        mut env: CondaEnvironment,
+        reporter: Option<&dyn Reporter>,
+    ) -> Option<(CondaEnvironment, CondaManager)> {
+        let root_prefix = self
+            .get_mamba_installs()
+            .get_root_prefix_of_env(&env.prefix)?;
+        let manager = self.get_mamba_root_prefix_manager(&root_prefix, reporter)?;
+        env.conda_dir = Some(root_prefix);
+        Some((env, manager))
+    }
 }
 
 impl Locator for Conda {
@@ -221,6 +356,13 @@ impl Locator for Conda {
             let mut conda_executable = self.conda_executable.write().unwrap();
             conda_executable.replace(conda_exe.clone());
         }
+        if let Some(workspace_directories) = &config.workspace_directories {
+            let mut ws_dirs = self.workspace_directories.write().unwrap();
+            ws_dirs.clear();
+            if !workspace_directories.is_empty() {
+                ws_dirs.extend(workspace_directories.clone());
+            }
+        }
     }
     fn supported_categories(&self) -> Vec<PythonEnvironmentKind> {
         vec![PythonEnvironmentKind::Conda]
@@ -249,131 +391,166 @@ impl Locator for Conda {
                 return None;
             }
 
-            // Check cache first
-            if let Some(cached_env) = self.environments.get(path) {
-                return Some(cached_env);
-            }
+            let declarations = self.get_conda_environment_files();
+            let env = self.try_from_prefix(path)?;
+            return Some(set_project(&env, &declarations).unwrap_or(env));
+        }
+        None
+    }
+
+    fn find(&self, reporter: &dyn Reporter) {
+        // if we're calling this again, then clear what ever cache we have.
+        self.clear();
+
+        // Environments declared in the workspace folders are associated with the workspace folder.
+        let declarations = self.get_conda_environment_files();
+        let reporter = &CondaEnvironmentFileReporter::new(reporter, &declarations);
 
-            // Not in cache, build the environment and insert
-            if let Some(env) = get_conda_environment_info(path, &None) {
-                if let Some(conda_dir) = &env.conda_dir {
-                    if let Some(manager) = self.get_manager(conda_dir) {
-                        let env = env.to_python_environment(Some(manager.to_manager()));
-                        self.environments.insert(path.clone(), env.clone());
-                        return Some(env);
-                    } else {
-                        // We will still return the conda env even though we do not have the manager.
-                        // This might seem incorrect, however the tool is about discovering environments.
-                        // The client can activate this env either using another conda manager or using the activation scripts
-                        error!("Unable to find Conda Manager for env (even though we have a conda_dir): {:?}", env);
-                        let env = env.to_python_environment(None);
-                        self.environments.insert(path.clone(), env.clone());
-                        return Some(env);
+        let env_vars = self.env_vars.clone();
+        let executable = self.conda_executable.read().unwrap().clone();
+        // If the user-provided conda_executable is actually a mamba/micromamba binary
+        // (backwards compatibility), report it as a mamba manager and discover its envs.
+        if let Some(ref exe) = executable {
+            if is_mamba_executable(exe) {
+                if let Some(mamba_dir) = get_conda_dir_from_exe(&executable) {
+                    if let Some(mamba_mgr) = get_mamba_manager(&mamba_dir) {
+                        self.mamba_managers.insert(mamba_dir, mamba_mgr.clone());
+                        reporter.report_manager(&mamba_mgr.to_manager());
                     }
+                }
+            }
+        }
+        // Standalone mamba/micromamba installs, these need not be next to a conda install.
+        for root_prefix in self.get_mamba_installs().root_prefixes.iter() {
+            self.get_mamba_root_prefix_manager(root_prefix, Some(reporter));
+        }
+        // 1. Get a list of all know conda environments file paths
+        let possible_conda_envs = get_conda_environment_paths(&env_vars, &executable);
+        possible_conda_envs.par_iter().for_each(|path| {
+            self.find_and_report_conda_env(path, reporter);
+        });
+
+        // Environments declared in the workspace folders that are yet to be created.
+        self.report_conda_environment_files_not_created(&declarations, reporter);
+    }
+}
+
+impl Conda {
+    /// Gets the conda environment of the prefix, the environment is cached.
+    fn try_from_prefix(&self, path: &Path) -> Option<PythonEnvironment> {
+        // Check cache first
+        if let Some(cached_env) = self.environments.get(&path.to_path_buf()) {
+            return Some(cached_env);
+        }
+
+        // Not in cache, build the environment and insert
+        if let Some(env) = get_conda_environment_info(path, &None) {
+            if let Some(conda_dir) = &env.conda_dir {
+                if let Some(manager) = self.get_manager(conda_dir) {
+                    let env = env.to_python_environment(Some(manager.to_manager()));
+                    self.environments.insert(path.to_path_buf(), env.clone());
+                    return Some(env);
                 } else {
                     // We will still return the conda env even though we do not have the manager.
                     // This might seem incorrect, however the tool is about discovering environments.
                     // The client can activate this env either using another conda manager or using the activation scripts
-                    error!("Unable to find Conda Manager for env: {:?}", env);
+                    error!("Unable to find Conda Manager for env (even though we have a conda_dir): {:?}", env);
                     let env = env.to_python_environment(None);
-                    self.environments.insert(path.clone(), env.clone());
+                    self.environments.insert(path.to_path_buf(), env.clone());
                     return Some(env);
                 }
+            } else if let Some((env, manager)) =
+                self.get_conda_env_with_mamba_root_prefix_manager(env.clone(), None)
+            {
+                let env = env.to_python_environment(Some(manager.to_manager()));
+                self.environments.insert(path.to_path_buf(), env.clone());
+                return Some(env);
+            } else {
+                // We will still return the conda env even though we do not have the manager.
+                // This might seem incorrect, however the tool is about discovering environments.
+                // The client can activate this env either using another conda manager or using the activation scripts
+                error!("Unable to find Conda Manager for env: {:?}", env);
+                let env = env.to_python_environment(None);
+                self.environments.insert(path.to_path_buf(), env.clone());
+                return Some(env);
             }
         }
         None
     }
 
-    fn find(&self, reporter: &dyn Reporter) {
-        // if we're calling this again, then clear what ever cache we have.
-        self.clear();
+    fn find_and_report_conda_env(&self, path: &Path, reporter: &dyn Reporter) -> Option<()> {
+        // 2. Get the details of the conda environment
+        // This we do not get any details, then its not a conda environment
+        let env = get_conda_environment_info(path, &None)?;
 
-        let env_vars = self.env_vars.clone();
-        let executable = self.conda_executable.read().unwrap().clone();
-        thread::scope(|s| {
-            // If the user-provided conda_executable is actually a mamba/micromamba binary
-            // (backwards compatibility), report it as a mamba manager and discover its envs.
-            if let Some(ref exe) = executable {
-                if is_mamba_executable(exe) {
-                    if let Some(mamba_dir) = get_conda_dir_from_exe(&executable) {
-                        if let Some(mamba_mgr) = get_mamba_manager(&mamba_dir) {
-                            self.mamba_managers.insert(mamba_dir, mamba_mgr.clone());
-                            reporter.report_manager(&mamba_mgr.to_manager());
-                        }
-                    }
-                }
+        // 3. If we have a conda environment without a conda_dir
+        // Then we will not be able to get the manager.
+        // Either way report this environment
+        if env.conda_dir.is_none() {
+            if let Some((env, manager)) =
+                self.get_conda_env_with_mamba_root_prefix_manager(env.clone(), Some(reporter))
+            {
+                let env = env.to_python_environment(Some(manager.to_manager()));
+                self.environments.insert(path.to_path_buf(), env.clone());
+                reporter.report_environment(&env);
+                return Some(());
             }
-            // 1. Get a list of all know conda environments file paths
-            let possible_conda_envs = get_conda_environment_paths(&env_vars, &executable);
-            for path in possible_conda_envs {
-                s.spawn(move || {
-                    // 2. Get the details of the conda environment
-                    // This we do not get any details, then its not a conda environment
-                    let env = get_conda_environment_info(&path, &None)?;
-
-                    // 3. If we have a conda environment without a conda_dir
-                    // Then we will not be able to get the manager.
-                    // Either way report this environment
-                    if env.conda_dir.is_none(){
-                        // We will still return the conda env even though we do not have the manager.
-                        // This might seem incorrect, however the tool is about discovering environments.
-                        // The client can activate this env either using another conda manager or using the activation scripts
-                        error!("Unable to find Conda Manager for the Conda env: {:?}", env);
-                        let prefix = env.prefix.clone();
-                        let env = env.to_python_environment(None);
-                        self.environments.insert(prefix, env.clone());
-                        reporter.report_environment(&env);
-                        return None;
-                    }
+            // We will still return the conda env even though we do not have the manager.
+            // This might seem incorrect, however the tool is about discovering environments.
+            // The client can activate this env either using another conda manager or using the activation scripts
+            error!("Unable to find Conda Manager for the Conda env: {:?}", env);
+            let prefix = env.prefix.clone();
+            let env = env.to_python_environment(None);
+            self.environments.insert(prefix, env.clone());
+            reporter.report_environment(&env);
+            return None;
+        }
 
-                    // 3. We have a conda environment with a conda_dir (above we handled the case when its not found)
-                    // We will try to get the manager for this conda_dir
-                    let prefix = env.clone().prefix.clone();
+        // 3. We have a conda environment with a conda_dir (above we handled the case when its not found)
+        // We will try to get the manager for this conda_dir
+        let prefix = env.clone().prefix.clone();
 
-                    // 3.1 Check if we have already reported this environment.
-                    if self.environments.contains_key(&env.prefix) {
-                        return None;
-                    }
+        // 3.1 Check if we have already reported this environment.
+        if self.environments.contains_key(&env.prefix) {
+            return None;
+        }
 
-                    // 4 Get the manager for this env.
-                    let conda_dir = &env.conda_dir.clone()?;
-                    let manager = self.managers.get_or_insert_with(conda_dir.clone(), || {
-                        CondaManager::from(conda_dir)
-                    });
+        // 4 Get the manager for this env.
+        let conda_dir = &env.conda_dir.clone()?;
+        let manager = self
+            .managers
+            .get_or_insert_with(conda_dir.clone(), || CondaManager::from(conda_dir));
+
+        // 5. Report this env.
+        if let Some(manager) = manager {
+            let env = env.to_python_environment(Some(manager.to_manager()));
+            self.environments.insert(prefix.clone(), env.clone());
+            reporter.report_manager(&manager.to_manager());
+            reporter.report_environment(&env);
 
-                    // 5. Report this env.
-                    if let Some(manager) = manager {
-                        let env = env.to_python_environment(
-                            Some(manager.to_manager()),
-                        );
-                        self.environments.insert(prefix.clone(), env.clone());
-                        reporter.report_manager(&manager.to_manager());
-                        reporter.report_environment(&env);
-
-                        // Also check for a mamba/micromamba manager in the same directory and report it.
-                        // Reporting inside the closure minimizes the TOCTOU window compared to a
-                        // separate contains_key check, though concurrent threads may still
-                        // briefly both invoke the closure before the write-lock double-check.
-                        let _ = self.mamba_managers.get_or_insert_with(conda_dir.clone(), || {
-                            let mgr = get_mamba_manager(conda_dir);
-                            if let Some(ref m) = mgr {
-                                reporter.report_manager(&m.to_manager());
-                            }
-                            mgr
-                        });
-                    } else {
-                        // We will still return the conda env even though we do not have the manager.
-                        // This might seem incorrect, however the tool is about discovering environments.
-                        // The client can activate this env either using another conda manager or using the activation scripts
-                        error!("Unable to find Conda Manager for Conda env (even though we have a conda_dir {:?}): Env Details = {:?}", conda_dir, env);
-                        let env = env.to_python_environment(None);
-                        self.environments.insert(prefix.clone(), env.clone());
-                        reporter.report_environment(&env);
+            // Also check for a mamba/micromamba manager in the same directory and report it.
+            // Reporting inside the closure minimizes the TOCTOU window compared to a
+            // separate contains_key check, though concurrent threads may still
+            // briefly both invoke the closure before the write-lock double-check.
+            let _ = self
+                .mamba_managers
+                .get_or_insert_with(conda_dir.clone(), || {
+                    let mgr = get_mamba_manager(conda_dir);
+                    if let Some(ref m) = mgr {
+                        reporter.report_manager(&m.to_manager());
                     }
-                    Option::<()>::Some(())
+                    mgr
                 });
-            }
-        });
+        } else {
+            // We will still return the conda env even though we do not have the manager.
+            // This might seem incorrect, however the tool is about discovering environments.
+            // The client can activate this env either using another conda manager or using the activation scripts
+            error!("Unable to find Conda Manager for Conda env (even though we have a conda_dir {:?}): Env Details = {:?}", conda_dir, env);
+            let env = env.to_python_environment(None);
+            self.environments.insert(prefix.clone(), env.clone());
+            reporter.report_environment(&env);
+        }
+        Some(())
     }
 }
 
@@ -0,0 +1,285 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT License.
+
+//! Standalone mamba & micromamba installs, these need not be next to a conda install.
+//! Environments are created in the `envs` directory of the root prefix,
+//! & the root prefix itself is not necessarily a conda environment (no `conda-meta` directory).
+
+use crate::{
+    env_variables::EnvVariables,
+    manager::{find_mamba_binary, get_mamba_executable, get_mamba_manager_type, CondaManager},
+};
+use lazy_static::lazy_static;
+use log::trace;
+use pet_fs::path::{expand_path, norm_case};
+use regex::Regex;
+use std::{
+    collections::HashSet,
+    fs,
+    path::{Path, PathBuf},
+};
+use yaml_rust2::YamlLoader;
+
+lazy_static! {
+    // Sample => export MAMBA_ROOT_PREFIX='/home/user/micromamba';
+    // Sample => set -gx MAMBA_EXE "/home/user/.local/bin/micromamba"
+    // Sample => $Env:MAMBA_ROOT_PREFIX = "C:\Users\user\micromamba"
+    static ref MAMBA_SHELL_INIT_VARIABLE: Regex = Regex::new(
+        r#"^\s*(?:export\s+|set\s+-gx\s+|\$Env:)(MAMBA_EXE|MAMBA_ROOT_PREFIX)\s*=?\s*["']?([^"';]+)["']?"#
+    )
+    .expect("error parsing regex for variables in mamba shell init blocks");
+}
+
+const SHELL_INIT_BLOCK_START: &str = "# >>> mamba initialize >>>";
+const SHELL_INIT_BLOCK_END: &str = "# <<< mamba initialize <<<";
+
+/// Variables set in the block added to shell startup files by `micromamba shell init`.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct MambaShellInit {
+    pub mamba_exe: Option<PathBuf>,
+    pub root_prefix: Option<PathBuf>,
+}
+
+impl MambaShellInit {
+    pub fn from(env_vars: &EnvVariables) -> Self {
+        let mut shell_init = MambaShellInit::default();
+        for file in get_shell_startup_files(env_vars) {
+            if let Ok(contents) = fs::read_to_string(&file) {
+                let init = parse_mamba_shell_init(&contents);
+                if init != MambaShellInit::default() {
+                    trace!("Mamba shell init in {:?} is {:?}", file, init);
+                }
+                shell_init.mamba_exe = shell_init.mamba_exe.or(init.mamba_exe);
+                shell_init.root_prefix = shell_init.root_prefix.or(init.root_prefix);
+            }
+        }
+        shell_init
+    }
+}
+
+fn get_shell_startup_files(env_vars: &EnvVariables) -> Vec<PathBuf> {
+    let home = match &env_vars.home {
+        Some(home) => home,
+        None => return vec![],
+    };
+    vec![
+        home.join(".bashrc"),
+        home.join(".bash_profile"),
+        home.join(".zshrc"),
+        home.join(".profile"),
+        home.join(".xonshrc"),
+        home.join(".config").join("fish").join("config.fish"),
+        home.join("Documents")
+            .join("PowerShell")
+            .join("Microsoft.PowerShell_profile.ps1"),
+        home.join("Documents")
+            .join("WindowsPowerShell")
+            .join("Microsoft.PowerShell_profile.ps1"),
+    ]
+}
+
+fn parse_mamba_shell_init(contents: &str) -> MambaShellInit {
+    let mut shell_init = MambaShellInit::default();
+    let mut in_block = false;
+    for line in contents.lines() {
+        let line = line.trim();
+        if line.starts_with(SHELL_INIT_BLOCK_START) {
+            in_block = true;
+        } else if line.starts_with(SHELL_INIT_BLOCK_END) {
+            in_block = false;
+        } else if in_block {
+            if let Some(captures) = MAMBA_SHELL_INIT_VARIABLE.captures(line) {
+                let value = Some(expand_path(PathBuf::from(captures[2].trim())));
+                match &captures[1] {
+                    "MAMBA_EXE" => shell_init.mamba_exe = shell_init.mamba_exe.or(value),
+                    _ => shell_init.root_prefix = shell_init.root_prefix.or(value),
+                }
+            }
+        }
+    }
+    shell_init
+}
+
+/// Gets the `root_prefix` configured in the mamba rc files.
+/// https://mamba.readthedocs.io/en/latest/user_guide/configuration.html
+fn get_root_prefix_from_mambarc(env_vars: &EnvVariables) -> Option<PathBuf> {
+    let mut mambarc_files = vec![];
+    if let Some(ref mambarc) = env_vars.mambarc {
+        mambarc_files.push(expand_path(PathBuf::from(mambarc)));
+    }
+    if let Some(ref home) = env_vars.home {
+        mambarc_files.push(home.join(".mambarc"));
+        mambarc_files.push(home.join(".config").join("mamba").join(".mambarc"));
+        mambarc_files.push(home.join(".config").join("mamba").join("mambarc"));
+    }
+    if let Some(ref xdg_config_home) = env_vars.xdg_config_home {
+        mambarc_files.push(PathBuf::from(xdg_config_home).join("mamba").join("mambarc"));
+    }
+    mambarc_files.iter().find_map(|file| {
+        let contents = fs::read_to_string(file).ok()?;
+        let docs = YamlLoader::load_from_str(&contents).ok()?;
+        let root_prefix = docs.first()?["root_prefix"].as_str()?.trim().to_string();
+        trace!("Mamba root prefix {:?} in {:?}", root_prefix, file);
+        Some(expand_path(PathBuf::from(root_prefix)))
+    })
+}
+
+/// Root prefixes of mamba/micromamba, in order of precedence.
+/// Only the root prefixes that exist are returned.
+pub fn get_mamba_root_prefixes(env_vars: &EnvVariables) -> Vec<PathBuf> {
+    get_root_prefixes(env_vars, &MambaShellInit::from(env_vars))
+}
+
+fn get_root_prefixes(env_vars: &EnvVariables, shell_init: &MambaShellInit) -> Vec<PathBuf> {
+    let mut root_prefixes = vec![];
+    if let Some(ref mamba_root_prefix) = env_vars.mamba_root_prefix {
+        root_prefixes.push(expand_path(PathBuf::from(mamba_root_prefix)));
+    }
+    if let Some(ref root_prefix) = shell_init.root_prefix {
+        root_prefixes.push(root_prefix.clone());
+    }
+    if let Some(root_prefix) = get_root_prefix_from_mambarc(env_vars) {
+        root_prefixes.push(root_prefix);
+    }
+    if let Some(ref home) = env_vars.home {
+        // Default root prefix of micromamba 1.x & 2.x respectively.
+        root_prefixes.push(home.join("micromamba"));
+        root_prefixes.push(home.join(".local").join("share").join("mamba"));
+    }
+
+    let mut unique = HashSet::new();
+    root_prefixes
+        .into_iter()
+        .map(norm_case)
+        .filter(|root_prefix| root_prefix.is_dir())
+        .filter(|root_prefix| unique.insert(root_prefix.clone()))
+        .collect()
+}
+
+/// Standalone mamba/micromamba installs, as configured in the shell startup files, mamba rc files & environment variables.
+/// Reading these files is not cheap, hence this is computed once & used for all environments.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct MambaInstalls {
+    pub shell_init: MambaShellInit,
+    /// Root prefixes that exist, in order of precedence.
+    pub root_prefixes: Vec<PathBuf>,
+}
+
+impl MambaInstalls {
+    pub fn from(env_vars: &EnvVariables) -> Self {
+        let shell_init = MambaShellInit::from(env_vars);
+        let root_prefixes = get_root_prefixes(env_vars, &shell_init);
+        MambaInstalls {
+            shell_init,
+            root_prefixes,
+        }
+    }
+
+    /// Gets the root prefix of a standalone mamba/micromamba install the environment belongs to,
+    /// i.e. the environment is the root prefix or is in the `envs` directory of the root prefix.
+    pub fn get_root_prefix_of_env(&self, env_path: &Path) -> Option<PathBuf> {
+        let env_path = norm_case(env_path);
+        self.root_prefixes
+            .iter()
+            .find(|root_prefix| {
+                env_path == **root_prefix || env_path.parent() == Some(&root_prefix.join("envs"))
+            })
+            .cloned()
+    }
+
+    /// Gets the mamba/micromamba manager of a standalone root prefix.
+    /// A single micromamba executable can manage any number of root prefixes,
+    /// hence the executable need not be in the root prefix.
+    pub fn get_root_prefix_manager(
+        &self,
+        root_prefix: &Path,
+        env_vars: &EnvVariables,
+    ) -> Option<CondaManager> {
+        let mut executables = vec![];
+        if let Some(ref mamba_exe) = env_vars.mamba_exe {
+            executables.push(expand_path(PathBuf::from(mamba_exe)));
+        }
+        if let Some(ref mamba_exe) = self.shell_init.mamba_exe {
+            executables.push(mamba_exe.clone());
+        }
+        if let Some(mamba_exe) = get_mamba_executable(root_prefix) {
+            executables.push(mamba_exe);
+        }
+        if let Some(mamba_exe) = find_mamba_binary(env_vars) {
+            executables.push(mamba_exe);
+        }
+        if let Some(ref home) = env_vars.home {
+            // Default install location of micromamba.
+            executables.push(home.join(".local").join("bin").join("micromamba"));
+            executables.push(home.join("bin").join("micromamba"));
+        }
+        let executable = executables.into_iter().find(|exe| exe.is_file())?;
+        trace!(
+            "Mamba manager for root prefix {:?} is {:?}",
+            root_prefix,
+            executable
+        );
+        Some(CondaManager {
+            manager_type: get_mamba_manager_type(&executable),
+            executable,
+            version: None,
+            conda_dir: Some(root_prefix.to_path_buf()),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_posix_shell_init() {
+        let contents = r#"
+export PATH="$HOME/bin:$PATH"
+export MAMBA_ROOT_PREFIX='/not/in/the/block';
+
+# >>> mamba initialize >>>
+# !! Contents within this block are managed by 'mamba init' !!
+export MAMBA_EXE='/home/user/.local/bin/micromamba';
+export MAMBA_ROOT_PREFIX='/home/user/micromamba';
+__mamba_setup="$("$MAMBA_EXE" shell hook --shell bash --root-prefix "$MAMBA_ROOT_PREFIX" 2> /dev/null)"
+unset __mamba_setup
+# <<< mamba initialize <<<
+"#;
+
+        assert_eq!(
+            parse_mamba_shell_init(contents),
+            MambaShellInit {
+                mamba_exe: Some(PathBuf::from("/home/user/.local/bin/micromamba")),
+                root_prefix: Some(PathBuf::from("/home/user/micromamba")),
+            }
+        );
+    }
+
+    #[test]
+    fn parse_fish_and_powershell_shell_init() {
+        let fish = r#"
+# >>> mamba initialize >>>
+set -gx MAMBA_EXE "/opt/micromamba/bin/micromamba"
+set -gx MAMBA_ROOT_PREFIX "/data/mamba"
+$MAMBA_EXE shell hook --shell fish --root-prefix $MAMBA_ROOT_PREFIX | source
+# <<< mamba initialize <<<
+"#;
+        let powershell = r#"
+# >>> mamba initialize >>>
+$Env:MAMBA_ROOT_PREFIX = "/data/mamba"
+$Env:MAMBA_EXE = "/opt/micromamba/bin/micromamba"
+# <<< mamba initialize <<<
+"#;
+
+        for contents in [fish, powershell] {
+            assert_eq!(
+                parse_mamba_shell_init(contents),
+                MambaShellInit {
+                    mamba_exe: Some(PathBuf::from("/opt/micromamba/bin/micromamba")),
+                    root_prefix: Some(PathBuf::from("/data/mamba")),
+                }
+            );
+        }
+    }
+}
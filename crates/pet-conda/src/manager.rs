@@ -36,7 +36,7 @@ fn get_conda_executable(path: &Path) -> Option<PathBuf> {
     None
 }
 
-fn get_mamba_executable(path: &Path) -> Option<PathBuf> {
+pub(crate) fn get_mamba_executable(path: &Path) -> Option<PathBuf> {
     #[cfg(windows)]
     let relative_paths = vec![
         PathBuf::from("Scripts").join("mamba.exe"),
@@ -215,15 +215,23 @@ pub fn is_mamba_executable(exe: &Path) -> bool {
     }
 }
 
+/// Micromamba is a standalone executable, distinct from mamba, hence reported as a separate manager.
+pub fn get_mamba_manager_type(exe: &Path) -> EnvManagerType {
+    match exe.file_name().and_then(|n| n.to_str()) {
+        Some(name) if name.to_lowercase().starts_with("micromamba") => EnvManagerType::Micromamba,
+        _ => EnvManagerType::Mamba,
+    }
+}
+
 pub(crate) fn get_mamba_manager(path: &Path) -> Option<CondaManager> {
     let mamba_exe = get_mamba_executable(path)?;
     // We cannot reliably determine the mamba/micromamba version from package metadata alone.
     // The conda package version in conda-meta is the conda version, not the mamba version.
     // Determining the mamba version would require spawning the mamba process.
     Some(CondaManager {
+        manager_type: get_mamba_manager_type(&mamba_exe),
         executable: mamba_exe,
         version: None,
         conda_dir: Some(path.to_path_buf()),
-        manager_type: EnvManagerType::Mamba,
     })
 }
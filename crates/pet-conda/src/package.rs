@@ -68,10 +68,87 @@ impl CondaPackageInfo {
 
 #[derive(Deserialize, Debug)]
 struct CondaMetaPackageStructure {
+    name: Option<String>,
     channel: Option<String>,
     version: Option<String>,
 }
 
+/// Gets the packages the user installed into the environment (as `name version`),
+/// i.e. the specs in the history file that have not been removed since.
+/// If the history file does not contain any specs, then all of the installed packages are returned.
+pub fn get_requested_packages(path: &Path) -> Vec<String> {
+    let conda_meta = path.join("conda-meta");
+    let mut installed: Vec<(String, String)> = fs::read_dir(&conda_meta)
+        .map(|reader| {
+            reader
+                .filter_map(Result::ok)
+                .map(|entry| entry.path())
+                .filter(|path| path.extension().unwrap_or_default() == "json")
+                .filter_map(|path| {
+                    let contents = read_to_string(path).ok()?;
+                    let js = serde_json::from_str::<CondaMetaPackageStructure>(&contents).ok()?;
+                    Some((js.name?, js.version.unwrap_or_default()))
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    installed.sort();
+
+    let history = fs::read_to_string(conda_meta.join("history")).unwrap_or_default();
+    let requested = get_requested_specs_from_history(&history);
+    let packages: Vec<String> = requested
+        .iter()
+        .filter_map(|name| installed.iter().find(|(installed, _)| installed == name))
+        .map(|(name, version)| format!("{name} {version}"))
+        .collect();
+    if packages.is_empty() {
+        installed
+            .iter()
+            .map(|(name, version)| format!("{name} {version}"))
+            .collect()
+    } else {
+        packages
+    }
+}
+
+/// Sample lines in the history file
+/// # update specs: ['r-base', 'conda-forge::nodejs=18']
+/// # remove specs: ['nodejs']
+fn get_requested_specs_from_history(history: &str) -> Vec<String> {
+    let mut requested: Vec<String> = vec![];
+    for line in history.lines().map(|l| l.trim()) {
+        let (specs, remove) = if let Some(specs) = line.strip_prefix("# update specs:") {
+            (specs, false)
+        } else if let Some(specs) = line.strip_prefix("# remove specs:") {
+            (specs, true)
+        } else {
+            continue;
+        };
+        for spec in specs
+            .trim()
+            .trim_start_matches('[')
+            .trim_end_matches(']')
+            .split(',')
+        {
+            let spec = spec.trim().trim_matches(|c| c == '\'' || c == '"');
+            let spec = spec.rsplit("::").next().unwrap_or_default();
+            let name = spec
+                .split(|c: char| "=<>!~ [".contains(c))
+                .next()
+                .unwrap_or_default()
+                .to_string();
+            if name.is_empty() {
+                continue;
+            }
+            requested.retain(|n| n != &name);
+            if !remove {
+                requested.push(name);
+            }
+        }
+    }
+    requested
+}
+
 /// Get the details of a conda package from the 'conda-meta' directory.
 fn get_conda_package_info(path: &Path, name: &Package) -> Option<CondaPackageInfo> {
     if let Some(info) = get_conda_package_info_from_history(path, name) {
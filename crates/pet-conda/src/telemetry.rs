@@ -12,10 +12,10 @@ use pet_core::{
 
 use crate::{
     conda_info::CondaInfo,
-    conda_rc::Condarc,
+    conda_rc::{Condarc, CondarcEnvDir},
     env_variables::EnvVariables,
     environments::get_conda_environment_info,
-    manager::{is_mamba_executable, CondaManager},
+    manager::{get_mamba_manager_type, is_mamba_executable, CondaManager},
     utils::is_conda_install,
 };
 
@@ -195,7 +195,7 @@ pub fn report_missing_envs(
 pub fn get_conda_rcs_and_env_dirs(
     env_vars: &EnvVariables,
     known_envs: &[PythonEnvironment],
-) -> (Vec<PathBuf>, Vec<PathBuf>) {
+) -> (Vec<PathBuf>, Vec<PathBuf>, Vec<CondarcEnvDir>) {
     let known_conda_rcs = get_all_known_conda_rc(env_vars, known_envs);
     let discovered_conda_rcs = known_conda_rcs
         .iter()
@@ -205,8 +205,12 @@ pub fn get_conda_rcs_and_env_dirs(
         .iter()
         .flat_map(|rc| rc.env_dirs.clone().into_iter())
         .collect();
+    let env_dir_sources = known_conda_rcs
+        .iter()
+        .flat_map(|rc| rc.env_dir_sources.clone().into_iter())
+        .collect();
 
-    (discovered_conda_rcs, discovered_env_dirs)
+    (discovered_conda_rcs, discovered_env_dirs, env_dir_sources)
 }
 
 fn log_and_find_missing_envs(
@@ -226,7 +230,7 @@ fn log_and_find_missing_envs(
 
     // Oh oh, we have new envs, lets see what they are.
     let manager_type = if is_mamba_executable(&conda_info.executable) {
-        pet_core::manager::EnvManagerType::Mamba
+        get_mamba_manager_type(&conda_info.executable)
     } else {
         pet_core::manager::EnvManagerType::Conda
     };
@@ -35,6 +35,7 @@ pub fn create_env_variables(home: PathBuf, root: PathBuf) -> EnvVariables {
         anaconda_project_envs_path: None,
         project_dir: None,
         mamba_root_prefix: None,
+        mamba_exe: None,
         mambarc: None,
     }
 }
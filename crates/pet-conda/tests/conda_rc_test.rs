@@ -96,3 +96,41 @@ fn finds_conda_rc_from_root() {
         .env_dirs
         .contains(&PathBuf::from("/Users/donjayamanne/root-folder")));
 }
+
+#[cfg(unix)]
+#[test]
+fn finds_conda_rc_from_condarc_d_with_final_env_dirs() {
+    use common::create_env_variables;
+    use common::resolve_test_path;
+    use pet_conda::conda_rc::{Condarc, CondarcEnvDir};
+    use std::path::PathBuf;
+
+    let root = resolve_test_path(&["unix", "conda_rc_condarc_d", "root"]);
+    let home = resolve_test_path(&["unix", "conda_rc_condarc_d", "user_home"]);
+    let env = create_env_variables(home, root.clone());
+
+    let conda_rc = Condarc::from(&env).unwrap();
+
+    // Files in condarc.d are read in order of their names, the later ones taking precedence,
+    // & the env dirs of the user's .condarc are ignored as the scratch env dirs are final.
+    assert_eq!(
+        conda_rc.env_dirs,
+        vec![
+            PathBuf::from("/scratch/conda/envs"),
+            PathBuf::from("/shared/conda/envs"),
+            PathBuf::from("/opt/conda/envs"),
+        ]
+    );
+    assert_eq!(
+        conda_rc.env_dir_sources[0],
+        CondarcEnvDir {
+            env_dir: PathBuf::from("/scratch/conda/envs"),
+            file: root
+                .join("etc")
+                .join("conda")
+                .join("condarc.d")
+                .join("20-scratch.yaml"),
+        }
+    );
+    assert_eq!(conda_rc.files.len(), 4);
+}
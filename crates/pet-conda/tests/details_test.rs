@@ -0,0 +1,47 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT License.
+
+mod common;
+
+#[cfg(unix)]
+#[test]
+fn no_details_for_non_conda_envs() {
+    use common::resolve_test_path;
+    use pet_conda::details::CondaEnvironmentDetails;
+
+    let path = resolve_test_path(&["unix", "bogus_path"]);
+
+    assert!(CondaEnvironmentDetails::from(&path).is_none());
+}
+
+#[cfg(unix)]
+#[test]
+fn details_of_conda_env() {
+    use common::resolve_test_path;
+    use pet_conda::details::CondaEnvironmentDetails;
+
+    let path = resolve_test_path(&["unix", "anaconda3-2023.03", "envs", "env_python_3"]);
+
+    let details = CondaEnvironmentDetails::from(&path).unwrap();
+
+    assert_eq!(details.prefix, path);
+    assert_eq!(details.channel, Some("conda-forge".to_string()));
+    assert_eq!(details.channels, vec!["conda-forge".to_string()]);
+    assert_eq!(details.package_count, 4);
+    assert!(details.last_modified.is_some());
+    assert!(details.size > 0);
+}
+
+#[cfg(unix)]
+#[test]
+fn channel_of_python_package() {
+    use common::resolve_test_path;
+    use pet_conda::details::CondaEnvironmentDetails;
+
+    let path = resolve_test_path(&["unix", "conda_env_with_python_upgrade"]);
+
+    let details = CondaEnvironmentDetails::from(&path).unwrap();
+
+    assert_eq!(details.channel, Some("pkgs/main".to_string()));
+    assert_eq!(details.package_count, 1);
+}
@@ -0,0 +1,77 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT License.
+
+mod common;
+
+#[cfg(unix)]
+#[test]
+fn set_project_of_conda_env_declared_in_workspace() {
+    use common::{create_test_environment, resolve_test_path};
+    use pet_conda::Conda;
+    use pet_core::{env::PythonEnv, Configuration, Locator};
+    use std::collections::HashMap;
+
+    let workspace = resolve_test_path(&["unix", "conda_workspaces", "created"]);
+    let prefix = workspace.join("env");
+    let environment = create_test_environment(HashMap::new(), None, vec![], None);
+    let locator = Conda::from(&environment);
+    locator.configure(&Configuration {
+        workspace_directories: Some(vec![workspace.clone()]),
+        ..Default::default()
+    });
+
+    let env = locator
+        .try_from(&PythonEnv::new(
+            prefix.join("bin").join("python"),
+            Some(prefix.clone()),
+            None,
+        ))
+        .unwrap();
+
+    assert_eq!(env.prefix, Some(prefix));
+    assert_eq!(env.project, Some(workspace));
+}
+
+#[cfg(unix)]
+#[test]
+fn report_conda_env_declared_in_workspace_but_not_created() {
+    use common::{create_test_environment, resolve_test_path};
+    use pet_conda::Conda;
+    use pet_core::{python_environment::PythonEnvironmentState, Configuration, Locator};
+    use pet_reporter::{cache::CacheReporter, collect};
+    use std::{collections::HashMap, sync::Arc};
+
+    let workspace = resolve_test_path(&["unix", "conda_workspaces", "declared"]);
+    let home = resolve_test_path(&["unix", "bogus_home"]);
+    let environment = create_test_environment(HashMap::new(), Some(home.clone()), vec![], None);
+    let locator = Conda::from(&environment);
+    locator.configure(&Configuration {
+        workspace_directories: Some(vec![workspace.clone()]),
+        ..Default::default()
+    });
+    let reporter = Arc::new(collect::create_reporter());
+
+    locator.find(&CacheReporter::new(reporter.clone()));
+
+    let environments = reporter.environments.lock().unwrap().clone();
+    let env = environments
+        .iter()
+        .find(|e| e.project == Some(workspace.clone()))
+        .unwrap();
+    assert_eq!(env.name, Some("ml".to_string()));
+    assert_eq!(env.executable, None);
+    assert_eq!(env.state, Some(PythonEnvironmentState::NotCreated));
+    let command = env.create_command.clone().unwrap();
+    assert_eq!(
+        command[1..],
+        [
+            "env".to_string(),
+            "create".to_string(),
+            "--file".to_string(),
+            workspace
+                .join("environment.yml")
+                .to_string_lossy()
+                .to_string(),
+        ]
+    );
+}
@@ -73,3 +73,39 @@ fn find_conda_env_without_python() {
     assert_eq!(env.executable, None);
     assert_eq!(env.version, None);
 }
+
+#[cfg(unix)]
+#[test]
+fn conda_env_without_python_reports_packages_and_install_command() {
+    use pet_core::{
+        manager::{EnvManager, EnvManagerType},
+        python_environment::PythonEnvironmentState,
+    };
+    use std::path::PathBuf;
+
+    let path = resolve_test_path(&["unix", "conda_env_with_r_only"]);
+    let manager = EnvManager::new(
+        PathBuf::from("/home/user/miniforge3/bin/conda"),
+        EnvManagerType::Conda,
+        None,
+    );
+
+    let env = CondaEnvironment::from(&path, &None)
+        .unwrap()
+        .to_python_environment(Some(manager));
+
+    assert_eq!(env.executable, None);
+    assert_eq!(env.state, Some(PythonEnvironmentState::NoPython));
+    // nodejs was removed & libgcc-ng was not requested by the user.
+    assert_eq!(env.packages, Some(vec!["r-base 4.3.2".to_string()]));
+    assert_eq!(
+        env.install_python_command,
+        Some(vec![
+            "/home/user/miniforge3/bin/conda".to_string(),
+            "install".to_string(),
+            "--prefix".to_string(),
+            path.to_string_lossy().to_string(),
+            "python".to_string(),
+        ])
+    );
+}
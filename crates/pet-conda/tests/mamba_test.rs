@@ -0,0 +1,83 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT License.
+
+mod common;
+
+#[cfg(unix)]
+#[test]
+fn finds_micromamba_manager_for_env_in_default_root_prefix() {
+    use common::{create_test_environment, resolve_test_path};
+    use pet_conda::Conda;
+    use pet_core::{
+        env::PythonEnv, manager::EnvManagerType, python_environment::PythonEnvironmentKind, Locator,
+    };
+    use std::collections::HashMap;
+
+    let home = resolve_test_path(&["unix", "micromamba_root_prefix", "user_home"]);
+    let environment = create_test_environment(HashMap::new(), Some(home.clone()), vec![], None);
+    let locator = Conda::from(&environment);
+    let path = home.join("micromamba").join("envs").join("data");
+
+    let env = locator
+        .try_from(&PythonEnv::new(
+            path.join("bin").join("python"),
+            Some(path.clone()),
+            None,
+        ))
+        .unwrap();
+
+    assert_eq!(env.kind, Some(PythonEnvironmentKind::Conda));
+    assert_eq!(env.name, Some("data".to_string()));
+    assert_eq!(env.version, Some("3.12.1".to_string()));
+    let manager = env.manager.unwrap();
+    assert_eq!(manager.tool, EnvManagerType::Micromamba);
+    assert_eq!(
+        manager.executable,
+        home.join(".local").join("bin").join("micromamba")
+    );
+}
+
+#[cfg(unix)]
+#[test]
+fn finds_envs_and_manager_of_mamba_root_prefix_env_variable() {
+    use common::{create_test_environment, resolve_test_path};
+    use pet_conda::Conda;
+    use pet_core::{manager::EnvManagerType, Locator};
+    use pet_reporter::{cache::CacheReporter, collect};
+    use std::{collections::HashMap, sync::Arc};
+
+    let home = resolve_test_path(&["unix", "micromamba_root_prefix", "user_home"]);
+    let root_prefix = home.join("micromamba");
+    let micromamba = home.join(".local").join("bin").join("micromamba");
+    let vars = HashMap::from([
+        (
+            "MAMBA_ROOT_PREFIX".to_string(),
+            root_prefix.to_string_lossy().to_string(),
+        ),
+        (
+            "MAMBA_EXE".to_string(),
+            micromamba.to_string_lossy().to_string(),
+        ),
+    ]);
+    // Use a home without the default root prefix, to ensure the variable is used.
+    let other_home = resolve_test_path(&["unix", "bogus_home"]);
+    let environment = create_test_environment(vars, Some(other_home), vec![], None);
+    let locator = Conda::from(&environment);
+    let reporter = Arc::new(collect::create_reporter());
+
+    locator.find(&CacheReporter::new(reporter.clone()));
+
+    let managers = reporter.managers.lock().unwrap().clone();
+    assert!(managers
+        .iter()
+        .any(|m| m.tool == EnvManagerType::Micromamba && m.executable == micromamba));
+    let environments = reporter.environments.lock().unwrap().clone();
+    let env = environments
+        .iter()
+        .find(|e| e.prefix == Some(root_prefix.join("envs").join("data")))
+        .unwrap();
+    assert_eq!(
+        env.manager.as_ref().map(|m| m.tool),
+        Some(EnvManagerType::Micromamba)
+    );
+}
@@ -191,7 +191,7 @@ fn finds_mamba_manager_when_only_micromamba_available() {
     // No conda binary, should fall back to micromamba
     assert_eq!(manager.executable, path.join("bin").join("micromamba"));
     assert_eq!(manager.version, None); // version is unknown for mamba
-    assert_eq!(manager.manager_type, EnvManagerType::Mamba);
+    assert_eq!(manager.manager_type, EnvManagerType::Micromamba);
 }
 
 /// Test that find_mamba_binary finds mamba from the PATH environment variable.
@@ -1,12 +1,13 @@
 // Copyright (c) Microsoft Corporation.
 // Licensed under the MIT License.
 
-use std::path::PathBuf;
+use std::{path::PathBuf, time::Duration};
 
 use env::PythonEnv;
 use manager::EnvManager;
 use python_environment::{PythonEnvironment, PythonEnvironmentKind};
 use reporter::Reporter;
+use serde::{Deserialize, Serialize};
 
 pub mod arch;
 pub mod cache;
@@ -38,9 +39,16 @@ pub struct Configuration {
     pub environment_directories: Option<Vec<PathBuf>>,
     /// Directory to cache the Python environment details.
     pub cache_directory: Option<PathBuf>,
+    /// Directories that are never searched for environments,
+    /// either folder names (e.g. `node_modules`) or paths (e.g. `/mnt/data`).
+    pub ignored_directories: Option<Vec<PathBuf>>,
+    /// Locators that are not used to discover environments.
+    pub disabled_locators: Option<Vec<LocatorKind>>,
+    /// Maximum time to wait for spawned processes (such as Python, conda & poetry) to exit.
+    pub spawn_timeout: Option<Duration>,
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
 pub enum LocatorKind {
     Conda,
     Homebrew,
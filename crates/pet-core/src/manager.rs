@@ -1,13 +1,15 @@
 // Copyright (c) Microsoft Corporation.
 // Licensed under the MIT License.
 
+use clap::ValueEnum;
 use serde::{Deserialize, Serialize};
 use std::path::PathBuf;
 
-#[derive(Serialize, Deserialize, Copy, Clone, PartialEq, Eq, Debug, Hash)]
+#[derive(ValueEnum, Serialize, Deserialize, Copy, Clone, PartialEq, Eq, Debug, Hash)]
 pub enum EnvManagerType {
     Conda,
     Mamba,
+    Micromamba,
     Pipenv,
     Poetry,
     Pyenv,
@@ -43,6 +43,19 @@ impl PartialOrd for PythonEnvironmentKind {
     }
 }
 
+/// Whether an environment can be used, so that clients can explain why an environment cannot be used.
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug, Hash)]
+pub enum PythonEnvironmentState {
+    /// The environment has a Python executable & no known issues.
+    Ready,
+    /// The environment does not have Python installed, e.g. conda environments with only R packages.
+    NoPython,
+    /// The environment is known to be in a bad state, see `error` for details.
+    Broken,
+    /// The environment is declared in a workspace folder (e.g. `environment.yml`) but has not been created.
+    NotCreated,
+}
+
 #[derive(Serialize, Deserialize, Clone, PartialEq, Eq)]
 #[serde(rename_all = "camelCase")]
 #[derive(Debug, Default)]
@@ -76,6 +89,81 @@ pub struct PythonEnvironment {
     /// If None, no known issues have been detected (but this doesn't guarantee
     /// the environment is fully functional - we don't spawn Python to verify).
     pub error: Option<String>,
+    /// Whether the version of the environment satisfies the Python versions supported
+    /// (`requires-python`) by each of the workspace folders.
+    pub compatibility: Option<Vec<WorkspaceCompatibility>>,
+    pub state: Option<PythonEnvironmentState>,
+    /// Packages installed in an environment that does not have Python, e.g. `r-base 4.3.1`.
+    pub packages: Option<Vec<String>>,
+    /// Command (program & arguments) to install Python into an environment that does not have Python.
+    pub install_python_command: Option<Vec<String>>,
+    /// Command (program & arguments) to create an environment that has been declared but not created.
+    pub create_command: Option<Vec<String>>,
+    /// Python version required by the project of the environment, e.g. `3.11` from the `Pipfile.lock` of a Pipenv project.
+    pub requires_python: Option<String>,
+    /// Whether a version manager such as pyenv selects this environment, globally, for the shell or for workspace folders.
+    pub selections: Option<Vec<EnvironmentSelection>>,
+    /// Prefix of the Python the virtual environment was created from (`home` in `pyvenv.cfg`).
+    pub base_prefix: Option<PathBuf>,
+    /// Implementation of Python, if known, e.g. PyPy for `pypy3.10-7.3.15` installed by pyenv.
+    pub implementation: Option<PythonImplementation>,
+    /// Distribution the environment was installed from, e.g. `miniconda3` or `pypy` installed by pyenv.
+    pub distribution: Option<PythonDistribution>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug, Hash)]
+pub enum PythonImplementation {
+    CPython,
+    PyPy,
+    GraalPy,
+    Stackless,
+    Pyston,
+    Jython,
+    IronPython,
+    MicroPython,
+}
+
+/// Distribution of Python, other than the CPython releases from python.org.
+#[derive(Serialize, Deserialize, Clone, PartialEq, Eq, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct PythonDistribution {
+    /// E.g. `anaconda3`, `miniforge3`, `pypy`, `graalpy-community` or `nogil`.
+    pub name: String,
+    /// Release of the distribution (not the Python version), e.g. `2024.02-1`, `latest` or `7.3.15`.
+    pub version: Option<String>,
+}
+
+/// How a version manager such as pyenv selects an environment, in increasing order of precedence.
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug, Hash)]
+pub enum SelectionScope {
+    /// Selected by the global version file, e.g. `$PYENV_ROOT/version`.
+    Global,
+    /// Selected by the `.python-version` file of a workspace folder (or one of its parents).
+    Local,
+    /// Selected for the current shell, e.g. `PYENV_VERSION`.
+    Shell,
+}
+
+/// An environment selected by a version manager such as pyenv.
+#[derive(Serialize, Deserialize, Clone, PartialEq, Eq, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct EnvironmentSelection {
+    pub scope: SelectionScope,
+    /// File the selection was read from, None when selected by an environment variable.
+    pub file: Option<PathBuf>,
+    /// Workspace folder the selection applies to, only for `Local` selections.
+    pub workspace: Option<PathBuf>,
+}
+
+/// Whether the version of an environment satisfies the Python versions supported by a workspace folder.
+#[derive(Serialize, Deserialize, Clone, PartialEq, Eq, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct WorkspaceCompatibility {
+    pub workspace: PathBuf,
+    /// Python versions supported by the project in the workspace folder, e.g. `>=3.11`.
+    pub requires_python: String,
+    /// None if unknown, e.g. when the version of the environment is not known.
+    pub compatible: Option<bool>,
 }
 
 impl Ord for PythonEnvironment {
@@ -185,6 +273,85 @@ impl std::fmt::Display for PythonEnvironment {
         if let Some(error) = &self.error {
             writeln!(f, "   Error       : {error}").unwrap_or_default();
         }
+        if let Some(state) = &self.state {
+            if *state != PythonEnvironmentState::Ready {
+                writeln!(f, "   State       : {state:?}").unwrap_or_default();
+            }
+        }
+        if let Some(packages) = &self.packages {
+            if !packages.is_empty() {
+                writeln!(f, "   Packages    : {}", packages.join(", ")).unwrap_or_default();
+            }
+        }
+        if let Some(command) = &self.install_python_command {
+            writeln!(f, "   Add Python  : {}", command.join(" ")).unwrap_or_default();
+        }
+        if let Some(requires_python) = &self.requires_python {
+            writeln!(f, "   Requires    : Python {requires_python}").unwrap_or_default();
+        }
+        if let Some(command) = &self.create_command {
+            writeln!(f, "   Create      : {}", command.join(" ")).unwrap_or_default();
+        }
+        if let Some(implementation) = &self.implementation {
+            writeln!(f, "   Impl        : {implementation:?}").unwrap_or_default();
+        }
+        if let Some(distribution) = &self.distribution {
+            match &distribution.version {
+                Some(version) => {
+                    writeln!(f, "   Distribution: {} {version}", distribution.name)
+                }
+                None => writeln!(f, "   Distribution: {}", distribution.name),
+            }
+            .unwrap_or_default();
+        }
+        if let Some(base_prefix) = &self.base_prefix {
+            writeln!(
+                f,
+                "   Base Prefix : {}",
+                base_prefix.to_str().unwrap_or_default()
+            )
+            .unwrap_or_default();
+        }
+        if let Some(selections) = &self.selections {
+            for (i, selection) in selections.iter().enumerate() {
+                let label = if i == 0 {
+                    "   Selected    :"
+                } else {
+                    "               :"
+                };
+                match &selection.workspace {
+                    Some(workspace) => writeln!(
+                        f,
+                        "{label} {:?} ({})",
+                        selection.scope,
+                        workspace.to_str().unwrap_or_default()
+                    ),
+                    None => writeln!(f, "{label} {:?}", selection.scope),
+                }
+                .unwrap_or_default();
+            }
+        }
+        if let Some(compatibility) = &self.compatibility {
+            for (i, item) in compatibility.iter().enumerate() {
+                let compatible = match item.compatible {
+                    Some(true) => "Yes",
+                    Some(false) => "No",
+                    None => "Unknown",
+                };
+                let label = if i == 0 {
+                    "   Compatible  :"
+                } else {
+                    "               :"
+                };
+                writeln!(
+                    f,
+                    "{label} {compatible} (requires-python {} in {})",
+                    item.requires_python,
+                    item.workspace.to_str().unwrap_or_default()
+                )
+                .unwrap_or_default();
+            }
+        }
         Ok(())
     }
 }
@@ -204,6 +371,16 @@ pub struct PythonEnvironmentBuilder {
     arch: Option<Architecture>,
     symlinks: Option<Vec<PathBuf>>,
     error: Option<String>,
+    compatibility: Option<Vec<WorkspaceCompatibility>>,
+    state: Option<PythonEnvironmentState>,
+    packages: Option<Vec<String>>,
+    install_python_command: Option<Vec<String>>,
+    create_command: Option<Vec<String>>,
+    requires_python: Option<String>,
+    selections: Option<Vec<EnvironmentSelection>>,
+    base_prefix: Option<PathBuf>,
+    implementation: Option<PythonImplementation>,
+    distribution: Option<PythonDistribution>,
 }
 
 impl PythonEnvironmentBuilder {
@@ -220,6 +397,16 @@ impl PythonEnvironmentBuilder {
             arch: None,
             symlinks: None,
             error: None,
+            compatibility: None,
+            state: None,
+            packages: None,
+            install_python_command: None,
+            create_command: None,
+            requires_python: None,
+            selections: None,
+            base_prefix: None,
+            implementation: None,
+            distribution: None,
         }
     }
     pub fn from_environment(env: PythonEnvironment) -> Self {
@@ -235,6 +422,17 @@ impl PythonEnvironmentBuilder {
             arch: env.arch,
             symlinks: env.symlinks,
             error: env.error,
+            compatibility: env.compatibility,
+            // Recomputed when built, as the error or the executable of the environment may change.
+            state: None,
+            packages: env.packages,
+            install_python_command: env.install_python_command,
+            create_command: env.create_command,
+            requires_python: env.requires_python,
+            selections: env.selections,
+            base_prefix: env.base_prefix,
+            implementation: env.implementation,
+            distribution: env.distribution,
         }
     }
 
@@ -302,6 +500,55 @@ impl PythonEnvironmentBuilder {
         self
     }
 
+    /// If not provided, the state is determined from the error & the executable.
+    pub fn state(mut self, state: Option<PythonEnvironmentState>) -> Self {
+        self.state = state;
+        self
+    }
+
+    pub fn packages(mut self, packages: Option<Vec<String>>) -> Self {
+        self.packages = packages;
+        self
+    }
+
+    pub fn install_python_command(mut self, install_python_command: Option<Vec<String>>) -> Self {
+        self.install_python_command = install_python_command;
+        self
+    }
+
+    pub fn create_command(mut self, create_command: Option<Vec<String>>) -> Self {
+        self.create_command = create_command;
+        self
+    }
+
+    pub fn requires_python(mut self, requires_python: Option<String>) -> Self {
+        self.requires_python = requires_python;
+        self
+    }
+
+    pub fn selections(mut self, selections: Option<Vec<EnvironmentSelection>>) -> Self {
+        self.selections = selections;
+        self
+    }
+
+    pub fn base_prefix(mut self, base_prefix: Option<PathBuf>) -> Self {
+        self.base_prefix.clone_from(&base_prefix);
+        if let Some(resolved) = base_prefix {
+            self.base_prefix = Some(norm_case(resolved))
+        }
+        self
+    }
+
+    pub fn implementation(mut self, implementation: Option<PythonImplementation>) -> Self {
+        self.implementation = implementation;
+        self
+    }
+
+    pub fn distribution(mut self, distribution: Option<PythonDistribution>) -> Self {
+        self.distribution = distribution;
+        self
+    }
+
     fn update_symlinks_and_exe(&mut self, symlinks: Option<Vec<PathBuf>>) {
         let mut all = self.symlinks.clone().unwrap_or_default();
         if let Some(ref exe) = self.executable {
@@ -345,6 +592,13 @@ impl PythonEnvironmentBuilder {
         let executable = self.executable.map(|executable| {
             get_shortest_executable(&self.kind, &Some(all.clone())).unwrap_or(executable)
         });
+        let state = self.state.unwrap_or(if self.error.is_some() {
+            PythonEnvironmentState::Broken
+        } else if executable.is_none() {
+            PythonEnvironmentState::NoPython
+        } else {
+            PythonEnvironmentState::Ready
+        });
 
         PythonEnvironment {
             display_name: self.display_name,
@@ -358,6 +612,16 @@ impl PythonEnvironmentBuilder {
             arch: self.arch,
             symlinks,
             error: self.error,
+            compatibility: self.compatibility,
+            state: Some(state),
+            packages: self.packages,
+            install_python_command: self.install_python_command,
+            create_command: self.create_command,
+            requires_python: self.requires_python,
+            selections: self.selections,
+            base_prefix: self.base_prefix,
+            implementation: self.implementation,
+            distribution: self.distribution,
         }
     }
 }
@@ -436,10 +700,31 @@ pub fn get_environment_key(env: &PythonEnvironment) -> Option<PathBuf> {
 
 #[cfg(test)]
 mod tests {
-    #[cfg(windows)]
-    use super::{get_shortest_executable, PythonEnvironmentKind};
-    #[cfg(windows)]
-    use std::path::PathBuf;
+    use super::*;
+
+    #[test]
+    fn rebuilt_environment_recomputes_state() {
+        let env = PythonEnvironmentBuilder::new(Some(PythonEnvironmentKind::Pyenv))
+            .executable(Some(PathBuf::from("/pyenv/versions/3.12.1/bin/python")))
+            .prefix(Some(PathBuf::from("/pyenv/versions/3.12.1")))
+            .build();
+        assert_eq!(env.state, Some(PythonEnvironmentState::Ready));
+
+        let broken = PythonEnvironmentBuilder::from_environment(env.clone())
+            .error(Some("Python executable is a broken symlink".to_string()))
+            .build();
+        assert_eq!(broken.state, Some(PythonEnvironmentState::Broken));
+
+        let no_python = PythonEnvironmentBuilder::from_environment(env.clone())
+            .executable(None)
+            .build();
+        assert_eq!(no_python.state, Some(PythonEnvironmentState::NoPython));
+
+        let not_created = PythonEnvironmentBuilder::from_environment(env)
+            .state(Some(PythonEnvironmentState::NotCreated))
+            .build();
+        assert_eq!(not_created.state, Some(PythonEnvironmentState::NotCreated));
+    }
 
     #[test]
     #[cfg(windows)]
@@ -23,6 +23,8 @@ pub struct PyVenvCfg {
     pub version_major: Option<u64>,
     pub version_minor: Option<u64>,
     pub prompt: Option<String>,
+    /// Directory of the Python the virtual environment was created from, generally `<base prefix>/bin`.
+    pub home: Option<PathBuf>,
     pub file_path: PathBuf,
 }
 
@@ -32,6 +34,7 @@ impl PyVenvCfg {
         version_major: Option<u64>,
         version_minor: Option<u64>,
         prompt: Option<String>,
+        home: Option<PathBuf>,
         file_path: PathBuf,
     ) -> Self {
         Self {
@@ -39,6 +42,7 @@ impl PyVenvCfg {
             version_major,
             version_minor,
             prompt,
+            home,
             file_path,
         }
     }
@@ -102,6 +106,7 @@ fn parse(file: &Path) -> Option<PyVenvCfg> {
     let mut version_major: Option<u64> = None;
     let mut version_minor: Option<u64> = None;
     let mut prompt: Option<String> = None;
+    let mut home: Option<PathBuf> = None;
 
     for line in contents.lines() {
         if version.is_none() {
@@ -123,7 +128,12 @@ fn parse(file: &Path) -> Option<PyVenvCfg> {
                 prompt = Some(p);
             }
         }
-        if version.is_some() && prompt.is_some() {
+        if home.is_none() {
+            if let Some(h) = parse_home(line) {
+                home = Some(h);
+            }
+        }
+        if version.is_some() && prompt.is_some() && home.is_some() {
             break;
         }
     }
@@ -134,11 +144,19 @@ fn parse(file: &Path) -> Option<PyVenvCfg> {
             Some(major),
             Some(minor),
             prompt,
+            home,
             file.to_path_buf(),
         )),
         // Even without version info, return the struct - presence of pyvenv.cfg
         // is sufficient to identify this as a venv environment
-        _ => Some(PyVenvCfg::new(None, None, None, prompt, file.to_path_buf())),
+        _ => Some(PyVenvCfg::new(
+            None,
+            None,
+            None,
+            prompt,
+            home,
+            file.to_path_buf(),
+        )),
     }
 }
 
@@ -161,6 +179,19 @@ fn parse_version(line: &str, regex: &Regex) -> Option<(String, u64, u64)> {
     None
 }
 
+fn parse_home(line: &str) -> Option<PathBuf> {
+    let (key, value) = line.split_once('=')?;
+    if key.trim() != "home" {
+        return None;
+    }
+    let value = value.trim();
+    if value.is_empty() {
+        None
+    } else {
+        Some(PathBuf::from(value))
+    }
+}
+
 fn parse_prompt(line: &str) -> Option<String> {
     let trimmed = line.trim();
     if trimmed.starts_with("prompt") {
@@ -266,6 +297,17 @@ mod tests {
         assert!(result.is_none());
     }
 
+    #[test]
+    fn test_parse_home() {
+        assert_eq!(
+            parse_home("home = /Users/user/.pyenv/versions/3.11.9/bin"),
+            Some(PathBuf::from("/Users/user/.pyenv/versions/3.11.9/bin"))
+        );
+        assert_eq!(parse_home("home ="), None);
+        assert_eq!(parse_home("include-system-site-packages = false"), None);
+        assert_eq!(parse_home("homebrew = /opt/homebrew"), None);
+    }
+
     #[test]
     fn test_pyvenv_cfg_find_in_directory() {
         let dir = tempdir().unwrap();
@@ -15,13 +15,14 @@ pub fn get_search_paths_from_env_variables(environment: &dyn Environment) -> Vec
             .join("Microsoft")
             .join("WindowsApps");
 
+        // Preserve the order of the PATH, as the first match wins when running a command.
+        let mut seen = HashSet::new();
         environment
             .get_know_global_search_locations()
             .into_iter()
             .map(normalize_search_path)
-            .collect::<HashSet<PathBuf>>()
-            .into_iter()
             .filter(|p| !p.starts_with(apps_path.clone()))
+            .filter(|p| seen.insert(p.clone()))
             .collect()
     } else {
         Vec::new()
@@ -15,7 +15,7 @@ use pet_core::{
 use pet_fs::path::resolve_symlink;
 use pet_python_utils::executable::find_executables;
 use pet_virtualenv::is_virtualenv;
-use std::{fs, path::PathBuf, thread};
+use std::{fs, path::PathBuf};
 use sym_links::is_homebrew_python;
 
 mod env_variables;
@@ -137,7 +137,7 @@ impl Locator for Homebrew {
 
     fn find(&self, reporter: &dyn Reporter) {
         let homebrew_prefix_bins = get_homebrew_prefix_bin(&self.environment);
-        thread::scope(|s| {
+        rayon::scope(|s| {
             for homebrew_prefix_bin in &homebrew_prefix_bins {
                 let homebrew_python_exes = find_executables(homebrew_prefix_bin);
                 for file in homebrew_python_exes.iter().filter(|f| {
@@ -155,7 +155,7 @@ impl Locator for Homebrew {
                         && file_name != "python"
                 }) {
                     let file = file.clone();
-                    s.spawn(move || {
+                    s.spawn(move |_| {
                         // Sometimes we end up with other python installs in the Homebrew bin directory.
                         // E.g. /usr/local/bin is treated as a location where homebrew can be found (homebrew bin)
                         // However this is a very generic location, and we might end up with other python installs here.
@@ -10,7 +10,12 @@ use std::path::PathBuf;
 pub struct EnvVariables {
     #[allow(dead_code)]
     pub pipenv_max_depth: u16,
+    /// Maps to env var `PIPENV_PIPFILE`, the name of the Pipfile or the path to a central Pipfile.
     pub pipenv_pipfile: String,
+    /// Maps to env var `PIPENV_CUSTOM_VENV_NAME`, used instead of `<project name>-<hash>`.
+    pub pipenv_custom_venv_name: Option<String>,
+    /// Maps to env var `PIPENV_VENV_IN_PROJECT`, if `false` the `.venv` in the project is never used.
+    pub pipenv_venv_in_project: Option<bool>,
     /// User's home directory
     pub home: Option<PathBuf>,
     /// Maps to env var `WORKON_HOME` - custom directory for virtual environments
@@ -30,6 +35,12 @@ impl EnvVariables {
             pipenv_pipfile: env
                 .get_env_var("PIPENV_PIPFILE".to_string())
                 .unwrap_or("Pipfile".to_string()),
+            pipenv_custom_venv_name: env
+                .get_env_var("PIPENV_CUSTOM_VENV_NAME".to_string())
+                .filter(|name| !name.trim().is_empty()),
+            pipenv_venv_in_project: env
+                .get_env_var("PIPENV_VENV_IN_PROJECT".to_string())
+                .and_then(|value| parse_bool(&value)),
             home: env.get_user_home(),
             xdg_data_home: env.get_env_var("XDG_DATA_HOME".to_string()),
             workon_home: env
@@ -39,3 +50,12 @@ impl EnvVariables {
         }
     }
 }
+
+/// Pipenv treats `1`, `true`, `yes` & `on` as true & `0`, `false`, `no` & `off` as false.
+fn parse_bool(value: &str) -> Option<bool> {
+    match value.trim().to_lowercase().as_str() {
+        "1" | "true" | "yes" | "on" => Some(true),
+        "0" | "false" | "no" | "off" => Some(false),
+        _ => None,
+    }
+}
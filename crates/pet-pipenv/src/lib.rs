@@ -20,9 +20,11 @@ use regex::Regex;
 use std::path::Path;
 use std::sync::{Arc, RwLock};
 use std::{fs, path::PathBuf};
+use virtualenv_name::generate_env_name;
 
 mod env_variables;
 pub mod manager;
+mod virtualenv_name;
 
 lazy_static! {
     /// Regex pattern for pipenv environment directory names.
@@ -136,7 +138,9 @@ fn is_in_pipenv_centralized_dir(env: &PythonEnv, env_vars: &EnvVariables) -> boo
                 // This handles edge cases where .project was deleted, corrupted,
                 // or environments from older pipenv versions.
                 if let Some(dir_name) = prefix.file_name().and_then(|n| n.to_str()) {
-                    if PIPENV_ENV_NAME_PATTERN.is_match(dir_name) {
+                    if PIPENV_ENV_NAME_PATTERN.is_match(dir_name)
+                        || env_vars.pipenv_custom_venv_name.as_deref() == Some(dir_name)
+                    {
                         trace!(
                             "Pipenv: Detected centralized pipenv env at {:?} (in {:?}, matched naming pattern, no .project file)",
                             prefix,
@@ -162,18 +166,48 @@ fn is_in_pipenv_centralized_dir(env: &PythonEnv, env_vars: &EnvVariables) -> boo
     false
 }
 
-fn get_pipenv_project(env: &PythonEnv) -> Option<PathBuf> {
+/// Directory of the central Pipfile, when `PIPENV_PIPFILE` is the path to a Pipfile.
+/// The directory of the Pipfile is the project, irrespective of where pipenv is run from.
+fn get_central_project_dir(env_vars: &EnvVariables) -> Option<PathBuf> {
+    let pipfile = Path::new(&env_vars.pipenv_pipfile);
+    if pipfile.is_absolute() {
+        pipfile.parent().map(norm_case)
+    } else {
+        None
+    }
+}
+
+/// Whether the directory is a pipenv project, i.e. contains the Pipfile.
+fn is_pipenv_project_dir(dir: &Path, env_vars: &EnvVariables) -> bool {
+    match get_central_project_dir(env_vars) {
+        Some(project_dir) => {
+            project_dir == norm_case(dir) && Path::new(&env_vars.pipenv_pipfile).is_file()
+        }
+        None => dir.join(&env_vars.pipenv_pipfile).is_file(),
+    }
+}
+
+fn get_pipenv_project(env: &PythonEnv, env_vars: &EnvVariables) -> Option<PathBuf> {
     if let Some(prefix) = &env.prefix {
         if let Some(project) = get_pipenv_project_from_prefix(prefix) {
             return Some(project);
         }
+        // Environments with a custom name belong to the project of the central Pipfile.
+        if let (Some(custom_venv_name), Some(project_dir)) = (
+            &env_vars.pipenv_custom_venv_name,
+            get_central_project_dir(env_vars),
+        ) {
+            if prefix.file_name().and_then(|n| n.to_str()) == Some(custom_venv_name.as_str()) {
+                return Some(project_dir);
+            }
+        }
         // If there's no .project file, but the venv lives inside the project folder
         // (e.g., <project>/.venv or <project>/venv), then the project is the parent
         // directory of the venv. Detect that by checking for a Pipfile next to the venv.
-        if let Some(parent) = prefix.parent() {
+        if let Some(parent) = prefix.parent().filter(|_| uses_venv_in_project(env_vars)) {
             let project_folder = parent;
-            if project_folder.join("Pipfile").exists() {
-                return Some(project_folder.to_path_buf());
+            if is_pipenv_project_dir(project_folder, env_vars) {
+                return Some(norm_case(project_folder));
             }
         }
     }
@@ -183,7 +217,11 @@ fn get_pipenv_project(env: &PythonEnv) -> Option<PathBuf> {
     // Derive the project folder from the executable path when prefix isn't available.
     // Typical layout: <project>/.venv/{bin|Scripts}/python
     // So walk up to {bin|Scripts} -> venv dir -> project dir and check for Pipfile.
-    if let Some(bin) = env.executable.parent() {
+    if let Some(bin) = env
+        .executable
+        .parent()
+        .filter(|_| uses_venv_in_project(env_vars))
+    {
         let venv_dir = if bin.file_name().unwrap_or_default() == Path::new("bin")
             || bin.file_name().unwrap_or_default() == Path::new("Scripts")
         {
@@ -193,8 +231,8 @@ fn get_pipenv_project(env: &PythonEnv) -> Option<PathBuf> {
         };
         if let Some(venv_dir) = venv_dir {
             if let Some(project_dir) = venv_dir.parent() {
-                if project_dir.join("Pipfile").exists() {
-                    return Some(project_dir.to_path_buf());
+                if is_pipenv_project_dir(project_dir, env_vars) {
+                    return Some(norm_case(project_dir));
                 }
             }
         }
@@ -224,11 +262,19 @@ fn get_pipenv_project_from_prefix(prefix: &Path) -> Option<PathBuf> {
     Some(project_folder)
 }
 
-fn is_pipenv_from_project(env: &PythonEnv) -> bool {
+/// With `PIPENV_VENV_IN_PROJECT=0` pipenv never uses the virtual env in the project folder.
+fn uses_venv_in_project(env_vars: &EnvVariables) -> bool {
+    env_vars.pipenv_venv_in_project != Some(false)
+}
+
+fn is_pipenv_from_project(env: &PythonEnv, env_vars: &EnvVariables) -> bool {
+    if !uses_venv_in_project(env_vars) {
+        return false;
+    }
     // If the env prefix is inside a project folder, check that folder for a Pipfile.
     if let Some(prefix) = &env.prefix {
         if let Some(project_dir) = prefix.parent() {
-            if project_dir.join("Pipfile").exists() {
+            if is_pipenv_project_dir(project_dir, env_vars) {
                 return true;
             }
         }
@@ -244,7 +290,7 @@ fn is_pipenv_from_project(env: &PythonEnv) -> bool {
         };
         if let Some(venv_dir) = venv_dir {
             if let Some(project_dir) = venv_dir.parent() {
-                if project_dir.join("Pipfile").exists() {
+                if is_pipenv_project_dir(project_dir, env_vars) {
                     return true;
                 }
             }
@@ -271,7 +317,7 @@ fn is_pipenv(env: &PythonEnv, env_vars: &EnvVariables) -> bool {
     }
 
     // Check if there's a .project file pointing to a project with a Pipfile
-    if let Some(project_path) = get_pipenv_project(env) {
+    if let Some(project_path) = get_pipenv_project(env, env_vars) {
         let pipfile_path = project_path.join(env_vars.pipenv_pipfile.clone());
         if pipfile_path.exists() {
             trace!(
@@ -290,7 +336,7 @@ fn is_pipenv(env: &PythonEnv, env_vars: &EnvVariables) -> bool {
     }
 
     // Check if the venv is inside a project folder with a Pipfile
-    if is_pipenv_from_project(env) {
+    if is_pipenv_from_project(env, env_vars) {
         trace!(
             "Pipenv: {:?} identified via in-project Pipfile",
             env.executable
@@ -302,6 +348,33 @@ fn is_pipenv(env: &PythonEnv, env_vars: &EnvVariables) -> bool {
     false
 }
 
+/// Gets the Python version required by the project, from `_meta.requires` in the `Pipfile.lock`
+/// or else from `[requires]` in the `Pipfile`.
+fn get_required_python_version(project_dir: &Path, env_vars: &EnvVariables) -> Option<String> {
+    let pipfile = project_dir.join(&env_vars.pipenv_pipfile);
+    let mut lock_file = pipfile.clone().into_os_string();
+    lock_file.push(".lock");
+    if let Ok(contents) = fs::read_to_string(PathBuf::from(lock_file)) {
+        if let Ok(lock) = serde_json::from_str::<serde_json::Value>(&contents) {
+            let requires = &lock["_meta"]["requires"];
+            if let Some(version) = requires["python_full_version"]
+                .as_str()
+                .or(requires["python_version"].as_str())
+            {
+                return Some(version.to_string());
+            }
+        }
+    }
+    let contents = fs::read_to_string(&pipfile).ok()?;
+    let pipfile = toml::from_str::<toml::Value>(&contents).ok()?;
+    let requires = pipfile.get("requires")?;
+    requires
+        .get("python_full_version")
+        .or(requires.get("python_version"))
+        .and_then(|version| version.as_str())
+        .map(|version| version.to_string())
+}
+
 /// Get the default virtualenvs directory for pipenv
 /// - If WORKON_HOME is set, use that
 /// - Linux/macOS: ~/.local/share/virtualenvs/
@@ -384,6 +457,10 @@ fn list_environments(env_vars: &EnvVariables) -> Vec<PythonEnvironment> {
                                 .executable(Some(norm_case(python_exe)))
                                 .version(version)
                                 .prefix(Some(norm_case(path.clone())))
+                                .requires_python(get_required_python_version(
+                                    &project_path,
+                                    env_vars,
+                                ))
                                 .project(Some(project_path))
                                 .symlinks(Some(symlinks))
                                 .build();
@@ -396,12 +473,100 @@ fn list_environments(env_vars: &EnvVariables) -> Vec<PythonEnvironment> {
         }
     }
 
+    // The virtual env of the central Pipfile need not be in any of the workspace folders.
+    if let Some(project_dir) = get_central_project_dir(env_vars) {
+        if let Some(env) = get_central_project_venv(&project_dir, env_vars) {
+            trace!("Found pipenv environment of the central Pipfile: {:?}", env);
+            environments.push(env);
+        }
+    }
+
     environments
 }
 
+fn get_central_project_venv(
+    project_dir: &Path,
+    env_vars: &EnvVariables,
+) -> Option<PythonEnvironment> {
+    if !uses_venv_in_project(env_vars) {
+        return None;
+    }
+    create_pipenv_env(&project_dir.join(".venv"), project_dir, env_vars)
+}
+
+/// Pipenv environments of the workspace folders with a Pipfile.
+/// Centralized environments are found using the name pipenv generates for the project,
+/// hence the environment is found even if the `.project` file is missing.
+fn list_workspace_environments(
+    workspace_dirs: &[PathBuf],
+    env_vars: &EnvVariables,
+) -> Vec<PythonEnvironment> {
+    // All workspace folders share the central Pipfile, its environment is found in `list_environments`.
+    if get_central_project_dir(env_vars).is_some() {
+        return vec![];
+    }
+    let virtualenv_dirs = get_pipenv_virtualenv_dirs(env_vars);
+    workspace_dirs
+        .iter()
+        .filter_map(|workspace_dir| {
+            let pipfile = norm_case(workspace_dir.join(&env_vars.pipenv_pipfile));
+            if !pipfile.is_file() {
+                return None;
+            }
+            let project_dir = pipfile.parent()?;
+            if uses_venv_in_project(env_vars) {
+                if let Some(env) =
+                    create_pipenv_env(&project_dir.join(".venv"), project_dir, env_vars)
+                {
+                    return Some(env);
+                }
+            }
+            let name = match &env_vars.pipenv_custom_venv_name {
+                Some(name) => name.clone(),
+                None => generate_env_name(project_dir.file_name()?.to_str()?, &pipfile),
+            };
+            trace!("Pipenv environment name for {:?} is {}", project_dir, name);
+            virtualenv_dirs
+                .iter()
+                .find_map(|dir| create_pipenv_env(&dir.join(&name), project_dir, env_vars))
+        })
+        .collect()
+}
+
+fn create_pipenv_env(
+    venv: &Path,
+    project_dir: &Path,
+    env_vars: &EnvVariables,
+) -> Option<PythonEnvironment> {
+    let bin_dir = if std::env::consts::OS == "windows" {
+        venv.join("Scripts")
+    } else {
+        venv.join("bin")
+    };
+    let python_exe = if std::env::consts::OS == "windows" {
+        bin_dir.join("python.exe")
+    } else {
+        bin_dir.join("python")
+    };
+    if !python_exe.is_file() {
+        return None;
+    }
+    Some(
+        PythonEnvironmentBuilder::new(Some(PythonEnvironmentKind::Pipenv))
+            .executable(Some(norm_case(python_exe)))
+            .version(version::from_creator_for_virtual_env(venv))
+            .prefix(Some(norm_case(venv)))
+            .project(Some(project_dir.to_path_buf()))
+            .requires_python(get_required_python_version(project_dir, env_vars))
+            .symlinks(Some(find_executables(&bin_dir)))
+            .build(),
+    )
+}
+
 pub struct PipEnv {
     env_vars: EnvVariables,
     pipenv_executable: Arc<RwLock<Option<PathBuf>>>,
+    workspace_directories: Arc<RwLock<Vec<PathBuf>>>,
 }
 
 impl PipEnv {
@@ -409,8 +574,18 @@ impl PipEnv {
         PipEnv {
             env_vars: EnvVariables::from(environment),
             pipenv_executable: Arc::new(RwLock::new(None)),
+            workspace_directories: Arc::new(RwLock::new(vec![])),
         }
     }
+
+    /// Finds the centralized pipenv environment created for the given project folder.
+    pub fn find_for_project(&self, project: &Path) -> Option<PythonEnvironment> {
+        let project = norm_case(project);
+        list_workspace_environments(std::slice::from_ref(&project), &self.env_vars)
+            .into_iter()
+            .chain(list_environments(&self.env_vars))
+            .find(|env| env.project.as_ref() == Some(&project))
+    }
 }
 
 impl Locator for PipEnv {
@@ -422,6 +597,13 @@ impl Locator for PipEnv {
         if let Some(exe) = &config.pipenv_executable {
             self.pipenv_executable.write().unwrap().replace(exe.clone());
         }
+        if let Some(workspace_directories) = &config.workspace_directories {
+            let mut ws_dirs = self.workspace_directories.write().unwrap();
+            ws_dirs.clear();
+            if !workspace_directories.is_empty() {
+                ws_dirs.extend(workspace_directories.clone());
+            }
+        }
     }
 
     fn supported_categories(&self) -> Vec<PythonEnvironmentKind> {
@@ -434,7 +616,7 @@ impl Locator for PipEnv {
         }
         // Project path is optional - centralized pipenv envs may have a .project file
         // pointing to a project that no longer exists
-        let project_path = get_pipenv_project(env);
+        let project_path = get_pipenv_project(env, &self.env_vars);
         trace!(
             "Pipenv: Building environment for {:?}, project: {:?}",
             env.executable,
@@ -460,12 +642,16 @@ impl Locator for PipEnv {
                 version = version::from_creator_for_virtual_env(prefix);
             }
         }
+        let requires_python = project_path
+            .as_ref()
+            .and_then(|project| get_required_python_version(project, &self.env_vars));
         Some(
             PythonEnvironmentBuilder::new(Some(PythonEnvironmentKind::Pipenv))
                 .executable(Some(env.executable.clone()))
                 .version(version)
                 .prefix(prefix)
                 .project(project_path)
+                .requires_python(requires_python)
                 .symlinks(Some(symlinks))
                 .build(),
         )
@@ -480,7 +666,14 @@ impl Locator for PipEnv {
         }
 
         // Then discover and report pipenv environments
-        let environments = list_environments(&self.env_vars);
+        let mut environments = list_environments(&self.env_vars);
+        // Environments of the workspace folders, even if not found in the centralized virtualenvs dir.
+        let workspace_dirs = self.workspace_directories.read().unwrap().clone();
+        for env in list_workspace_environments(&workspace_dirs, &self.env_vars) {
+            if !environments.iter().any(|e| e.prefix == env.prefix) {
+                environments.push(env);
+            }
+        }
         for env in environments {
             reporter.report_environment(&env);
         }
@@ -506,6 +699,8 @@ mod tests {
         EnvVariables {
             pipenv_max_depth: 3,
             pipenv_pipfile: "Pipfile".to_string(),
+            pipenv_custom_venv_name: None,
+            pipenv_venv_in_project: None,
             home,
             xdg_data_home: None,
             workon_home: None,
@@ -545,13 +740,15 @@ mod tests {
         };
 
         // Validate helper infers project
-        let inferred = get_pipenv_project(&env).expect("expected project path");
+        let inferred =
+            get_pipenv_project(&env, &create_test_env_vars(None)).expect("expected project path");
         assert_eq!(inferred, norm_case(project_dir.clone()));
 
         // Validate locator populates project
         let locator = PipEnv {
             env_vars: create_test_env_vars(None),
             pipenv_executable: Arc::new(RwLock::new(None)),
+            workspace_directories: Arc::new(RwLock::new(vec![])),
         };
         let result = locator
             .try_from(&env)
@@ -607,6 +804,8 @@ mod tests {
         let env_vars = EnvVariables {
             pipenv_max_depth: 3,
             pipenv_pipfile: "Pipfile".to_string(),
+            pipenv_custom_venv_name: None,
+            pipenv_venv_in_project: None,
             home: Some(temp_home.clone()),
             xdg_data_home: None,
             workon_home: None,
@@ -629,6 +828,7 @@ mod tests {
         let locator = PipEnv {
             env_vars,
             pipenv_executable: Arc::new(RwLock::new(None)),
+            workspace_directories: Arc::new(RwLock::new(vec![])),
         };
         let result = locator
             .try_from(&env)
@@ -676,6 +876,8 @@ mod tests {
         let env_vars = EnvVariables {
             pipenv_max_depth: 3,
             pipenv_pipfile: "Pipfile".to_string(),
+            pipenv_custom_venv_name: None,
+            pipenv_venv_in_project: None,
             home: Some(temp_home.clone()),
             xdg_data_home: None,
             workon_home: None,
@@ -696,6 +898,7 @@ mod tests {
         let locator = PipEnv {
             env_vars,
             pipenv_executable: Arc::new(RwLock::new(None)),
+            workspace_directories: Arc::new(RwLock::new(vec![])),
         };
         let result = locator
             .try_from(&env)
@@ -742,6 +945,8 @@ mod tests {
         let env_vars = EnvVariables {
             pipenv_max_depth: 3,
             pipenv_pipfile: "Pipfile".to_string(),
+            pipenv_custom_venv_name: None,
+            pipenv_venv_in_project: None,
             home: Some(temp_home.clone()),
             xdg_data_home: None,
             workon_home: None,
@@ -762,6 +967,7 @@ mod tests {
         let locator = PipEnv {
             env_vars,
             pipenv_executable: Arc::new(RwLock::new(None)),
+            workspace_directories: Arc::new(RwLock::new(vec![])),
         };
         let result = locator
             .try_from(&env)
@@ -792,4 +998,144 @@ mod tests {
         assert!(!PIPENV_ENV_NAME_PATTERN.is_match("myproject-abcdefg")); // hash too short (7 chars)
         assert!(!PIPENV_ENV_NAME_PATTERN.is_match("-AbC12xYz")); // no project name
     }
+
+    #[test]
+    fn central_pipfile_and_custom_venv_name() {
+        // Monorepo with a central Pipfile, pipenv is run from the service folders.
+        let temp_home = unique_temp_dir();
+        let repo_dir = temp_home.join("repo");
+        let venv_dir = repo_dir.join(".venv");
+        let bin_dir = if cfg!(windows) {
+            venv_dir.join("Scripts")
+        } else {
+            venv_dir.join("bin")
+        };
+        let python_exe = if cfg!(windows) {
+            bin_dir.join("python.exe")
+        } else {
+            bin_dir.join("python")
+        };
+        std::fs::create_dir_all(repo_dir.join("services").join("api")).unwrap();
+        std::fs::create_dir_all(&bin_dir).unwrap();
+        std::fs::write(&python_exe, b"").unwrap();
+        std::fs::write(
+            repo_dir.join("Pipfile"),
+            b"[requires]\npython_version = \"3.10\"\n",
+        )
+        .unwrap();
+        std::fs::write(
+            repo_dir.join("Pipfile.lock"),
+            br#"{"_meta": {"requires": {"python_version": "3.11"}}}"#,
+        )
+        .unwrap();
+
+        let mut env_vars = create_test_env_vars(Some(temp_home.clone()));
+        env_vars.pipenv_pipfile = repo_dir.join("Pipfile").to_string_lossy().to_string();
+
+        let environments = list_environments(&env_vars);
+        assert_eq!(environments.len(), 1);
+        assert_eq!(environments[0].prefix, Some(norm_case(&venv_dir)));
+        assert_eq!(environments[0].project, Some(norm_case(&repo_dir)));
+        assert_eq!(environments[0].requires_python, Some("3.11".to_string()));
+
+        // Virtual envs in the service folders are not part of the central project.
+        let service_venv = repo_dir.join("services").join("api").join(".venv");
+        let env = PythonEnv {
+            executable: service_venv.join("bin").join("python"),
+            prefix: Some(service_venv),
+            version: None,
+            symlinks: None,
+        };
+        assert!(!is_pipenv(&env, &env_vars));
+
+        // Centralized env with a custom name & without a `.project` file.
+        let custom_venv = temp_home
+            .join(".local")
+            .join("share")
+            .join("virtualenvs")
+            .join("api-env");
+        std::fs::create_dir_all(custom_venv.join("bin")).unwrap();
+        env_vars.pipenv_custom_venv_name = Some("api-env".to_string());
+        let env = PythonEnv {
+            executable: custom_venv.join("bin").join("python"),
+            prefix: Some(custom_venv),
+            version: None,
+            symlinks: None,
+        };
+        assert!(is_pipenv(&env, &env_vars));
+        assert_eq!(
+            get_pipenv_project(&env, &env_vars),
+            Some(norm_case(&repo_dir))
+        );
+
+        std::fs::remove_dir_all(&temp_home).ok();
+    }
+
+    #[test]
+    fn venv_in_project_disabled() {
+        let project_dir = unique_temp_dir();
+        let venv_dir = project_dir.join(".venv");
+        std::fs::create_dir_all(venv_dir.join("bin")).unwrap();
+        std::fs::write(
+            project_dir.join("Pipfile"),
+            b"[requires]\npython_version = \"3.12\"\n",
+        )
+        .unwrap();
+        let env = PythonEnv {
+            executable: venv_dir.join("bin").join("python"),
+            prefix: Some(venv_dir),
+            version: None,
+            symlinks: None,
+        };
+
+        let mut env_vars = create_test_env_vars(None);
+        assert!(is_pipenv(&env, &env_vars));
+        assert_eq!(
+            get_required_python_version(&project_dir, &env_vars),
+            Some("3.12".to_string())
+        );
+
+        env_vars.pipenv_venv_in_project = Some(false);
+        assert!(!is_pipenv(&env, &env_vars));
+
+        std::fs::remove_dir_all(&project_dir).ok();
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn find_centralized_env_of_workspace_using_generated_name() {
+        let temp_home = unique_temp_dir();
+        let workspace = temp_home.join("projects").join("my app");
+        std::fs::create_dir_all(&workspace).unwrap();
+        std::fs::write(workspace.join("Pipfile"), b"[[source]]\n").unwrap();
+        // No `.project` file in the environment.
+        let name = generate_env_name("my app", &workspace.join("Pipfile"));
+        assert!(name.starts_with("my_app-"));
+        let venv_dir = temp_home
+            .join(".local")
+            .join("share")
+            .join("virtualenvs")
+            .join(name);
+        std::fs::create_dir_all(venv_dir.join("bin")).unwrap();
+        std::fs::write(venv_dir.join("bin").join("python"), b"").unwrap();
+
+        let locator = PipEnv {
+            env_vars: create_test_env_vars(Some(temp_home.clone())),
+            pipenv_executable: Arc::new(RwLock::new(None)),
+            workspace_directories: Arc::new(RwLock::new(vec![workspace.clone()])),
+        };
+        let reporter = pet_reporter::collect::create_reporter();
+        locator.find(&reporter);
+
+        let environments = reporter.environments.lock().unwrap().clone();
+        assert_eq!(environments.len(), 1);
+        assert_eq!(environments[0].prefix, Some(norm_case(&venv_dir)));
+        assert_eq!(environments[0].project, Some(norm_case(&workspace)));
+        assert_eq!(
+            locator.find_for_project(&workspace).unwrap().prefix,
+            Some(norm_case(&venv_dir))
+        );
+
+        std::fs::remove_dir_all(&temp_home).ok();
+    }
 }
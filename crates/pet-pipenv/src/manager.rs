@@ -3,21 +3,35 @@
 
 use log::trace;
 use pet_core::manager::{EnvManager, EnvManagerType};
-use std::{env, path::PathBuf};
+use pet_fs::path::resolve_any_symlink;
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+};
 
 use crate::env_variables::EnvVariables;
 
 #[derive(Clone, PartialEq, Eq, Debug)]
 pub struct PipenvManager {
     pub executable: PathBuf,
+    pub version: Option<String>,
 }
 
 impl PipenvManager {
+    fn new(executable: PathBuf) -> Self {
+        let version = get_version_from_dist_info(&executable);
+        trace!("Pipenv {:?} has version {:?}", executable, version);
+        PipenvManager {
+            executable,
+            version,
+        }
+    }
+
     pub fn find(executable: Option<PathBuf>, env_variables: &EnvVariables) -> Option<Self> {
         // If an explicit executable path is provided, check if it exists
         if let Some(executable) = executable {
             if executable.is_file() {
-                return Some(PipenvManager { executable });
+                return Some(PipenvManager::new(executable));
             }
         }
 
@@ -66,7 +80,7 @@ impl PipenvManager {
 
             for executable in search_paths {
                 if executable.is_file() {
-                    return Some(PipenvManager { executable });
+                    return Some(PipenvManager::new(executable));
                 }
             }
 
@@ -75,12 +89,12 @@ impl PipenvManager {
                 for each in env::split_paths(env_path) {
                     let executable = each.join("pipenv");
                     if executable.is_file() {
-                        return Some(PipenvManager { executable });
+                        return Some(PipenvManager::new(executable));
                     }
                     if std::env::consts::OS == "windows" {
                         let executable = each.join("pipenv.exe");
                         if executable.is_file() {
-                            return Some(PipenvManager { executable });
+                            return Some(PipenvManager::new(executable));
                         }
                     }
                 }
@@ -94,8 +108,107 @@ impl PipenvManager {
     pub fn to_manager(&self) -> EnvManager {
         EnvManager {
             executable: self.executable.clone(),
-            version: None,
+            version: self.version.clone(),
             tool: EnvManagerType::Pipenv,
         }
     }
 }
+
+/// Gets the version of pipenv from the `pipenv-<version>.dist-info` directory in the `site-packages`
+/// of the environment pipenv is installed in (pipx venv, user site, Homebrew `libexec`, etc.),
+/// this way pipenv need not be spawned.
+fn get_version_from_dist_info(executable: &Path) -> Option<String> {
+    // Homebrew & pipx install symlinks to the executable in the environment.
+    let executable = resolve_any_symlink(&executable).unwrap_or(executable.to_path_buf());
+    let prefix = executable.parent()?.parent()?;
+    let mut site_packages = vec![prefix.join("Lib").join("site-packages")];
+    if let Ok(entries) = fs::read_dir(prefix.join("lib")) {
+        site_packages.extend(
+            entries
+                .filter_map(Result::ok)
+                .map(|entry| entry.path())
+                .filter(|path| {
+                    path.file_name()
+                        .and_then(|name| name.to_str())
+                        .is_some_and(|name| name.starts_with("python"))
+                })
+                .map(|path| path.join("site-packages")),
+        );
+    }
+    site_packages.iter().find_map(|site_packages| {
+        fs::read_dir(site_packages)
+            .ok()?
+            .filter_map(Result::ok)
+            .find_map(|entry| get_version_from_dist_info_name(entry.file_name().to_str()?))
+    })
+}
+
+/// Sample `pipenv-2024.0.1.dist-info`
+fn get_version_from_dist_info_name(name: &str) -> Option<String> {
+    let version = name.strip_prefix("pipenv-")?.strip_suffix(".dist-info")?;
+    if version.starts_with(|c: char| c.is_ascii_digit()) {
+        Some(version.to_string())
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn version_from_dist_info_name() {
+        assert_eq!(
+            get_version_from_dist_info_name("pipenv-2024.0.1.dist-info"),
+            Some("2024.0.1".to_string())
+        );
+        assert_eq!(
+            get_version_from_dist_info_name("pipenv-2024.0.1.data"),
+            None
+        );
+        assert_eq!(
+            get_version_from_dist_info_name("pipenv_extras-1.0.dist-info"),
+            None
+        );
+        assert_eq!(
+            get_version_from_dist_info_name("virtualenv-20.26.3.dist-info"),
+            None
+        );
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn version_from_site_packages_of_pipx_venv() {
+        let venv = std::env::temp_dir().join(format!(
+            "pet_pipenv_manager_test_{}",
+            std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .unwrap()
+                .as_nanos()
+        ));
+        let site_packages = venv.join("lib").join("python3.12").join("site-packages");
+        fs::create_dir_all(site_packages.join("pipenv-2024.4.0.dist-info")).unwrap();
+        fs::create_dir_all(site_packages.join("virtualenv-20.26.3.dist-info")).unwrap();
+        fs::create_dir_all(venv.join("bin")).unwrap();
+        fs::write(venv.join("bin").join("pipenv"), b"").unwrap();
+
+        let manager = PipenvManager::find(
+            Some(venv.join("bin").join("pipenv")),
+            &EnvVariables {
+                pipenv_max_depth: 3,
+                pipenv_pipfile: "Pipfile".to_string(),
+                pipenv_custom_venv_name: None,
+                pipenv_venv_in_project: None,
+                home: None,
+                workon_home: None,
+                xdg_data_home: None,
+                path: None,
+            },
+        )
+        .unwrap();
+        assert_eq!(manager.to_manager().version, Some("2024.4.0".to_string()));
+
+        fs::remove_dir_all(&venv).ok();
+    }
+}
@@ -0,0 +1,65 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT License.
+
+use base64::{engine::general_purpose, Engine as _};
+use lazy_static::lazy_static;
+use regex::Regex;
+use sha2::{Digest, Sha256};
+use std::path::Path;
+
+lazy_static! {
+    static ref SANITIZE_NAME: Regex = Regex::new(r#"[ &$`!*@"()\[\]\\\r\n\t]"#)
+        .expect("Error generating RegEx for pipenv virtualenv name sanitizer");
+}
+
+// Source from `Project.virtualenv_name` in https://github.com/pypa/pipenv/blob/main/pipenv/project.py
+/// Name of the centralized virtual env created by pipenv for a project, i.e. `<sanitized project name>-<hash>`.
+/// The project name is the name of the directory containing the Pipfile.
+pub fn generate_env_name(project_name: &str, pipfile: &Path) -> String {
+    // sanitized = re.sub(r'[ &$`!*@"()\[\]\\\r\n\t]', "_", name)[0:42]
+    // hash = hashlib.sha256(location.encode()).digest()[:6]
+    // encoded_hash = base64.urlsafe_b64encode(hash).decode()[:8]
+    let sanitized_name = SANITIZE_NAME
+        .replace_all(project_name, "_")
+        .chars()
+        .take(42)
+        .collect::<String>();
+    let mut hasher = Sha256::new();
+    hasher.update(pipfile.to_string_lossy().as_bytes());
+    let h_bytes = hasher.finalize();
+    let h_str = general_purpose::URL_SAFE
+        .encode(&h_bytes[..6])
+        .chars()
+        .take(8)
+        .collect::<String>();
+    format!("{sanitized_name}-{h_str}")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    #[cfg(unix)]
+    fn test_hash_generation() {
+        assert_eq!(
+            generate_env_name(
+                "pipenv-demo",
+                Path::new("/Users/donjayamanne/temp/pipenv-demo/Pipfile")
+            ),
+            "pipenv-demo-1kwwRQGk"
+        );
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_hash_generation_with_special_characters() {
+        assert_eq!(
+            generate_env_name(
+                "My Project",
+                Path::new("/home/user/projects/My Project/Pipfile")
+            ),
+            "My_Project-NDPwmiLr"
+        );
+    }
+}
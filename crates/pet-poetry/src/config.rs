@@ -19,81 +19,86 @@ pub struct Config {
     pub virtualenvs_path: PathBuf,
     pub cache_dir: Option<PathBuf>,
     pub file: Option<PathBuf>,
+    /// Whether virtual environments are created using the active Python instead of the Python Poetry was installed with.
+    /// Maps to `virtualenvs.prefer-active-python` (Poetry 1) & the inverse of `virtualenvs.use-poetry-python` (Poetry 2).
+    pub virtualenvs_prefer_active_python: Option<bool>,
+    pub virtualenvs_options: VirtualenvsOptions,
+}
+
+/// Maps to `virtualenvs.options.*` in poetry
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct VirtualenvsOptions {
+    pub always_copy: Option<bool>,
+    pub no_pip: Option<bool>,
+    pub system_site_packages: Option<bool>,
+}
+
+impl VirtualenvsOptions {
+    fn merge(self, other: VirtualenvsOptions) -> Self {
+        VirtualenvsOptions {
+            always_copy: other.always_copy.or(self.always_copy),
+            no_pip: other.no_pip.or(self.no_pip),
+            system_site_packages: other.system_site_packages.or(self.system_site_packages),
+        }
+    }
 }
 
 impl Config {
-    fn new(
-        file: Option<PathBuf>,
-        virtualenvs_path: PathBuf,
-        cache_dir: Option<PathBuf>,
-        virtualenvs_in_project: Option<bool>,
-    ) -> Self {
+    fn new(file: Option<PathBuf>, cfg: ConfigToml, virtualenvs_path: PathBuf) -> Self {
         trace!(
             "Poetry config file => {:?}, virtualenv.path => {:?}, cache_dir => {:?}, virtualenvs_in_project => {:?}",
             file,
             virtualenvs_path,
-            cache_dir,
-            virtualenvs_in_project
+            cfg.cache_dir,
+            cfg.virtualenvs_in_project
         );
         Config {
             file,
             virtualenvs_path,
-            cache_dir,
-            virtualenvs_in_project,
+            cache_dir: cfg.cache_dir,
+            virtualenvs_in_project: cfg.virtualenvs_in_project,
+            virtualenvs_prefer_active_python: cfg.virtualenvs_prefer_active_python,
+            virtualenvs_options: cfg.virtualenvs_options,
         }
     }
+    /// Global configuration, i.e. the `config.toml` in the Poetry config directory & the env variables.
     pub fn find_global(env: &EnvVariables) -> Option<Self> {
-        let file = find_config_file(env);
-        create_config(file, env)
+        let files = find_config_file(env).into_iter().collect::<Vec<_>>();
+        create_config(&files, env)
     }
+    /// Configuration in the `poetry.toml` of the project & the env variables, ignoring the global configuration.
     pub fn find_local(path: &Path, env: &EnvVariables) -> Option<Self> {
         let file = path.join("poetry.toml");
         if file.is_file() {
-            create_config(Some(file), env)
+            create_config(&[file], env)
         } else {
             None
         }
     }
+    /// Configuration used by Poetry for the project, in order of precedence
+    /// env variables > `poetry.toml` of the project > global `config.toml` > defaults.
+    pub fn find_project(path: &Path, env: &EnvVariables) -> Option<Self> {
+        let mut files = find_config_file(env).into_iter().collect::<Vec<_>>();
+        let file = path.join("poetry.toml");
+        if file.is_file() {
+            files.push(file);
+        }
+        create_config(&files, env)
+    }
 }
 
-fn create_config(file: Option<PathBuf>, env: &EnvVariables) -> Option<Config> {
-    if let Some(file) = &file {
+/// Merges the config files (later files take precedence) & then the env variables.
+/// The last of the config files is the file of the resulting config.
+fn create_config(files: &[PathBuf], env: &EnvVariables) -> Option<Config> {
+    let mut cfg = ConfigToml::default();
+    for file in files {
         trace!("Parsing Poetry config file => {:?}", file);
-    }
-
-    let cfg = file.clone().and_then(|f| parse(&f));
-    let cache_dir = get_cache_dir(&cfg, env);
-    let virtualenvs_path_from_env_var = env
-        .poetry_virtualenvs_path
-        .clone()
-        .map(|p| resolve_virtualenvs_path(&p, &cache_dir));
-
-    if let Some(virtualenvs_path) = &cfg.clone().and_then(|cfg| cfg.virtualenvs_path) {
-        let virtualenvs_path = resolve_virtualenvs_path(&virtualenvs_path.clone(), &cache_dir);
-
-        return Some(Config::new(
-            file.clone(),
-            // Give preference to the virtualenvs path from the env var
-            virtualenvs_path_from_env_var.unwrap_or(virtualenvs_path.clone()),
-            cache_dir,
-            cfg.and_then(|cfg| cfg.virtualenvs_in_project),
-        ));
-    }
-
-    // Give preference to the virtualenvs path from the env var
-    if let Some(virtualenvs_path_from_env_var) = virtualenvs_path_from_env_var {
-        if virtualenvs_path_from_env_var.exists() {
-            return Some(Config::new(
-                file,
-                virtualenvs_path_from_env_var,
-                cache_dir,
-                cfg.and_then(|cfg| cfg.virtualenvs_in_project),
-            ));
+        if let Some(file_cfg) = parse(file) {
+            cfg = cfg.merge(file_cfg);
         }
     }
-
-    cache_dir
-        .map(|cache_dir| Config::new(file, cache_dir.join("virtualenvs"), Some(cache_dir), None))
+    cfg.merge(ConfigToml::from(env))
+        .into_config(files.last().cloned())
 }
 
 /// Replaces {cache-dir} in virtualenvs path with the cache dir
@@ -119,23 +124,9 @@ fn resolve_virtualenvs_path(virtualenvs_path: &Path, cache_dir: &Option<PathBuf>
     trace!("Poetry virtualenvs path => {:?}", virtualenvs_path);
     virtualenvs_path.to_path_buf()
 }
-/// Maps to DEFAULT_CACHE_DIR in poetry
-fn get_cache_dir(cfg: &Option<ConfigToml>, env: &EnvVariables) -> Option<PathBuf> {
-    // Cache dir in env variables takes precedence
-    if let Some(cache_dir) = env.poetry_cache_dir.clone() {
-        if cache_dir.is_dir() {
-            trace!("Poetry cache dir from env variable: {:?}", cache_dir);
-            return Some(cache_dir);
-        }
-    }
-    // Check cache dir in config.
-    if let Some(cache_dir) = cfg.as_ref().and_then(|cfg| cfg.cache_dir.clone()) {
-        if cache_dir.is_dir() {
-            trace!("Poetry cache dir from config: {:?}", cache_dir);
-            return Some(cache_dir);
-        }
-    }
 
+/// Maps to DEFAULT_CACHE_DIR in poetry
+fn get_default_cache_dir() -> Option<PathBuf> {
     let default_cache_dir = Platformdirs::new(_APP_NAME.into(), false).user_cache_path();
     trace!("Poetry cache (default): {:?}", default_cache_dir);
     default_cache_dir
@@ -162,11 +153,60 @@ pub fn find_config_file(env: &EnvVariables) -> Option<PathBuf> {
     }
 }
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+/// Poetry settings from a single source, i.e. a config file or the env variables.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
 struct ConfigToml {
     virtualenvs_in_project: Option<bool>,
     cache_dir: Option<PathBuf>,
     virtualenvs_path: Option<PathBuf>,
+    virtualenvs_prefer_active_python: Option<bool>,
+    virtualenvs_options: VirtualenvsOptions,
+}
+
+impl ConfigToml {
+    /// Settings in `other` take precedence.
+    fn merge(self, other: ConfigToml) -> Self {
+        ConfigToml {
+            virtualenvs_in_project: other.virtualenvs_in_project.or(self.virtualenvs_in_project),
+            cache_dir: other.cache_dir.or(self.cache_dir),
+            virtualenvs_path: other.virtualenvs_path.or(self.virtualenvs_path),
+            virtualenvs_prefer_active_python: other
+                .virtualenvs_prefer_active_python
+                .or(self.virtualenvs_prefer_active_python),
+            virtualenvs_options: self.virtualenvs_options.merge(other.virtualenvs_options),
+        }
+    }
+
+    /// Resolves the settings that are not set to the defaults of poetry.
+    fn into_config(mut self, file: Option<PathBuf>) -> Option<Config> {
+        self.cache_dir = self.cache_dir.clone().or_else(get_default_cache_dir);
+
+        // Defaults to `{cache-dir}/virtualenvs`
+        let virtualenvs_path = match &self.virtualenvs_path {
+            Some(virtualenvs_path) => resolve_virtualenvs_path(virtualenvs_path, &self.cache_dir),
+            None => self.cache_dir.clone()?.join("virtualenvs"),
+        };
+        Some(Config::new(file, self, virtualenvs_path))
+    }
+}
+
+impl From<&EnvVariables> for ConfigToml {
+    /// Settings in the `POETRY_<SETTING>` env variables.
+    fn from(env: &EnvVariables) -> Self {
+        ConfigToml {
+            virtualenvs_in_project: env.poetry_virtualenvs_in_project,
+            cache_dir: env.poetry_cache_dir.clone(),
+            virtualenvs_path: env.poetry_virtualenvs_path.clone(),
+            virtualenvs_prefer_active_python: env
+                .poetry_virtualenvs_prefer_active_python
+                .or(env.poetry_virtualenvs_use_poetry_python.map(|v| !v)),
+            virtualenvs_options: VirtualenvsOptions {
+                always_copy: env.poetry_virtualenvs_options_always_copy,
+                no_pip: env.poetry_virtualenvs_options_no_pip,
+                system_site_packages: env.poetry_virtualenvs_options_system_site_packages,
+            },
+        }
+    }
 }
 
 fn parse(file: &Path) -> Option<ConfigToml> {
@@ -177,48 +217,34 @@ fn parse(file: &Path) -> Option<ConfigToml> {
 }
 
 fn parse_contents(contents: &str) -> Option<ConfigToml> {
-    let mut virtualenvs_path = None;
-    let mut cache_dir = None;
-    let mut virtualenvs_in_project = None;
     match toml::from_str::<toml::Value>(contents) {
         Ok(value) => {
-            if let Some(virtualenvs) = value.get("virtualenvs") {
-                if let Some(path) = virtualenvs.get("path") {
-                    // Can contain invalid toml, hence make no assumptions
-                    // virtualenvs.in-project = null
-                    // https://github.com/python-poetry/poetry/blob/5bab98c9500f1050c6bb6adfb55580a23173f18d/docs/configuration.md#L56
-                    if path.is_str() {
-                        virtualenvs_path = path.as_str().map(|s| s.trim()).map(PathBuf::from);
-                    }
-                }
-                if let Some(in_project) = virtualenvs.get("in-project") {
-                    // Can contain invalid toml, hence make no assumptions
-                    // virtualenvs.in-project = null
-                    // https://github.com/python-poetry/poetry/blob/5bab98c9500f1050c6bb6adfb55580a23173f18d/docs/configuration.md#L56
-                    if in_project.is_bool() {
-                        virtualenvs_in_project = in_project.as_bool();
-                    }
-                }
-            }
-            if let Some(value) = value.get("cache-dir") {
-                // Can contain invalid toml, hence make no assumptions
-                // virtualenvs.in-project = null
-                // https://github.com/python-poetry/poetry/blob/5bab98c9500f1050c6bb6adfb55580a23173f18d/docs/configuration.md#L56
-                if value.is_str() {
-                    cache_dir = value.as_str().map(|s| s.trim()).map(PathBuf::from);
-
-                    if let Some(cache_dir) = &cache_dir {
-                        if virtualenvs_path.is_none() {
-                            virtualenvs_path = Some(cache_dir.join("virtualenvs"));
-                        }
-                    }
-                }
-            }
+            // Can contain invalid toml, hence make no assumptions
+            // virtualenvs.in-project = null
+            // https://github.com/python-poetry/poetry/blob/5bab98c9500f1050c6bb6adfb55580a23173f18d/docs/configuration.md#L56
+            let get_bool = |value: Option<&toml::Value>| value.and_then(|v| v.as_bool());
+            let get_path = |value: Option<&toml::Value>| {
+                value
+                    .and_then(|v| v.as_str())
+                    .map(|s| s.trim())
+                    .map(PathBuf::from)
+            };
+            let virtualenvs = value.get("virtualenvs");
+            let virtualenvs_get = |key: &str| virtualenvs.and_then(|v| v.get(key));
+            let options = virtualenvs_get("options");
+            let options_get = |key: &str| get_bool(options.and_then(|v| v.get(key)));
 
             Some(ConfigToml {
-                virtualenvs_in_project,
-                virtualenvs_path,
-                cache_dir,
+                virtualenvs_in_project: get_bool(virtualenvs_get("in-project")),
+                virtualenvs_path: get_path(virtualenvs_get("path")),
+                cache_dir: get_path(value.get("cache-dir")),
+                virtualenvs_prefer_active_python: get_bool(virtualenvs_get("prefer-active-python"))
+                    .or(get_bool(virtualenvs_get("use-poetry-python")).map(|v| !v)),
+                virtualenvs_options: VirtualenvsOptions {
+                    always_copy: options_get("always-copy"),
+                    no_pip: options_get("no-pip"),
+                    system_site_packages: options_get("system-site-packages"),
+                },
             })
         }
         Err(e) => {
@@ -325,8 +351,68 @@ some-other-value = 1234
 cache-dir = "/path/to/cache/directory"
 "#;
         assert_eq!(
-            parse_contents(cfg).unwrap().virtualenvs_path,
-            Some(PathBuf::from("/path/to/cache/directory/virtualenvs"))
+            parse_contents(cfg)
+                .unwrap()
+                .into_config(None)
+                .unwrap()
+                .virtualenvs_path,
+            PathBuf::from("/path/to/cache/directory/virtualenvs")
+        );
+    }
+
+    #[test]
+    fn parse_virtualenvs_options_in_poetry_toml() {
+        let cfg = r#"
+[virtualenvs]
+prefer-active-python = true
+
+[virtualenvs.options]
+always-copy = true
+no-pip = false
+"#;
+        let cfg = parse_contents(cfg).unwrap();
+        assert_eq!(cfg.virtualenvs_prefer_active_python, Some(true));
+        assert_eq!(
+            cfg.virtualenvs_options,
+            VirtualenvsOptions {
+                always_copy: Some(true),
+                no_pip: Some(false),
+                system_site_packages: None,
+            }
         );
+
+        // Poetry 2 replaced `prefer-active-python` with `use-poetry-python`.
+        let cfg = r#"
+virtualenvs.use-poetry-python = true
+virtualenvs.options.system-site-packages = true
+"#;
+        let cfg = parse_contents(cfg).unwrap();
+        assert_eq!(cfg.virtualenvs_prefer_active_python, Some(false));
+        assert_eq!(cfg.virtualenvs_options.system_site_packages, Some(true));
+    }
+
+    #[test]
+    fn later_settings_take_precedence() {
+        let global = parse_contents(
+            r#"
+cache-dir = "/global/cache"
+virtualenvs.in-project = true
+virtualenvs.options.no-pip = true
+"#,
+        )
+        .unwrap();
+        let local = parse_contents(
+            r#"
+virtualenvs.in-project = false
+virtualenvs.path = "{cache-dir}/envs"
+"#,
+        )
+        .unwrap();
+
+        let config = global.merge(local).into_config(None).unwrap();
+        assert_eq!(config.virtualenvs_in_project, Some(false));
+        assert_eq!(config.cache_dir, Some(PathBuf::from("/global/cache")));
+        assert_eq!(config.virtualenvs_path, PathBuf::from("/global/cache/envs"));
+        assert_eq!(config.virtualenvs_options.no_pip, Some(true));
     }
 }
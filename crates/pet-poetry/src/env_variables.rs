@@ -23,6 +23,16 @@ pub struct EnvVariables {
     pub poetry_cache_dir: Option<PathBuf>,
     /// Maps to env var `POETRY_VIRTUALENVS_IN_PROJECT`
     pub poetry_virtualenvs_in_project: Option<bool>,
+    /// Maps to env var `POETRY_VIRTUALENVS_PREFER_ACTIVE_PYTHON`
+    pub poetry_virtualenvs_prefer_active_python: Option<bool>,
+    /// Maps to env var `POETRY_VIRTUALENVS_USE_POETRY_PYTHON`
+    pub poetry_virtualenvs_use_poetry_python: Option<bool>,
+    /// Maps to env var `POETRY_VIRTUALENVS_OPTIONS_ALWAYS_COPY`
+    pub poetry_virtualenvs_options_always_copy: Option<bool>,
+    /// Maps to env var `POETRY_VIRTUALENVS_OPTIONS_NO_PIP`
+    pub poetry_virtualenvs_options_no_pip: Option<bool>,
+    /// Maps to env var `POETRY_VIRTUALENVS_OPTIONS_SYSTEM_SITE_PACKAGES`
+    pub poetry_virtualenvs_options_system_site_packages: Option<bool>,
     /// Maps to env var `PATH`
     pub path: Option<String>,
 }
@@ -42,6 +52,7 @@ impl EnvVariables {
                 poetry_home = Some(PathBuf::from(poetry_home_value));
             }
         }
+        let get_bool = |key: &str| get_bool_env_var(env, key);
 
         EnvVariables {
             home,
@@ -57,10 +68,25 @@ impl EnvVariables {
             poetry_config_dir: env
                 .get_env_var("POETRY_CONFIG_DIR".to_string())
                 .map(PathBuf::from),
-            poetry_virtualenvs_in_project: env
-                .get_env_var("POETRY_VIRTUALENVS_IN_PROJECT".to_string())
-                .map(|v| v == "1" || v.to_lowercase() == "true"),
+            poetry_virtualenvs_in_project: get_bool("POETRY_VIRTUALENVS_IN_PROJECT"),
+            poetry_virtualenvs_prefer_active_python: get_bool(
+                "POETRY_VIRTUALENVS_PREFER_ACTIVE_PYTHON",
+            ),
+            poetry_virtualenvs_use_poetry_python: get_bool("POETRY_VIRTUALENVS_USE_POETRY_PYTHON"),
+            poetry_virtualenvs_options_always_copy: get_bool(
+                "POETRY_VIRTUALENVS_OPTIONS_ALWAYS_COPY",
+            ),
+            poetry_virtualenvs_options_no_pip: get_bool("POETRY_VIRTUALENVS_OPTIONS_NO_PIP"),
+            poetry_virtualenvs_options_system_site_packages: get_bool(
+                "POETRY_VIRTUALENVS_OPTIONS_SYSTEM_SITE_PACKAGES",
+            ),
             poetry_home,
         }
     }
 }
+
+/// Poetry treats `true` & `1` as true, anything else is false.
+fn get_bool_env_var(env: &dyn Environment, key: &str) -> Option<bool> {
+    env.get_env_var(key.to_string())
+        .map(|v| v == "1" || v.to_lowercase() == "true")
+}
@@ -93,20 +93,22 @@ fn list_all_environments_from_project_config(
     path: &Path,
     env: &EnvVariables,
 ) -> Option<Vec<PathBuf>> {
-    let local = Config::find_local(path, env);
-    trace!("Poetry Project ({:?}) config file => {:?}", path, local);
+    let config = Config::find_project(path, env);
+    trace!("Poetry Project ({:?}) config => {:?}", path, config);
+    let config = config?;
     let mut envs = vec![];
 
-    if let Some(local) = &local {
-        if let Some(project_envs) = list_all_environments_from_config(local) {
+    // Environments in the global virtualenvs path are listed separately.
+    if global.as_ref().map(|global| &global.virtualenvs_path) != Some(&config.virtualenvs_path) {
+        if let Some(project_envs) = list_all_environments_from_config(&config) {
             envs.extend(project_envs);
         }
     }
 
     // Check if we're allowed to use .venv as a poetry env
     // This can be configured in global, project or env variable.
-    // Order of preference is Project (local config) > EnvVariable > Global
-    if should_use_local_venv_as_poetry_env(global, &local, env) {
+    // Order of preference is EnvVariable > Project (local config) > Global
+    if config.virtualenvs_in_project.unwrap_or_default() {
         // If virtualenvs are in the project, then look for .venv
         let venv = path.join(".venv");
         if venv.is_dir() {
@@ -116,43 +118,6 @@ fn list_all_environments_from_project_config(
     Some(envs)
 }
 
-fn should_use_local_venv_as_poetry_env(
-    global: &Option<Config>,
-    local: &Option<Config>,
-    env: &EnvVariables,
-) -> bool {
-    // Give preference to setting in local config file (project-level).
-    if let Some(poetry_virtualenvs_in_project) =
-        local.clone().and_then(|c| c.virtualenvs_in_project)
-    {
-        trace!(
-            "Poetry virtualenvs_in_project from local config file: {}",
-            poetry_virtualenvs_in_project
-        );
-        return poetry_virtualenvs_in_project;
-    }
-
-    // Then check env variable.
-    if let Some(poetry_virtualenvs_in_project) = env.poetry_virtualenvs_in_project {
-        trace!(
-            "Poetry virtualenvs_in_project from Env Variable: {}",
-            poetry_virtualenvs_in_project
-        );
-        return poetry_virtualenvs_in_project;
-    }
-
-    // Check global config setting.
-    let value = global
-        .clone()
-        .and_then(|config| config.virtualenvs_in_project)
-        .unwrap_or_default();
-    trace!(
-        "Poetry virtualenvs_in_project from global config file: {}",
-        value
-    );
-    value
-}
-
 fn list_all_environments_from_config(cfg: &Config) -> Option<Vec<PathBuf>> {
     Some(
         fs::read_dir(&cfg.virtualenvs_path)
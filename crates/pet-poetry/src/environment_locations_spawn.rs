@@ -4,7 +4,7 @@
 use lazy_static::lazy_static;
 use log::{error, trace};
 use pet_core::python_environment::PythonEnvironment;
-use pet_python_utils::executable::new_silent_command;
+use pet_python_utils::executable::{new_silent_command, output_with_timeout};
 use regex::Regex;
 use std::{path::PathBuf, time::SystemTime};
 
@@ -37,12 +37,13 @@ pub fn list_environments(
 
 fn get_environments(executable: &PathBuf, workspace_dir: &PathBuf) -> Option<Vec<PathBuf>> {
     let start = SystemTime::now();
-    let result = new_silent_command(executable)
-        .arg("env")
-        .arg("list")
-        .arg("--full-path")
-        .current_dir(workspace_dir)
-        .output();
+    let result = output_with_timeout(
+        new_silent_command(executable)
+            .arg("env")
+            .arg("list")
+            .arg("--full-path")
+            .current_dir(workspace_dir),
+    );
     trace!(
         "Executed Poetry ({}ms): {:?} env list --full-path for {:?}",
         start.elapsed().unwrap_or_default().as_millis(),
@@ -131,11 +132,12 @@ fn get_config_value(
     setting: &str,
 ) -> Option<String> {
     let start = SystemTime::now();
-    let result = std::process::Command::new(executable)
-        .arg("config")
-        .arg(setting)
-        .current_dir(workspace_dir)
-        .output();
+    let result = output_with_timeout(
+        std::process::Command::new(executable)
+            .arg("config")
+            .arg(setting)
+            .current_dir(workspace_dir),
+    );
     trace!(
         "Executed Poetry ({}ms): {executable:?} config {setting} {workspace_dir:?}",
         start.elapsed().unwrap_or_default().as_millis(),
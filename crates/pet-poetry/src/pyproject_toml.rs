@@ -49,31 +49,24 @@ fn parse(file: &Path) -> Option<PyProjectToml> {
 fn parse_contents(contents: &str, file: &Path) -> Option<PyProjectToml> {
     match toml::from_str::<toml::Value>(contents) {
         Ok(value) => {
-            let mut name = None;
-            if let Some(tool) = value.get("tool") {
-                if let Some(poetry) = tool.get("poetry") {
-                    if let Some(name_value) = poetry.get("name") {
-                        name = name_value.as_str().map(|s| s.to_string());
-                    }
-                }
-            }
-
-            match name {
-                Some(name) => Some(PyProjectToml::new(name, file.into())),
-                None => {
-                    trace!(
-                        "Poetry project name not found in {:?}, trying the new format",
-                        file
-                    );
-                    let mut name = None;
-                    if let Some(project) = value.get("project") {
-                        if let Some(name_value) = project.get("name") {
-                            name = name_value.as_str().map(|s| s.to_string());
-                        }
-                    }
-                    name.map(|name| PyProjectToml::new(name, file.into()))
-                }
+            // Poetry 2 supports PEP 621 projects, where `[project]` takes precedence over `[tool.poetry]`
+            // & `[tool.poetry]` is optional.
+            // https://python-poetry.org/docs/pyproject/#the-project-section
+            let project_name = value
+                .get("project")
+                .and_then(|project| project.get("name"))
+                .and_then(|name| name.as_str());
+            let poetry_name = value
+                .get("tool")
+                .and_then(|tool| tool.get("poetry"))
+                .and_then(|poetry| poetry.get("name"))
+                .and_then(|name| name.as_str());
+            if project_name.is_none() {
+                trace!("PEP 621 project name not found in {:?}", file);
             }
+            project_name
+                .or(poetry_name)
+                .map(|name| PyProjectToml::new(name.to_string(), file.into()))
         }
         Err(e) => {
             error!("Error parsing toml file: {:?}", e);
@@ -138,4 +131,39 @@ build-backend = "poetry.core.masonry.api"
             "poetry-demo"
         );
     }
+
+    #[test]
+    fn extract_name_from_pep_621_pyproject_toml() {
+        let cfg = r#"
+[project]
+name = "Poetry2.Demo"
+version = "0.1.0"
+requires-python = ">=3.12"
+
+[build-system]
+requires = ["poetry-core>=2.0.0,<3.0.0"]
+build-backend = "poetry.core.masonry.api"
+"#;
+        assert_eq!(
+            parse_contents(cfg, Path::new("pyproject.toml"))
+                .unwrap()
+                .name,
+            "poetry2-demo"
+        );
+
+        let cfg = r#"
+[project]
+name = "poetry2-demo"
+
+[tool.poetry]
+name = "legacy-name"
+package-mode = true
+"#;
+        assert_eq!(
+            parse_contents(cfg, Path::new("pyproject.toml"))
+                .unwrap()
+                .name,
+            "poetry2-demo"
+        );
+    }
 }
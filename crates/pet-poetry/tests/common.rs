@@ -26,6 +26,11 @@ pub fn create_env_variables(home: PathBuf, root: PathBuf) -> EnvVariables {
         poetry_home: None,
         poetry_virtualenvs_in_project: None,
         poetry_virtualenvs_path: None,
+        poetry_virtualenvs_prefer_active_python: None,
+        poetry_virtualenvs_use_poetry_python: None,
+        poetry_virtualenvs_options_always_copy: None,
+        poetry_virtualenvs_options_no_pip: None,
+        poetry_virtualenvs_options_system_site_packages: None,
     }
 }
 
@@ -123,3 +123,82 @@ fn local_config_with_specific_values() {
         PathBuf::from("/directory/virtualenvs".to_string())
     );
 }
+
+#[cfg(unix)]
+#[test]
+fn project_config_merges_global_and_local_config() {
+    use std::path::PathBuf;
+
+    use common::create_env_variables;
+    use common::resolve_test_path;
+    use pet_poetry::config::Config;
+
+    let root = resolve_test_path(&["unix", "local_config_with_values", "root_empty"]);
+    let home = resolve_test_path(&["unix", "local_config_with_values", "user_home"]);
+    let mut env = create_env_variables(home, root);
+    env.poetry_config_dir = Some(resolve_test_path(&[
+        "unix",
+        "local_config_with_values",
+        "user_home",
+        "config_dir",
+    ]));
+
+    let project_dir = resolve_test_path(&["unix", "local_config_with_values", "project_dir"]);
+    let config = Config::find_project(&project_dir, &env).unwrap();
+
+    assert_eq!(config.file, Some(project_dir.join("poetry.toml")));
+    // Local config takes precedence over the global config.
+    assert_eq!(config.virtualenvs_in_project, Some(false));
+    assert_eq!(config.cache_dir, Some(PathBuf::from("/directory")));
+    // Not in the local config, hence from the global config.
+    assert_eq!(
+        config.virtualenvs_path,
+        PathBuf::from("some/path/virtualenvs".to_string())
+    );
+}
+
+#[cfg(unix)]
+#[test]
+fn env_variables_take_precedence_over_project_config() {
+    use std::path::PathBuf;
+
+    use common::create_env_variables;
+    use common::resolve_test_path;
+    use pet_poetry::config::{Config, VirtualenvsOptions};
+
+    let root = resolve_test_path(&["unix", "project_config_with_options", "root_empty"]);
+    let home = resolve_test_path(&["unix", "project_config_with_options", "user_home"]);
+    let mut env = create_env_variables(home, root);
+    env.poetry_config_dir = Some(resolve_test_path(&[
+        "unix",
+        "local_config_with_values",
+        "user_home",
+        "config_dir",
+    ]));
+    env.poetry_cache_dir = Some(PathBuf::from("/env/cache"));
+    env.poetry_virtualenvs_in_project = Some(false);
+    env.poetry_virtualenvs_options_no_pip = Some(false);
+
+    let project_dir = resolve_test_path(&["unix", "project_config_with_options", "project_dir"]);
+    let config = Config::find_project(&project_dir, &env).unwrap();
+
+    assert_eq!(config.virtualenvs_in_project, Some(false));
+    assert_eq!(config.cache_dir, Some(PathBuf::from("/env/cache")));
+    assert_eq!(
+        config.virtualenvs_path,
+        PathBuf::from("some/path/virtualenvs".to_string())
+    );
+    assert_eq!(config.virtualenvs_prefer_active_python, Some(false));
+    assert_eq!(
+        config.virtualenvs_options,
+        VirtualenvsOptions {
+            always_copy: Some(true),
+            no_pip: Some(false),
+            system_site_packages: None,
+        }
+    );
+
+    env.poetry_virtualenvs_path = Some(PathBuf::from("{cache-dir}/envs"));
+    let config = Config::find_project(&project_dir, &env).unwrap();
+    assert_eq!(config.virtualenvs_path, PathBuf::from("/env/cache/envs"));
+}
@@ -0,0 +1,65 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT License.
+
+//! Conda distributions installed by pyenv, e.g. `versions/miniconda3-latest` or `versions/anaconda3-2024.02-1`.
+//! These are reported by the conda locator (with the conda manager), else by pyenv (with the pyenv manager)
+//! when the distribution does not have a conda executable.
+
+use crate::environments::set_version_name;
+use pet_conda::environments::CondaEnvironment;
+use pet_core::{
+    manager::EnvManager,
+    python_environment::{EnvironmentSelection, PythonEnvironment},
+    reporter::Reporter,
+    telemetry::TelemetryEvent,
+};
+use pet_fs::path::norm_case;
+use std::path::{Path, PathBuf};
+
+pub fn get_conda_distribution_environment(
+    path: &Path,
+    manager: &Option<EnvManager>,
+) -> Option<PythonEnvironment> {
+    let env = CondaEnvironment::from(path, &None)?.to_python_environment(manager.clone());
+    Some(set_version_name(env, path))
+}
+
+/// Adds the pyenv details (implementation, distribution & selections) to the root environment
+/// of a conda distribution installed by pyenv, when reported by the conda locator.
+pub struct CondaDistributionReporter<'a> {
+    reporter: &'a dyn Reporter,
+    prefix: PathBuf,
+    selections: Option<Vec<EnvironmentSelection>>,
+}
+
+impl<'a> CondaDistributionReporter<'a> {
+    pub fn new(
+        reporter: &'a dyn Reporter,
+        prefix: &Path,
+        selections: Option<Vec<EnvironmentSelection>>,
+    ) -> Self {
+        CondaDistributionReporter {
+            reporter,
+            prefix: norm_case(prefix),
+            selections,
+        }
+    }
+}
+
+impl Reporter for CondaDistributionReporter<'_> {
+    fn report_manager(&self, manager: &EnvManager) {
+        self.reporter.report_manager(manager);
+    }
+    fn report_environment(&self, env: &PythonEnvironment) {
+        if env.prefix.as_ref().map(norm_case) != Some(self.prefix.clone()) {
+            self.reporter.report_environment(env);
+            return;
+        }
+        let mut env = set_version_name(env.clone(), &self.prefix);
+        env.selections = self.selections.clone();
+        self.reporter.report_environment(&env);
+    }
+    fn report_telemetry(&self, event: &TelemetryEvent) {
+        self.reporter.report_telemetry(event);
+    }
+}
@@ -13,6 +13,8 @@ pub struct EnvVariables {
     pub path: Option<String>,
     pub pyenv_root: Option<String>,
     pub pyenv: Option<String>,
+    /// Version selected for the current shell, see `pyenv shell`.
+    pub pyenv_version: Option<String>,
     pub known_global_search_locations: Vec<PathBuf>,
 }
 
@@ -24,6 +26,7 @@ impl EnvVariables {
             path: env.get_env_var("PATH".to_string()),
             pyenv_root: env.get_env_var("PYENV_ROOT".to_string()),
             pyenv: env.get_env_var("PYENV".to_string()),
+            pyenv_version: env.get_env_var("PYENV_VERSION".to_string()),
             known_global_search_locations: env.get_know_global_search_locations(),
         }
     }
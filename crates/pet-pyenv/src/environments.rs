@@ -1,31 +1,16 @@
 // Copyright (c) Microsoft Corporation.
 // Licensed under the MIT License.
 
-use lazy_static::lazy_static;
+use crate::version_name::PyEnvVersionName;
 use pet_core::{
     arch::Architecture,
     manager::EnvManager,
     python_environment::{PythonEnvironment, PythonEnvironmentBuilder, PythonEnvironmentKind},
+    pyvenv_cfg::PyVenvCfg,
 };
 use pet_python_utils::executable::find_executables;
 use pet_python_utils::version;
-use regex::Regex;
-use std::path::Path;
-
-lazy_static! {
-    // Stable Versions = like 3.10.10
-    static ref PURE_PYTHON_VERSION: Regex = Regex::new(r"^(\d+\.\d+\.\d+)$")
-        .expect("error parsing Version regex for Python Version in pyenv");
-    // Dev Versions = like 3.10-dev
-    static ref DEV_PYTHON_VERSION: Regex = Regex::new(r"^(\d+\.\d+-.*)$")
-        .expect("error parsing Version regex for Dev Python Version in pyenv");
-    // Alpha, rc Versions = like 3.10.0a3
-    static ref BETA_PYTHON_VERSION: Regex = Regex::new(r"^(\d+\.\d+.\d+\w\d+)")
-        .expect("error parsing Version regex for Alpha Python Version in pyenv");
-    // win32 versions, rc Versions = like 3.11.0a-win32
-    static ref WIN32_PYTHON_VERSION: Regex = Regex::new(r"^(\d+\.\d+.\d+\w\d+)-win32")
-        .expect("error parsing Version regex for Win32 Python Version in pyenv");
-}
+use std::path::{Path, PathBuf};
 
 pub fn get_generic_python_environment(
     executable: &Path,
@@ -33,13 +18,14 @@ pub fn get_generic_python_environment(
     manager: &Option<EnvManager>,
 ) -> Option<PythonEnvironment> {
     let file_name = path.file_name()?.to_string_lossy().to_string();
+    let version_name = PyEnvVersionName::parse(&file_name);
     // If we can get the version from the header files, thats more accurate.
-    let version = version::from_header_files(path).or_else(|| get_version(&file_name));
-
-    let arch = if file_name.ends_with("-win32") {
-        Some(Architecture::X86)
-    } else {
-        None
+    let version = version::from_header_files(path)
+        .or_else(|| version_name.as_ref().and_then(|name| name.version.clone()));
+    let (implementation, distribution, arch) = match version_name {
+        Some(name) => (name.implementation, name.distribution, name.arch),
+        None if file_name.ends_with("-win32") => (None, None, Some(Architecture::X86)),
+        None => (None, None, None),
     };
 
     Some(
@@ -49,6 +35,8 @@ pub fn get_generic_python_environment(
             .prefix(Some(path.to_path_buf()))
             .manager(manager.clone())
             .arch(arch)
+            .implementation(implementation)
+            .distribution(distribution)
             .symlinks(Some(find_executables(path)))
             .build(),
     )
@@ -67,36 +55,33 @@ pub fn get_virtual_env_environment(
             .prefix(Some(path.to_path_buf()))
             .manager(manager.clone())
             .symlinks(Some(find_executables(path)))
+            .base_prefix(get_base_prefix(path))
             .build(),
     )
 }
 
-fn get_version(folder_name: &str) -> Option<String> {
-    // Stable Versions = like 3.10.10
-    match PURE_PYTHON_VERSION.captures(folder_name) {
-        Some(captures) => captures.get(1).map(|version| version.as_str().to_string()),
-        None => {
-            // Dev Versions = like 3.10-dev
-            match DEV_PYTHON_VERSION.captures(folder_name) {
-                Some(captures) => captures.get(1).map(|version| version.as_str().to_string()),
-                None => {
-                    // Alpha, rc Versions = like 3.10.0a3
-                    match BETA_PYTHON_VERSION.captures(folder_name) {
-                        Some(captures) => {
-                            captures.get(1).map(|version| version.as_str().to_string())
-                        }
-                        None => {
-                            // win32 versions, rc Versions = like 3.11.0a-win32
-                            match WIN32_PYTHON_VERSION.captures(folder_name) {
-                                Some(captures) => {
-                                    captures.get(1).map(|version| version.as_str().to_string())
-                                }
-                                None => None,
-                            }
-                        }
-                    }
-                }
-            }
-        }
+/// Gets the prefix of the Python (generally a pyenv version) the virtual environment was created from.
+/// The `home` in `pyvenv.cfg` is the directory of that Python executable, i.e. `<prefix>/bin` (`<prefix>` on Windows).
+fn get_base_prefix(path: &Path) -> Option<PathBuf> {
+    let home = PyVenvCfg::find(path)?.home?;
+    if home.ends_with("bin") || home.ends_with("Scripts") {
+        home.parent().map(|prefix| prefix.to_path_buf())
+    } else {
+        Some(home)
     }
 }
+
+/// Sets the implementation & distribution of a version installed by pyenv, from the name of its directory.
+pub fn set_version_name(env: PythonEnvironment, path: &Path) -> PythonEnvironment {
+    let version_name = match path
+        .file_name()
+        .and_then(|name| PyEnvVersionName::parse(&name.to_string_lossy()))
+    {
+        Some(version_name) => version_name,
+        None => return env,
+    };
+    PythonEnvironmentBuilder::from_environment(env)
+        .implementation(version_name.implementation)
+        .distribution(version_name.distribution)
+        .build()
+}
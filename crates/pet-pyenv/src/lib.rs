@@ -3,36 +3,41 @@
 
 use std::{
     fs,
-    path::PathBuf,
-    sync::{Arc, Mutex},
-    thread,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex, RwLock},
 };
 
+use conda::{get_conda_distribution_environment, CondaDistributionReporter};
 use env_variables::EnvVariables;
 use environments::{get_generic_python_environment, get_virtual_env_environment};
 use log::trace;
 use manager::PyEnvInfo;
-use pet_conda::{utils::is_conda_env, CondaLocator};
+use pet_conda::{manager::CondaManager, utils::is_conda_env, CondaLocator};
 use pet_core::{
     env::PythonEnv,
     manager::{EnvManager, EnvManagerType},
     os_environment::Environment,
     python_environment::{PythonEnvironment, PythonEnvironmentKind},
     reporter::Reporter,
-    Locator, LocatorKind,
+    Configuration, Locator, LocatorKind,
 };
 use pet_python_utils::executable::find_executable;
+use version_selection::VersionSelections;
 
+mod conda;
 pub mod env_variables;
 mod environment_locations;
 mod environments;
 mod manager;
+pub mod version_name;
+pub mod version_selection;
 
 pub struct PyEnv {
     pub env_vars: EnvVariables,
     pub conda_locator: Arc<dyn CondaLocator>,
     manager: Arc<Mutex<Option<EnvManager>>>,
     versions_dir: Arc<Mutex<Option<PathBuf>>>,
+    workspace_directories: Arc<RwLock<Vec<PathBuf>>>,
 }
 
 impl PyEnv {
@@ -45,6 +50,7 @@ impl PyEnv {
             conda_locator,
             manager: Arc::new(Mutex::new(None)),
             versions_dir: Arc::new(Mutex::new(None)),
+            workspace_directories: Arc::new(RwLock::new(vec![])),
         }
     }
     fn clear(&self) {
@@ -78,12 +84,25 @@ impl PyEnv {
         }
         (managers.clone(), versions.clone())
     }
+    fn get_version_selections(&self, versions: &Path) -> VersionSelections {
+        let workspace_directories = self.workspace_directories.read().unwrap().clone();
+        VersionSelections::from(versions, &self.env_vars, &workspace_directories)
+    }
 }
 
 impl Locator for PyEnv {
     fn get_kind(&self) -> LocatorKind {
         LocatorKind::PyEnv
     }
+    fn configure(&self, config: &Configuration) {
+        if let Some(workspace_directories) = &config.workspace_directories {
+            let mut ws_dirs = self.workspace_directories.write().unwrap();
+            ws_dirs.clear();
+            if !workspace_directories.is_empty() {
+                ws_dirs.extend(workspace_directories.clone());
+            }
+        }
+    }
     fn supported_categories(&self) -> Vec<PythonEnvironmentKind> {
         vec![
             PythonEnvironmentKind::Pyenv,
@@ -114,16 +133,14 @@ impl Locator for PyEnv {
 
         let (manager, versions) = self.get_manager_versions_dir();
         if let Some(versions) = versions {
-            if env.executable.starts_with(versions) {
+            if env.executable.starts_with(&versions) {
                 let env_path = env.prefix.clone()?;
-                if let Some(env) = get_virtual_env_environment(&env.executable, &env_path, &manager)
-                {
-                    return Some(env);
-                } else if let Some(env) =
-                    get_generic_python_environment(&env.executable, &env_path, &manager)
-                {
-                    return Some(env);
-                }
+                let mut env = get_virtual_env_environment(&env.executable, &env_path, &manager)
+                    .or_else(|| {
+                        get_generic_python_environment(&env.executable, &env_path, &manager)
+                    })?;
+                env.selections = self.get_version_selections(&versions).get(&env_path);
+                return Some(env);
             }
         }
         None
@@ -140,23 +157,42 @@ impl Locator for PyEnv {
 
         if let Some(versions) = versions {
             let conda_locator = self.conda_locator.clone();
-            thread::scope(|s| {
+            let selections = &self.get_version_selections(&versions);
+            rayon::scope(|s| {
                 if let Ok(reader) = fs::read_dir(versions) {
                     for path in reader.filter_map(Result::ok).map(|e| e.path()) {
                         let conda_locator = conda_locator.clone();
                         let manager = manager.clone();
                         let path = path.clone();
-                        s.spawn(move || {
+                        s.spawn(move |_| {
                             if let Some(executable) = find_executable(&path) {
                                 if is_conda_env(&path) {
-                                    conda_locator.find_and_report(reporter, &path);
-                                } else if let Some(env) =
+                                    if CondaManager::from(&path).is_some() {
+                                        conda_locator.find_and_report(
+                                            &CondaDistributionReporter::new(
+                                                reporter,
+                                                &path,
+                                                selections.get(&path),
+                                            ),
+                                            &path,
+                                        );
+                                    } else if let Some(mut env) =
+                                        get_conda_distribution_environment(&path, &manager)
+                                    {
+                                        env.selections = selections.get(&path);
+                                        reporter.report_environment(&env)
+                                    }
+                                } else if let Some(mut env) =
                                     get_virtual_env_environment(&executable, &path, &manager)
+                                        .or_else(|| {
+                                            get_generic_python_environment(
+                                                &executable,
+                                                &path,
+                                                &manager,
+                                            )
+                                        })
                                 {
-                                    reporter.report_environment(&env)
-                                } else if let Some(env) =
-                                    get_generic_python_environment(&executable, &path, &manager)
-                                {
+                                    env.selections = selections.get(&path);
                                     reporter.report_environment(&env)
                                 }
                             }
@@ -0,0 +1,307 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT License.
+
+//! Names of the versions installed by pyenv (directories in `versions`), these are the names of the
+//! python-build definitions, see https://github.com/pyenv/pyenv/tree/master/plugins/python-build/share/python-build
+//! E.g. `3.12.4`, `3.13t-dev`, `pypy3.10-7.3.15`, `graalpy-24.0.0`, `miniconda3-3.10-22.11.1-1` or `anaconda3-2024.02-1`.
+
+use lazy_static::lazy_static;
+use pet_core::{
+    arch::Architecture,
+    python_environment::{PythonDistribution, PythonImplementation},
+};
+use regex::Regex;
+
+lazy_static! {
+    // CPython = like 3.10.10, 3.12.1a3, 3.13.0rc1, 3.13.0t (free threaded), 3.10-dev or 3.13t-dev
+    static ref CPYTHON: Regex = Regex::new(r"^(\d+\.\d+(?:\.\d+)?(?:(?:a|b|rc)\d+)?)t?(-dev)?$")
+        .expect("error parsing regex for CPython version names in pyenv");
+    // PyPy = like pypy3.10-7.3.15, pypy2.7-7.3.15-src, pypy3.9-dev or pypy-c-jit-latest
+    static ref PYPY: Regex = Regex::new(r"^pypy(\d+\.\d+)?-(.+?)(?:-src)?$")
+        .expect("error parsing regex for PyPy version names in pyenv");
+    // Conda = like anaconda3-2024.02-1, miniconda3-latest, miniconda3-3.10-22.11.1-1, miniforge3-24.3.0-0 or mambaforge
+    static ref CONDA: Regex = Regex::new(
+        r"^(anaconda[23]?|miniconda[23]?|miniforge3|miniforge-pypy3|mambaforge|mambaforge-pypy3)(?:-(.+))?$"
+    )
+    .expect("error parsing regex for conda version names in pyenv");
+    // Python version in conda distributions = like 3.10-22.11.1-1 (miniconda3 for Python 3.10)
+    static ref CONDA_PYTHON_VERSION: Regex = Regex::new(r"^([23]\.\d{1,2})-(.+)$")
+        .expect("error parsing regex for Python version in conda version names in pyenv");
+    // Other implementations & distributions = like graalpy-24.0.0, graalpy-community-24.0.0, stackless-3.7.5 or nogil-3.9.10-1
+    static ref DISTRIBUTION: Regex = Regex::new(
+        r"^(graalpy-community|graalpython|graalpy|stackless|pyston|jython|ironpython|micropython|activepython|cinder|nogil)-(.+)$"
+    )
+    .expect("error parsing regex for distribution version names in pyenv");
+    // Python version in distributions that follow CPython releases = like 3.9.10 in nogil-3.9.10-1
+    static ref DISTRIBUTION_PYTHON_VERSION: Regex = Regex::new(r"^(\d+\.\d+(?:\.\d+)?)(?:-.*)?$")
+        .expect("error parsing regex for Python version in distribution version names in pyenv");
+}
+
+/// Suffix used by pyenv-win for 32bit versions, e.g. `3.11.0a3-win32`.
+const WIN32_SUFFIX: &str = "-win32";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PyEnvVersionName {
+    pub implementation: Option<PythonImplementation>,
+    /// None for the CPython releases from python.org.
+    pub distribution: Option<PythonDistribution>,
+    /// Python version, if it can be determined from the name.
+    pub version: Option<String>,
+    pub arch: Option<Architecture>,
+}
+
+impl PyEnvVersionName {
+    pub fn parse(name: &str) -> Option<Self> {
+        let (name, arch) = match name.strip_suffix(WIN32_SUFFIX) {
+            Some(name) => (name, Some(Architecture::X86)),
+            None => (name, None),
+        };
+        let mut version_name = parse_cpython(name)
+            .or_else(|| parse_pypy(name))
+            .or_else(|| parse_conda(name))
+            .or_else(|| parse_distribution(name))?;
+        version_name.arch = arch;
+        Some(version_name)
+    }
+}
+
+fn parse_cpython(name: &str) -> Option<PyEnvVersionName> {
+    let captures = CPYTHON.captures(name)?;
+    // Dev versions are not a release, hence keep the `-dev` suffix, e.g. `3.13-dev`.
+    let version = match captures.get(2) {
+        Some(dev) => format!("{}{}", &captures[1], dev.as_str()),
+        None => captures[1].to_string(),
+    };
+    Some(PyEnvVersionName {
+        implementation: Some(PythonImplementation::CPython),
+        distribution: None,
+        version: Some(version),
+        arch: None,
+    })
+}
+
+fn parse_pypy(name: &str) -> Option<PyEnvVersionName> {
+    let captures = PYPY.captures(name)?;
+    Some(PyEnvVersionName {
+        implementation: Some(PythonImplementation::PyPy),
+        distribution: Some(PythonDistribution {
+            name: "pypy".to_string(),
+            version: Some(captures[2].to_string()),
+        }),
+        version: captures.get(1).map(|version| version.as_str().to_string()),
+        arch: None,
+    })
+}
+
+fn parse_conda(name: &str) -> Option<PyEnvVersionName> {
+    let captures = CONDA.captures(name)?;
+    let distribution = captures[1].to_string();
+    // Some miniconda releases are built for a specific Python version, e.g. `miniconda3-3.10-22.11.1-1`.
+    let (version, release) = match captures.get(2).map(|release| release.as_str()) {
+        Some(release) => match CONDA_PYTHON_VERSION.captures(release) {
+            Some(captures) => (Some(captures[1].to_string()), Some(captures[2].to_string())),
+            None => (None, Some(release.to_string())),
+        },
+        None => (None, None),
+    };
+    let implementation = if distribution.ends_with("-pypy3") {
+        PythonImplementation::PyPy
+    } else {
+        PythonImplementation::CPython
+    };
+    Some(PyEnvVersionName {
+        implementation: Some(implementation),
+        distribution: Some(PythonDistribution {
+            name: distribution,
+            version: release,
+        }),
+        version,
+        arch: None,
+    })
+}
+
+fn parse_distribution(name: &str) -> Option<PyEnvVersionName> {
+    let captures = DISTRIBUTION.captures(name)?;
+    let distribution = captures[1].to_string();
+    let release = captures[2].to_string();
+    let (implementation, follows_cpython) = match distribution.as_str() {
+        "graalpy" | "graalpy-community" | "graalpython" => (PythonImplementation::GraalPy, false),
+        "stackless" => (PythonImplementation::Stackless, true),
+        "pyston" => (PythonImplementation::Pyston, false),
+        "jython" => (PythonImplementation::Jython, true),
+        "ironpython" => (PythonImplementation::IronPython, true),
+        "micropython" => (PythonImplementation::MicroPython, false),
+        // Builds of CPython.
+        _ => (PythonImplementation::CPython, true),
+    };
+    // Releases of Stackless, Jython & the like are numbered after the version of Python they implement.
+    let version = if follows_cpython {
+        DISTRIBUTION_PYTHON_VERSION
+            .captures(&release)
+            .map(|captures| captures[1].to_string())
+    } else {
+        None
+    };
+    Some(PyEnvVersionName {
+        implementation: Some(implementation),
+        distribution: Some(PythonDistribution {
+            name: distribution,
+            version: Some(release),
+        }),
+        version,
+        arch: None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn distribution(name: &str, version: Option<&str>) -> Option<PythonDistribution> {
+        Some(PythonDistribution {
+            name: name.to_string(),
+            version: version.map(|version| version.to_string()),
+        })
+    }
+
+    #[test]
+    fn parse_cpython_versions() {
+        for (name, version) in [
+            ("3.12.4", "3.12.4"),
+            ("3.12.1a3", "3.12.1a3"),
+            ("3.13.0rc1", "3.13.0rc1"),
+            ("3.13.0t", "3.13.0"),
+            ("3.13-dev", "3.13-dev"),
+            ("3.13t-dev", "3.13-dev"),
+        ] {
+            let version_name = PyEnvVersionName::parse(name).unwrap();
+            assert_eq!(
+                version_name.implementation,
+                Some(PythonImplementation::CPython)
+            );
+            assert_eq!(version_name.distribution, None);
+            assert_eq!(version_name.version, Some(version.to_string()));
+        }
+
+        let win32 = PyEnvVersionName::parse("3.11.0a3-win32").unwrap();
+        assert_eq!(win32.version, Some("3.11.0a3".to_string()));
+        assert_eq!(win32.arch, Some(Architecture::X86));
+    }
+
+    #[test]
+    fn parse_pypy_versions() {
+        let pypy = PyEnvVersionName::parse("pypy3.10-7.3.15").unwrap();
+        assert_eq!(pypy.implementation, Some(PythonImplementation::PyPy));
+        assert_eq!(pypy.distribution, distribution("pypy", Some("7.3.15")));
+        assert_eq!(pypy.version, Some("3.10".to_string()));
+
+        let src = PyEnvVersionName::parse("pypy2.7-7.3.15-src").unwrap();
+        assert_eq!(src.distribution, distribution("pypy", Some("7.3.15")));
+        assert_eq!(src.version, Some("2.7".to_string()));
+
+        let jit = PyEnvVersionName::parse("pypy-c-jit-latest").unwrap();
+        assert_eq!(jit.distribution, distribution("pypy", Some("c-jit-latest")));
+        assert_eq!(jit.version, None);
+    }
+
+    #[test]
+    fn parse_conda_versions() {
+        for (name, dist, release, version, implementation) in [
+            (
+                "anaconda3-2024.02-1",
+                "anaconda3",
+                Some("2024.02-1"),
+                None,
+                PythonImplementation::CPython,
+            ),
+            (
+                "miniconda3-latest",
+                "miniconda3",
+                Some("latest"),
+                None,
+                PythonImplementation::CPython,
+            ),
+            (
+                "miniconda3-3.10-22.11.1-1",
+                "miniconda3",
+                Some("22.11.1-1"),
+                Some("3.10"),
+                PythonImplementation::CPython,
+            ),
+            (
+                "mambaforge",
+                "mambaforge",
+                None,
+                None,
+                PythonImplementation::CPython,
+            ),
+            (
+                "miniforge-pypy3",
+                "miniforge-pypy3",
+                None,
+                None,
+                PythonImplementation::PyPy,
+            ),
+        ] {
+            let version_name = PyEnvVersionName::parse(name).unwrap();
+            assert_eq!(version_name.implementation, Some(implementation));
+            assert_eq!(version_name.distribution, distribution(dist, release));
+            assert_eq!(version_name.version, version.map(|v| v.to_string()));
+        }
+    }
+
+    #[test]
+    fn parse_other_distributions() {
+        for (name, implementation, dist, release, version) in [
+            (
+                "graalpy-24.0.0",
+                PythonImplementation::GraalPy,
+                "graalpy",
+                "24.0.0",
+                None,
+            ),
+            (
+                "graalpy-community-24.0.0",
+                PythonImplementation::GraalPy,
+                "graalpy-community",
+                "24.0.0",
+                None,
+            ),
+            (
+                "stackless-3.7.5",
+                PythonImplementation::Stackless,
+                "stackless",
+                "3.7.5",
+                Some("3.7.5"),
+            ),
+            (
+                "nogil-3.9.10-1",
+                PythonImplementation::CPython,
+                "nogil",
+                "3.9.10-1",
+                Some("3.9.10"),
+            ),
+            (
+                "pyston-2.3.5",
+                PythonImplementation::Pyston,
+                "pyston",
+                "2.3.5",
+                None,
+            ),
+            (
+                "jython-2.7.2",
+                PythonImplementation::Jython,
+                "jython",
+                "2.7.2",
+                Some("2.7.2"),
+            ),
+        ] {
+            let version_name = PyEnvVersionName::parse(name).unwrap();
+            assert_eq!(version_name.implementation, Some(implementation));
+            assert_eq!(version_name.distribution, distribution(dist, Some(release)));
+            assert_eq!(version_name.version, version.map(|v| v.to_string()));
+        }
+
+        assert_eq!(PyEnvVersionName::parse("my-virtual-env"), None);
+    }
+}
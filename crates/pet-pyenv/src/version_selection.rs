@@ -0,0 +1,173 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT License.
+
+use log::trace;
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use pet_core::{
+    os_environment::Environment,
+    python_environment::{EnvironmentSelection, SelectionScope},
+};
+use pet_fs::path::norm_case;
+
+use crate::{
+    env_variables::EnvVariables,
+    environment_locations::{get_home_pyenv_dir, get_pyenv_dir},
+};
+
+/// Name of the file used by pyenv (and other tools such as uv) to select the Python version of a project.
+pub const LOCAL_VERSION_FILE: &str = ".python-version";
+/// Name of the file in the pyenv root used to select the global Python version, see `pyenv global`.
+pub const GLOBAL_VERSION_FILE: &str = "version";
+/// Version name pyenv uses for the Python found on the PATH outside of pyenv.
+const SYSTEM_VERSION: &str = "system";
+
+/// Returns the directory where pyenv installs the Python versions.
+pub fn get_versions_dir(environment: &dyn Environment) -> Option<PathBuf> {
+    let env_vars = EnvVariables::from(environment);
+    [get_pyenv_dir(&env_vars), get_home_pyenv_dir(&env_vars)]
+        .into_iter()
+        .flatten()
+        .map(|dir| dir.join("versions"))
+        .find(|dir| dir.is_dir())
+}
+
+/// Looks for the `.python-version` file in the given directory & its parents, the same as pyenv.
+pub fn find_local_version_file(dir: &Path) -> Option<PathBuf> {
+    dir.ancestors()
+        .map(|dir| dir.join(LOCAL_VERSION_FILE))
+        .find(|file| file.is_file())
+}
+
+/// Reads the versions from a pyenv version file.
+/// Versions are separated by whitespace & lines starting with `#` are comments.
+pub fn read_version_file(file: &Path) -> Vec<String> {
+    fs::read_to_string(file)
+        .unwrap_or_default()
+        .lines()
+        .map(|line| line.trim())
+        .filter(|line| !line.starts_with('#'))
+        .flat_map(|line| line.split_whitespace())
+        .map(|version| version.to_string())
+        .collect()
+}
+
+/// Finds the directory of the installed version that pyenv would use for the given version.
+/// Besides exact matches, pyenv accepts prefixes such as `3.12`, which select the latest `3.12.x`.
+pub fn find_installed_version(versions_dir: &Path, version: &str) -> Option<PathBuf> {
+    let exact = versions_dir.join(version);
+    if exact.is_dir() {
+        return Some(exact);
+    }
+    let prefix = format!("{version}.");
+    fs::read_dir(versions_dir)
+        .ok()?
+        .filter_map(Result::ok)
+        .filter(|entry| entry.path().is_dir())
+        .filter_map(|entry| {
+            let name = entry.file_name().to_str()?.to_string();
+            let remainder = name.strip_prefix(&prefix)?;
+            let parts = remainder
+                .split('.')
+                .map(|part| part.parse::<u32>().ok())
+                .collect::<Option<Vec<u32>>>()?;
+            Some((parts, entry.path()))
+        })
+        .max_by(|a, b| a.0.cmp(&b.0))
+        .map(|(_, path)| path)
+}
+
+/// Environments selected by pyenv, globally, for the shell (`PYENV_VERSION`) & for each workspace folder.
+#[derive(Debug, Default)]
+pub struct VersionSelections {
+    selections: Vec<(PathBuf, EnvironmentSelection)>,
+}
+
+impl VersionSelections {
+    pub fn from(
+        versions_dir: &Path,
+        env_vars: &EnvVariables,
+        workspace_directories: &[PathBuf],
+    ) -> Self {
+        let mut selections = vec![];
+        if let Some(file) = versions_dir
+            .parent()
+            .map(|dir| dir.join(GLOBAL_VERSION_FILE))
+        {
+            if let Some(prefix) = get_selected_version(versions_dir, &read_version_file(&file)) {
+                selections.push((
+                    prefix,
+                    EnvironmentSelection {
+                        scope: SelectionScope::Global,
+                        file: Some(file),
+                        workspace: None,
+                    },
+                ));
+            }
+        }
+        for workspace in workspace_directories {
+            if let Some(file) = find_local_version_file(workspace) {
+                if let Some(prefix) = get_selected_version(versions_dir, &read_version_file(&file))
+                {
+                    selections.push((
+                        prefix,
+                        EnvironmentSelection {
+                            scope: SelectionScope::Local,
+                            file: Some(file),
+                            workspace: Some(workspace.clone()),
+                        },
+                    ));
+                }
+            }
+        }
+        if let Some(pyenv_version) = &env_vars.pyenv_version {
+            // Multiple versions are separated by `:`, e.g. `PYENV_VERSION=3.12:3.11`.
+            let versions = pyenv_version
+                .split(':')
+                .map(|version| version.trim().to_string())
+                .filter(|version| !version.is_empty())
+                .collect::<Vec<String>>();
+            if let Some(prefix) = get_selected_version(versions_dir, &versions) {
+                selections.push((
+                    prefix,
+                    EnvironmentSelection {
+                        scope: SelectionScope::Shell,
+                        file: None,
+                        workspace: None,
+                    },
+                ));
+            }
+        }
+        trace!("PyEnv version selections {:?}", selections);
+        VersionSelections { selections }
+    }
+
+    /// Gets the selections of the pyenv environment with the given prefix (a directory in `versions`).
+    pub fn get(&self, prefix: &Path) -> Option<Vec<EnvironmentSelection>> {
+        let prefix = norm_case(prefix);
+        let selections = self
+            .selections
+            .iter()
+            .filter(|(selected, _)| *selected == prefix)
+            .map(|(_, selection)| selection.clone())
+            .collect::<Vec<EnvironmentSelection>>();
+        if selections.is_empty() {
+            None
+        } else {
+            Some(selections)
+        }
+    }
+}
+
+/// The first version in a version file is the one pyenv uses for `python`,
+/// the others only provide commands that are not in the first, such as `python3.9`.
+fn get_selected_version(versions_dir: &Path, versions: &[String]) -> Option<PathBuf> {
+    let version = versions.first()?;
+    if version == SYSTEM_VERSION {
+        return None;
+    }
+    find_installed_version(versions_dir, version).map(norm_case)
+}
@@ -23,6 +23,7 @@ pub fn create_env_variables(home: PathBuf, root: PathBuf) -> EnvVariables {
         path: None,
         pyenv_root: None,
         pyenv: None,
+        pyenv_version: None,
         known_global_search_locations: vec![],
     }
 }
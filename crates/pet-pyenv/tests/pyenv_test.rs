@@ -147,7 +147,10 @@ fn find_pyenv_envs() {
         self,
         arch::Architecture,
         manager::{EnvManager, EnvManagerType},
-        python_environment::{PythonEnvironment, PythonEnvironmentKind},
+        python_environment::{
+            PythonDistribution, PythonEnvironment, PythonEnvironmentKind, PythonEnvironmentState,
+            PythonImplementation,
+        },
         Locator,
     };
     use pet_pyenv;
@@ -222,6 +225,16 @@ fn find_pyenv_envs() {
             ".pyenv/versions/3.9.9/bin/python",
         ])]),
         error: None,
+        compatibility: None,
+        state: Some(PythonEnvironmentState::Ready),
+        packages: None,
+        install_python_command: None,
+        create_command: None,
+        requires_python: None,
+        selections: None,
+        base_prefix: None,
+        implementation: Some(PythonImplementation::CPython),
+        distribution: None,
     };
     let expected_virtual_env = PythonEnvironment {
         display_name: None,
@@ -244,6 +257,18 @@ fn find_pyenv_envs() {
             ".pyenv/versions/my-virtual-env/bin/python",
         ])]),
         error: None,
+        compatibility: None,
+        state: Some(PythonEnvironmentState::Ready),
+        packages: None,
+        install_python_command: None,
+        create_command: None,
+        requires_python: None,
+        selections: None,
+        base_prefix: Some(std::path::PathBuf::from(
+            "/Users/donjayamanne/.pyenv/versions/3.10.13",
+        )),
+        implementation: None,
+        distribution: None,
     };
     let expected_3_12_1 = PythonEnvironment {
         display_name: None,
@@ -266,6 +291,16 @@ fn find_pyenv_envs() {
             ".pyenv/versions/3.12.1/bin/python",
         ])]),
         error: None,
+        compatibility: None,
+        state: Some(PythonEnvironmentState::Ready),
+        packages: None,
+        install_python_command: None,
+        create_command: None,
+        requires_python: None,
+        selections: None,
+        base_prefix: None,
+        implementation: Some(PythonImplementation::CPython),
+        distribution: None,
     };
     let expected_3_13_dev = PythonEnvironment {
         display_name: None,
@@ -288,6 +323,16 @@ fn find_pyenv_envs() {
             ".pyenv/versions/3.13-dev/bin/python",
         ])]),
         error: None,
+        compatibility: None,
+        state: Some(PythonEnvironmentState::Ready),
+        packages: None,
+        install_python_command: None,
+        create_command: None,
+        requires_python: None,
+        selections: None,
+        base_prefix: None,
+        implementation: Some(PythonImplementation::CPython),
+        distribution: None,
     };
     let expected_3_12_1a3 = PythonEnvironment {
         display_name: None,
@@ -310,6 +355,16 @@ fn find_pyenv_envs() {
             ".pyenv/versions/3.12.1a3/bin/python",
         ])]),
         error: None,
+        compatibility: None,
+        state: Some(PythonEnvironmentState::Ready),
+        packages: None,
+        install_python_command: None,
+        create_command: None,
+        requires_python: None,
+        selections: None,
+        base_prefix: None,
+        implementation: Some(PythonImplementation::CPython),
+        distribution: None,
     };
     let expected_no_gil = PythonEnvironment {
         display_name: None,
@@ -332,6 +387,19 @@ fn find_pyenv_envs() {
             ".pyenv/versions/nogil-3.9.10-1/bin/python",
         ])]),
         error: None,
+        compatibility: None,
+        state: Some(PythonEnvironmentState::Ready),
+        packages: None,
+        install_python_command: None,
+        create_command: None,
+        requires_python: None,
+        selections: None,
+        base_prefix: None,
+        implementation: Some(PythonImplementation::CPython),
+        distribution: Some(PythonDistribution {
+            name: "nogil".to_string(),
+            version: Some("3.9.10-1".to_string()),
+        }),
     };
     let expected_pypy = PythonEnvironment {
         display_name: None,
@@ -354,6 +422,19 @@ fn find_pyenv_envs() {
             ".pyenv/versions/pypy3.9-7.3.15/bin/python",
         ])]),
         error: None,
+        compatibility: None,
+        state: Some(PythonEnvironmentState::Ready),
+        packages: None,
+        install_python_command: None,
+        create_command: None,
+        requires_python: None,
+        selections: None,
+        base_prefix: None,
+        implementation: Some(PythonImplementation::PyPy),
+        distribution: Some(PythonDistribution {
+            name: "pypy".to_string(),
+            version: Some("7.3.15".to_string()),
+        }),
     };
 
     let expected_conda_root = PythonEnvironment {
@@ -368,6 +449,19 @@ fn find_pyenv_envs() {
         arch: Some(Architecture::X64),
         symlinks: Some(vec![conda_dir.join("bin").join("python")]),
         error: None,
+        compatibility: None,
+        state: Some(PythonEnvironmentState::Ready),
+        packages: None,
+        install_python_command: None,
+        create_command: None,
+        requires_python: None,
+        selections: None,
+        base_prefix: None,
+        implementation: Some(PythonImplementation::CPython),
+        distribution: Some(PythonDistribution {
+            name: "anaconda".to_string(),
+            version: Some("4.0.0".to_string()),
+        }),
     };
     let expected_conda_one = PythonEnvironment {
         display_name: None,
@@ -381,6 +475,16 @@ fn find_pyenv_envs() {
         arch: None,
         symlinks: Some(vec![conda_dir.join("envs").join("one").join("python")]),
         error: None,
+        compatibility: None,
+        state: Some(PythonEnvironmentState::Ready),
+        packages: None,
+        install_python_command: None,
+        create_command: None,
+        requires_python: None,
+        selections: None,
+        base_prefix: None,
+        implementation: None,
+        distribution: None,
     };
     let expected_conda_two = PythonEnvironment {
         display_name: None,
@@ -394,6 +498,87 @@ fn find_pyenv_envs() {
         symlinks: Some(vec![conda_dir.join("envs").join("two").join("python")]),
         arch: None,
         error: None,
+        compatibility: None,
+        state: Some(PythonEnvironmentState::Ready),
+        packages: None,
+        install_python_command: None,
+        create_command: None,
+        requires_python: None,
+        selections: None,
+        base_prefix: None,
+        implementation: None,
+        distribution: None,
+    };
+
+    let expected_graalpy = PythonEnvironment {
+        executable: Some(resolve_test_path(&[
+            home.to_str().unwrap(),
+            ".pyenv/versions/graalpy-24.0.0/bin/python",
+        ])),
+        kind: Some(PythonEnvironmentKind::Pyenv),
+        // The GraalPy release does not tell us the version of Python.
+        version: None,
+        prefix: Some(resolve_test_path(&[
+            home.to_str().unwrap(),
+            ".pyenv/versions/graalpy-24.0.0",
+        ])),
+        manager: Some(expected_pyenv_manager.clone()),
+        symlinks: Some(vec![resolve_test_path(&[
+            home.to_str().unwrap(),
+            ".pyenv/versions/graalpy-24.0.0/bin/python",
+        ])]),
+        state: Some(PythonEnvironmentState::Ready),
+        implementation: Some(PythonImplementation::GraalPy),
+        distribution: Some(PythonDistribution {
+            name: "graalpy".to_string(),
+            version: Some("24.0.0".to_string()),
+        }),
+        ..Default::default()
+    };
+    let expected_stackless = PythonEnvironment {
+        executable: Some(resolve_test_path(&[
+            home.to_str().unwrap(),
+            ".pyenv/versions/stackless-3.7.5/bin/python",
+        ])),
+        kind: Some(PythonEnvironmentKind::Pyenv),
+        version: Some("3.7.5".to_string()),
+        prefix: Some(resolve_test_path(&[
+            home.to_str().unwrap(),
+            ".pyenv/versions/stackless-3.7.5",
+        ])),
+        manager: Some(expected_pyenv_manager.clone()),
+        symlinks: Some(vec![resolve_test_path(&[
+            home.to_str().unwrap(),
+            ".pyenv/versions/stackless-3.7.5/bin/python",
+        ])]),
+        state: Some(PythonEnvironmentState::Ready),
+        implementation: Some(PythonImplementation::Stackless),
+        distribution: Some(PythonDistribution {
+            name: "stackless".to_string(),
+            version: Some("3.7.5".to_string()),
+        }),
+        ..Default::default()
+    };
+    // Conda distributions without a conda executable are reported with the pyenv manager.
+    let expected_conda_distribution = |name: &str, distribution: &str, release: Option<&str>| {
+        let prefix = resolve_test_path(&[home.to_str().unwrap(), ".pyenv/versions", name]);
+        PythonEnvironment {
+            name: Some("base".to_string()),
+            executable: Some(prefix.join("bin").join("python")),
+            kind: Some(PythonEnvironmentKind::Conda),
+            version: Some("3.11.5".to_string()),
+            prefix: Some(prefix.clone()),
+            manager: Some(expected_pyenv_manager.clone()),
+            arch: Some(Architecture::X64),
+            symlinks: Some(vec![prefix.join("bin").join("python")]),
+            state: Some(PythonEnvironmentState::Ready),
+            implementation: Some(PythonImplementation::CPython),
+            distribution: Some(PythonDistribution {
+                name: distribution.to_string(),
+                version: release.map(|release| release.to_string()),
+            }),
+            ..Default::default()
+        }
     };
 
     let mut expected_envs = vec![
@@ -407,6 +592,16 @@ fn find_pyenv_envs() {
         expected_conda_two,
         expected_no_gil,
         expected_pypy,
+        expected_graalpy,
+        expected_stackless,
+        expected_conda_distribution("anaconda3-2021.04", "anaconda3", Some("2021.04")),
+        expected_conda_distribution("mambaforge", "mambaforge", None),
+        expected_conda_distribution("mambaforge-4.10.1-4", "mambaforge", Some("4.10.1-4")),
+        expected_conda_distribution("miniconda-latest", "miniconda", Some("latest")),
+        expected_conda_distribution("miniconda3-3.10-22.11.1-1", "miniconda3", Some("22.11.1-1")),
+        expected_conda_distribution("miniconda3-3.10.1", "miniconda3", Some("3.10.1")),
+        expected_conda_distribution("miniconda3-4.0.5", "miniconda3", Some("4.0.5")),
+        expected_conda_distribution("miniforge3-4.11.0-1", "miniforge3", Some("4.11.0-1")),
     ];
     expected_envs.sort();
     environments.sort();
@@ -423,7 +618,9 @@ fn resolve_pyenv_environment() {
         self,
         env::PythonEnv,
         manager::{EnvManager, EnvManagerType},
-        python_environment::{PythonEnvironment, PythonEnvironmentKind},
+        python_environment::{
+            PythonEnvironment, PythonEnvironmentKind, PythonEnvironmentState, PythonImplementation,
+        },
         Locator,
     };
     use pet_pyenv;
@@ -464,6 +661,16 @@ fn resolve_pyenv_environment() {
         arch: None,
         symlinks: Some(vec![executable]),
         error: None,
+        compatibility: None,
+        state: Some(PythonEnvironmentState::Ready),
+        packages: None,
+        install_python_command: None,
+        create_command: None,
+        requires_python: None,
+        selections: None,
+        base_prefix: None,
+        implementation: Some(PythonImplementation::CPython),
+        distribution: None,
     };
     let expected_virtual_env = PythonEnvironment {
         display_name: None,
@@ -486,6 +693,18 @@ fn resolve_pyenv_environment() {
             ".pyenv/versions/my-virtual-env/bin/python",
         ])]),
         error: None,
+        compatibility: None,
+        state: Some(PythonEnvironmentState::Ready),
+        packages: None,
+        install_python_command: None,
+        create_command: None,
+        requires_python: None,
+        selections: None,
+        base_prefix: Some(std::path::PathBuf::from(
+            "/Users/donjayamanne/.pyenv/versions/3.10.13",
+        )),
+        implementation: None,
+        distribution: None,
     };
 
     // Resolve regular Python installs in Pyenv
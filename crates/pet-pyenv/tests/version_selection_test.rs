@@ -0,0 +1,113 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT License.
+
+mod common;
+
+#[test]
+#[cfg(unix)]
+fn reads_local_version_file_from_parent_directory() {
+    use common::resolve_test_path;
+    use pet_pyenv::version_selection::{find_local_version_file, read_version_file};
+
+    let project = resolve_test_path(&["unix", "pyenv_project"]);
+    let version_file = find_local_version_file(&project.join("src")).unwrap();
+
+    assert_eq!(version_file, project.join(".python-version"));
+    assert_eq!(read_version_file(&version_file), vec!["3.12", "3.9.9"]);
+}
+
+#[test]
+#[cfg(unix)]
+fn finds_installed_version_for_exact_and_prefix_versions() {
+    use common::resolve_test_path;
+    use pet_pyenv::version_selection::find_installed_version;
+
+    let versions = resolve_test_path(&["unix", "pyenv", "user_home", ".pyenv", "versions"]);
+
+    assert_eq!(
+        find_installed_version(&versions, "3.9.9"),
+        Some(versions.join("3.9.9"))
+    );
+    // Prefixes select the latest final release, 3.12.1a3 is not a match.
+    assert_eq!(
+        find_installed_version(&versions, "3.12"),
+        Some(versions.join("3.12.1"))
+    );
+    assert_eq!(
+        find_installed_version(&versions, "3.13-dev"),
+        Some(versions.join("3.13-dev"))
+    );
+    assert_eq!(find_installed_version(&versions, "3.10"), None);
+}
+
+#[test]
+#[cfg(unix)]
+fn marks_environments_selected_by_pyenv() {
+    use common::{create_test_environment, resolve_test_path};
+    use pet_conda::Conda;
+    use pet_core::{
+        python_environment::{EnvironmentSelection, SelectionScope},
+        Configuration, Locator,
+    };
+    use pet_pyenv::PyEnv;
+    use pet_reporter::{cache::CacheReporter, collect};
+    use std::{collections::HashMap, path::PathBuf, sync::Arc};
+
+    let home = resolve_test_path(&["unix", "pyenv_selection", "user_home"]);
+    let project = resolve_test_path(&["unix", "pyenv_selection", "project"]);
+    let versions = home.join(".pyenv").join("versions");
+    let environment = create_test_environment(
+        HashMap::from([("PYENV_VERSION".to_string(), "3.11.9".to_string())]),
+        Some(home.clone()),
+        vec![],
+        None,
+    );
+    let conda = Arc::new(Conda::from(&environment));
+    let locator = PyEnv::from(&environment, conda);
+    locator.configure(&Configuration {
+        workspace_directories: Some(vec![project.clone()]),
+        ..Default::default()
+    });
+    let reporter = Arc::new(collect::create_reporter());
+
+    locator.find(&CacheReporter::new(reporter.clone()));
+
+    let environments = reporter.environments.lock().unwrap().clone();
+    let get_env = |name: &str| {
+        environments
+            .iter()
+            .find(|env| env.prefix == Some(versions.join(name)))
+            .unwrap()
+            .clone()
+    };
+    // `3.12` in the global version file selects the latest 3.12 version.
+    assert_eq!(
+        get_env("3.12.4").selections,
+        Some(vec![EnvironmentSelection {
+            scope: SelectionScope::Global,
+            file: Some(home.join(".pyenv").join("version")),
+            workspace: None,
+        }])
+    );
+    assert_eq!(
+        get_env("3.11.9").selections,
+        Some(vec![EnvironmentSelection {
+            scope: SelectionScope::Shell,
+            file: None,
+            workspace: None,
+        }])
+    );
+    let myenv = get_env("myenv");
+    assert_eq!(
+        myenv.selections,
+        Some(vec![EnvironmentSelection {
+            scope: SelectionScope::Local,
+            file: Some(project.join(".python-version")),
+            workspace: Some(project),
+        }])
+    );
+    assert_eq!(
+        myenv.base_prefix,
+        Some(PathBuf::from("/home/user/.pyenv/versions/3.11.9"))
+    );
+}
@@ -3,17 +3,25 @@
 
 use lazy_static::lazy_static;
 use log::{trace, warn};
+use serde::{Deserialize, Serialize};
 use std::{
     collections::{hash_map::Entry, HashMap, HashSet},
     io,
-    path::PathBuf,
-    sync::{Arc, Mutex},
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex,
+    },
     time::SystemTime,
 };
 
 use crate::{
     env::ResolvedPythonEnv,
-    fs_cache::{delete_cache_file, get_cache_from_file, store_cache_in_file},
+    fingerprint::{CacheValidation, EnvironmentFingerprint},
+    fs_cache::{
+        clear_cache_directory, delete_cache_file, evict_cache_files, get_cache_from_file,
+        list_cache_files, lock_cache_entry, store_cache_in_file, CacheFileLock, CacheFileLookup,
+    },
 };
 
 lazy_static! {
@@ -22,8 +30,15 @@ lazy_static! {
 
 pub trait CacheEntry: Send + Sync {
     fn get(&self) -> Option<ResolvedPythonEnv>;
+    /// Same as `get`, used to read the entry again once locked across processes.
+    /// The miss has already been counted by `get`, hence it is not counted again.
+    fn get_after_lock(&self) -> Option<ResolvedPythonEnv>;
     fn store(&self, environment: ResolvedPythonEnv);
     fn track_symlinks(&self, symlinks: Vec<PathBuf>);
+    /// Locks this entry across all processes sharing the same cache directory.
+    /// Used to ensure only one process spawns Python to resolve the same executable,
+    /// others wait for the lock to be released & then use the cached details.
+    fn lock_across_processes(&self) -> Option<CacheFileLock>;
 }
 
 pub fn clear_cache() -> io::Result<()> {
@@ -42,12 +57,83 @@ pub fn set_cache_directory(cache_dir: PathBuf) {
     CACHE.set_cache_directory(cache_dir)
 }
 
+/// Limits the number of entries in the on-disk cache.
+/// Once exceeded, the least recently used entries are evicted.
+pub fn set_cache_max_entries(max_entries: usize) {
+    CACHE.set_max_entries(max_entries)
+}
+
+/// Selects how cached interpreter details are validated before being used.
+pub fn set_cache_validation(validation: CacheValidation) {
+    CACHE.set_validation(validation)
+}
+
+pub fn get_cache_stats() -> CacheStats {
+    CACHE.get_stats()
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CacheStats {
+    /// Number of lookups that were served from the cache (in memory or on disk).
+    pub hits: u64,
+    /// Number of lookups that were not in the cache.
+    pub misses: u64,
+    /// Number of entries discarded because the executables changed or the file was corrupt.
+    pub invalidations: u64,
+    /// Number of entries evicted to keep the cache within the configured size.
+    pub evictions: u64,
+    /// Number of entries currently in the on-disk cache.
+    pub entries: u64,
+    /// Total size of the on-disk cache in bytes.
+    pub size: u64,
+    /// Maximum number of entries allowed in the on-disk cache.
+    pub max_entries: Option<usize>,
+    pub validation: CacheValidation,
+}
+
+#[derive(Debug, Default, Clone, Copy)]
+struct CacheSettings {
+    max_entries: Option<usize>,
+    validation: CacheValidation,
+}
+
+#[derive(Default)]
+struct CacheCounters {
+    hits: AtomicU64,
+    misses: AtomicU64,
+    invalidations: AtomicU64,
+    evictions: AtomicU64,
+    /// Entries stored since the cache was last evicted (not reported).
+    stores_since_eviction: AtomicU64,
+}
+
+impl CacheCounters {
+    fn increment(counter: &AtomicU64, value: u64) {
+        counter.fetch_add(value, Ordering::Relaxed);
+    }
+}
+
+/// Number of entries stored between evictions, hence the number of entries the cache can exceed its limit by.
+/// Evicting lists the whole cache directory, doing so on every store makes a cold refresh quadratic.
+fn get_eviction_slack(max_entries: usize) -> u64 {
+    (max_entries as u64 / 10).max(1)
+}
+
+fn evict(cache_directory: &Path, max_entries: usize, counters: &CacheCounters) {
+    counters.stores_since_eviction.store(0, Ordering::Relaxed);
+    let evicted = evict_cache_files(cache_directory, max_entries);
+    CacheCounters::increment(&counters.evictions, evicted as u64);
+}
+
 pub type LockableCacheEntry = Arc<Mutex<Box<dyn CacheEntry>>>;
 
 /// Cache of Interpreter details for a given executable.
 /// Uses in memory cache as well as a file cache as backing store.
 struct CacheImpl {
     cache_dir: Arc<Mutex<Option<PathBuf>>>,
+    settings: Arc<Mutex<CacheSettings>>,
+    counters: Arc<CacheCounters>,
     locks: Mutex<HashMap<PathBuf, LockableCacheEntry>>,
 }
 
@@ -55,6 +141,8 @@ impl CacheImpl {
     fn new(cache_dir: Option<PathBuf>) -> CacheImpl {
         CacheImpl {
             cache_dir: Arc::new(Mutex::new(cache_dir)),
+            settings: Arc::new(Mutex::new(CacheSettings::default())),
+            counters: Arc::new(CacheCounters::default()),
             locks: Mutex::new(HashMap::<PathBuf, LockableCacheEntry>::new()),
         }
     }
@@ -87,6 +175,50 @@ impl CacheImpl {
             .expect("cache_dir mutex poisoned")
             .replace(cache_dir);
     }
+
+    fn set_max_entries(&self, max_entries: usize) {
+        trace!("Setting max cache entries to {}", max_entries);
+        let previous = self
+            .settings
+            .lock()
+            .expect("settings mutex poisoned")
+            .max_entries
+            .replace(max_entries);
+        // The cache is already within the limit, unless the limit is new or has been lowered.
+        if previous.is_some_and(|previous| previous <= max_entries) {
+            return;
+        }
+        if let Some(cache_directory) = self.get_cache_directory() {
+            evict(&cache_directory, max_entries, &self.counters);
+        }
+    }
+
+    fn set_validation(&self, validation: CacheValidation) {
+        trace!("Setting cache validation to {:?}", validation);
+        self.settings
+            .lock()
+            .expect("settings mutex poisoned")
+            .validation = validation;
+    }
+
+    fn get_stats(&self) -> CacheStats {
+        let settings = *self.settings.lock().expect("settings mutex poisoned");
+        let files = self
+            .get_cache_directory()
+            .map(|cache_directory| list_cache_files(&cache_directory))
+            .unwrap_or_default();
+        CacheStats {
+            hits: self.counters.hits.load(Ordering::Relaxed),
+            misses: self.counters.misses.load(Ordering::Relaxed),
+            invalidations: self.counters.invalidations.load(Ordering::Relaxed),
+            evictions: self.counters.evictions.load(Ordering::Relaxed),
+            entries: files.len() as u64,
+            size: files.iter().map(|(_, size, _)| size).sum(),
+            max_entries: settings.max_entries,
+            validation: settings.validation,
+        }
+    }
+
     fn clear(&self) -> io::Result<()> {
         trace!("Clearing cache");
         self.locks.lock().expect("locks mutex poisoned").clear();
@@ -96,7 +228,7 @@ impl CacheImpl {
             .expect("cache_dir mutex poisoned")
             .clone()
         {
-            std::fs::remove_dir_all(cache_directory)
+            clear_cache_directory(&cache_directory)
         } else {
             Ok(())
         }
@@ -115,8 +247,12 @@ impl CacheImpl {
         {
             Entry::Occupied(lock) => lock.get().clone(),
             Entry::Vacant(lock) => {
-                let cache = Box::new(CacheEntryImpl::create(cache_directory.clone(), executable))
-                    as Box<dyn CacheEntry + 'static>;
+                let cache = Box::new(CacheEntryImpl::create(
+                    cache_directory.clone(),
+                    executable,
+                    self.settings.clone(),
+                    self.counters.clone(),
+                )) as Box<dyn CacheEntry + 'static>;
                 lock.insert(Arc::new(Mutex::new(cache))).clone()
             }
         }
@@ -135,14 +271,26 @@ struct CacheEntryImpl {
     envoronment: Arc<Mutex<Option<ResolvedPythonEnv>>>,
     /// List of known symlinks to this executable.
     symlinks: Arc<Mutex<Vec<FilePathWithMTimeCTime>>>,
+    /// Only available when using content based validation.
+    fingerprint: Arc<Mutex<Option<EnvironmentFingerprint>>>,
+    settings: Arc<Mutex<CacheSettings>>,
+    counters: Arc<CacheCounters>,
 }
 impl CacheEntryImpl {
-    pub fn create(cache_directory: Option<PathBuf>, executable: PathBuf) -> impl CacheEntry {
+    pub fn create(
+        cache_directory: Option<PathBuf>,
+        executable: PathBuf,
+        settings: Arc<Mutex<CacheSettings>>,
+        counters: Arc<CacheCounters>,
+    ) -> impl CacheEntry {
         CacheEntryImpl {
             cache_directory,
             executable,
             envoronment: Arc::new(Mutex::new(None)),
             symlinks: Arc::new(Mutex::new(Vec::new())),
+            fingerprint: Arc::new(Mutex::new(None)),
+            settings,
+            counters,
         }
     }
     pub fn verify_in_memory_cache(&self) {
@@ -169,21 +317,59 @@ impl CacheEntryImpl {
                         metadata.modified().ok(),
                         metadata.created().ok()
                     );
-                    self.envoronment
-                        .lock()
-                        .expect("envoronment mutex poisoned")
-                        .take();
-                    if let Some(cache_directory) = &self.cache_directory {
-                        delete_cache_file(cache_directory, &self.executable);
-                    }
+                    self.invalidate();
                 }
             }
         }
+
+        let validation = self
+            .settings
+            .lock()
+            .expect("settings mutex poisoned")
+            .validation;
+        if validation == CacheValidation::Content {
+            let environment = self
+                .envoronment
+                .lock()
+                .expect("envoronment mutex poisoned")
+                .clone();
+            if let Some(environment) = environment {
+                let is_current = self
+                    .fingerprint
+                    .lock()
+                    .expect("fingerprint mutex poisoned")
+                    .as_ref()
+                    .is_some_and(|fingerprint| fingerprint.is_current(&environment));
+                if !is_current {
+                    trace!(
+                        "Contents of {:?} have changed since we last cached it",
+                        self.executable
+                    );
+                    self.invalidate();
+                }
+            }
+        }
+    }
+
+    fn invalidate(&self) {
+        if self
+            .envoronment
+            .lock()
+            .expect("envoronment mutex poisoned")
+            .take()
+            .is_some()
+        {
+            CacheCounters::increment(&self.counters.invalidations, 1);
+        }
+        if let Some(cache_directory) = &self.cache_directory {
+            delete_cache_file(cache_directory, &self.executable);
+        }
     }
 }
 
-impl CacheEntry for CacheEntryImpl {
-    fn get(&self) -> Option<ResolvedPythonEnv> {
+impl CacheEntryImpl {
+    /// Cached details (in memory or on disk), misses are counted by the callers.
+    fn lookup(&self) -> Option<ResolvedPythonEnv> {
         self.verify_in_memory_cache();
 
         // New scope to drop lock immediately after we have the value.
@@ -194,23 +380,52 @@ impl CacheEntry for CacheEntryImpl {
                 .expect("envoronment mutex poisoned")
                 .clone()
             {
+                CacheCounters::increment(&self.counters.hits, 1);
                 return Some(env);
             }
         }
 
         if let Some(ref cache_directory) = self.cache_directory {
-            let (env, mut symlinks) = get_cache_from_file(cache_directory, &self.executable)?;
-            self.envoronment
+            let validation = self
+                .settings
                 .lock()
-                .expect("envoronment mutex poisoned")
-                .replace(env.clone());
-            let mut locked_symlinks = self.symlinks.lock().expect("symlinks mutex poisoned");
-            locked_symlinks.clear();
-            locked_symlinks.append(&mut symlinks);
-            Some(env)
-        } else {
-            None
+                .expect("settings mutex poisoned")
+                .validation;
+            match get_cache_from_file(cache_directory, &self.executable, validation) {
+                CacheFileLookup::Valid(env, mut symlinks, fingerprint) => {
+                    CacheCounters::increment(&self.counters.hits, 1);
+                    self.envoronment
+                        .lock()
+                        .expect("envoronment mutex poisoned")
+                        .replace(env.clone());
+                    let mut locked_symlinks =
+                        self.symlinks.lock().expect("symlinks mutex poisoned");
+                    locked_symlinks.clear();
+                    locked_symlinks.append(&mut symlinks);
+                    *self.fingerprint.lock().expect("fingerprint mutex poisoned") = fingerprint;
+                    return Some(env);
+                }
+                CacheFileLookup::Invalidated => {
+                    CacheCounters::increment(&self.counters.invalidations, 1);
+                }
+                CacheFileLookup::Missing => (),
+            }
+        }
+        None
+    }
+}
+
+impl CacheEntry for CacheEntryImpl {
+    fn get(&self) -> Option<ResolvedPythonEnv> {
+        let env = self.lookup();
+        if env.is_none() {
+            CacheCounters::increment(&self.counters.misses, 1);
         }
+        env
+    }
+
+    fn get_after_lock(&self) -> Option<ResolvedPythonEnv> {
+        self.lookup()
     }
 
     fn store(&self, environment: ResolvedPythonEnv) {
@@ -230,6 +445,13 @@ impl CacheEntry for CacheEntryImpl {
         symlinks.sort();
         symlinks.dedup();
 
+        let settings = *self.settings.lock().expect("settings mutex poisoned");
+        let fingerprint = match settings.validation {
+            CacheValidation::Timestamps => None,
+            CacheValidation::Content => Some(EnvironmentFingerprint::from(&environment)),
+        };
+        *self.fingerprint.lock().expect("fingerprint mutex poisoned") = fingerprint.clone();
+
         {
             let mut locked_symlinks = self.symlinks.lock().expect("symlinks mutex poisoned");
             locked_symlinks.clear();
@@ -243,10 +465,32 @@ impl CacheEntry for CacheEntryImpl {
         trace!("Caching interpreter info for {:?}", self.executable);
 
         if let Some(ref cache_directory) = self.cache_directory {
-            store_cache_in_file(cache_directory, &self.executable, &environment, symlinks)
+            store_cache_in_file(
+                cache_directory,
+                &self.executable,
+                &environment,
+                symlinks,
+                fingerprint,
+            );
+
+            if let Some(max_entries) = settings.max_entries {
+                let stores = self
+                    .counters
+                    .stores_since_eviction
+                    .fetch_add(1, Ordering::Relaxed)
+                    + 1;
+                if stores >= get_eviction_slack(max_entries) {
+                    evict(cache_directory, max_entries, &self.counters);
+                }
+            }
         }
     }
 
+    fn lock_across_processes(&self) -> Option<CacheFileLock> {
+        let cache_directory = self.cache_directory.as_ref()?;
+        lock_cache_entry(cache_directory, &self.executable)
+    }
+
     fn track_symlinks(&self, symlinks: Vec<PathBuf>) {
         self.verify_in_memory_cache();
 
@@ -265,7 +509,14 @@ impl CacheEntry for CacheEntryImpl {
         }
 
         if let Some(ref cache_directory) = self.cache_directory {
-            if let Some((mut env, _)) = get_cache_from_file(cache_directory, &self.executable) {
+            let validation = self
+                .settings
+                .lock()
+                .expect("settings mutex poisoned")
+                .validation;
+            if let CacheFileLookup::Valid(mut env, _, _) =
+                get_cache_from_file(cache_directory, &self.executable, validation)
+            {
                 let mut all_symlinks = vec![];
                 all_symlinks.append(&mut env.symlinks.clone().unwrap_or_default());
                 all_symlinks.append(&mut symlinks.clone());
@@ -283,3 +534,58 @@ impl CacheEntry for CacheEntryImpl {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn store(cache: &CacheImpl, executable: PathBuf) {
+        let environment = ResolvedPythonEnv {
+            executable: executable.clone(),
+            prefix: PathBuf::from("/usr"),
+            version: "3.12.1".to_string(),
+            is64_bit: true,
+            symlinks: None,
+        };
+        let entry = cache.create_cache(executable);
+        entry.lock().unwrap().store(environment);
+    }
+
+    #[test]
+    fn reading_again_after_locking_is_not_another_miss() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let cache = CacheImpl::new(Some(temp_dir.path().join("cache")));
+        let entry = cache.create_cache(temp_dir.path().join("python"));
+        let entry = entry.lock().unwrap();
+
+        assert!(entry.get().is_none());
+        let _lock = entry.lock_across_processes();
+        assert!(entry.get_after_lock().is_none());
+        assert_eq!(cache.get_stats().misses, 1);
+        assert_eq!(cache.get_stats().hits, 0);
+    }
+
+    #[test]
+    fn evicts_once_the_limit_is_exceeded_by_the_slack() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let cache = CacheImpl::new(Some(temp_dir.path().join("cache")));
+        // Two entries are stored between evictions.
+        cache.set_max_entries(20);
+        for i in 0..21 {
+            store(&cache, temp_dir.path().join(format!("python{i}")));
+        }
+        assert_eq!(cache.get_stats().entries, 21);
+        assert_eq!(cache.get_stats().evictions, 0);
+
+        store(&cache, temp_dir.path().join("python21"));
+        assert_eq!(cache.get_stats().entries, 20);
+        assert_eq!(cache.get_stats().evictions, 2);
+
+        // Raising the limit does not evict, lowering it does.
+        cache.set_max_entries(30);
+        assert_eq!(cache.get_stats().evictions, 2);
+        cache.set_max_entries(15);
+        assert_eq!(cache.get_stats().entries, 15);
+        assert_eq!(cache.get_stats().evictions, 7);
+    }
+}
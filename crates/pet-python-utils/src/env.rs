@@ -9,7 +9,10 @@ use std::{
     time::SystemTime,
 };
 
-use crate::{cache::create_cache, executable::new_silent_command};
+use crate::{
+    cache::create_cache,
+    executable::{new_silent_command, output_with_timeout},
+};
 
 const PYTHON_INFO_JSON_SEPARATOR: &str = "093385e9-59f7-4a16-a604-14bf206256fe";
 const PYTHON_INFO_CMD:&str = "import json, sys; print('093385e9-59f7-4a16-a604-14bf206256fe');print(json.dumps({'version': '.'.join(str(n) for n in sys.version_info), 'sys_prefix': sys.prefix, 'executable': sys.executable, 'is64_bit': sys.maxsize > 2**32}))";
@@ -77,8 +80,17 @@ impl ResolvedPythonEnv {
         let cache = create_cache(executable.to_path_buf());
         let entry = cache.lock().expect("cache mutex poisoned");
         if let Some(env) = entry.get() {
-            Some(env)
-        } else if let Some(env) = get_interpreter_details(executable) {
+            return Some(env);
+        }
+        // Another process sharing the same cache directory could be resolving this executable,
+        // if so, wait for it to complete & use the details it cached.
+        let lock = entry.lock_across_processes();
+        if lock.is_some() {
+            if let Some(env) = entry.get_after_lock() {
+                return Some(env);
+            }
+        }
+        if let Some(env) = get_interpreter_details(executable) {
             entry.store(env.clone());
             Some(env)
         } else {
@@ -92,9 +104,7 @@ fn get_interpreter_details(executable: &Path) -> Option<ResolvedPythonEnv> {
     let executable = executable.to_str()?;
     let start = SystemTime::now();
     trace!("Executing Python: {} -c {}", executable, PYTHON_INFO_CMD);
-    let result = new_silent_command(executable)
-        .args(["-c", PYTHON_INFO_CMD])
-        .output();
+    let result = output_with_timeout(new_silent_command(executable).args(["-c", PYTHON_INFO_CMD]));
     match result {
         Ok(output) => {
             let output = String::from_utf8(output.stdout).unwrap().trim().to_string();
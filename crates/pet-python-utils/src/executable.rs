@@ -5,11 +5,21 @@ use lazy_static::lazy_static;
 use log::trace;
 use regex::Regex;
 use std::ffi::OsStr;
+use std::io::{self, Read};
+use std::process::{Command, Output, Stdio};
+use std::sync::{mpsc, RwLock};
+use std::thread;
+use std::time::{Duration, Instant};
 use std::{
     fs,
     path::{Path, PathBuf},
 };
 
+/// Directories that are never searched for environments, in addition to the well known folders such as `node_modules`.
+static IGNORED_DIRECTORIES: RwLock<Vec<PathBuf>> = RwLock::new(vec![]);
+/// Maximum time to wait for spawned processes to exit, no limit if None.
+static SPAWN_TIMEOUT: RwLock<Option<Duration>> = RwLock::new(None);
+
 lazy_static! {
     static ref WINDOWS_EXE: Regex =
         Regex::new(r"python(\d+\.?)*.exe").expect("error parsing Windows executable regex");
@@ -221,6 +231,24 @@ fn is_pyenv_shims_dir(path: &Path) -> bool {
     false
 }
 
+/// Sets the directories that are never searched for environments,
+/// either folder names (e.g. `build` or `data/cache`) or absolute paths (e.g. `/mnt/data`).
+pub fn set_ignored_directories(directories: Vec<PathBuf>) {
+    *IGNORED_DIRECTORIES
+        .write()
+        .expect("ignored directories lock poisoned") = directories;
+}
+
+fn is_ignored_directory(path: &Path, ignored_directories: &[PathBuf]) -> bool {
+    ignored_directories.iter().any(|ignored| {
+        if ignored.is_absolute() {
+            path.starts_with(ignored)
+        } else {
+            path.ends_with(ignored)
+        }
+    })
+}
+
 pub fn should_search_for_environments_in_path<P: AsRef<Path>>(path: &P) -> bool {
     // Never search in the .git folder
     // Never search in the node_modules folder
@@ -257,6 +285,13 @@ pub fn should_search_for_environments_in_path<P: AsRef<Path>>(path: &P) -> bool
             return false;
         }
     }
+    let ignored_directories = IGNORED_DIRECTORIES
+        .read()
+        .expect("ignored directories lock poisoned");
+    if is_ignored_directory(path.as_ref(), &ignored_directories) {
+        trace!("Ignoring configured folder: {:?}", path.as_ref());
+        return false;
+    }
 
     true
 }
@@ -277,6 +312,76 @@ pub fn new_silent_command(program: impl AsRef<OsStr>) -> std::process::Command {
     std::process::Command::new(program)
 }
 
+/// Sets the maximum time to wait for spawned processes to exit, no limit if None.
+pub fn set_spawn_timeout(timeout: Option<Duration>) {
+    *SPAWN_TIMEOUT.write().expect("spawn timeout lock poisoned") = timeout;
+}
+
+pub fn get_spawn_timeout() -> Option<Duration> {
+    *SPAWN_TIMEOUT.read().expect("spawn timeout lock poisoned")
+}
+
+/// Same as `Command::output`, however the process is killed if it does not exit within the spawn timeout.
+pub fn output_with_timeout(command: &mut Command) -> io::Result<Output> {
+    match get_spawn_timeout() {
+        Some(timeout) => output_within(command, timeout),
+        None => command.output(),
+    }
+}
+
+fn output_within(command: &mut Command, timeout: Duration) -> io::Result<Output> {
+    let mut child = command
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?;
+    // Read the output in the background, else the process could block on a full pipe.
+    let read = |pipe: Option<Box<dyn Read + Send>>| {
+        let (sender, receiver) = mpsc::channel();
+        thread::spawn(move || {
+            let mut buffer = vec![];
+            if let Some(mut pipe) = pipe {
+                let _ = pipe.read_to_end(&mut buffer);
+            }
+            let _ = sender.send(buffer);
+        });
+        receiver
+    };
+    let stdout = read(child.stdout.take().map(|pipe| Box::new(pipe) as _));
+    let stderr = read(child.stderr.take().map(|pipe| Box::new(pipe) as _));
+
+    let timed_out = || {
+        io::Error::new(
+            io::ErrorKind::TimedOut,
+            format!("process did not exit within {timeout:?}"),
+        )
+    };
+    let deadline = Instant::now() + timeout;
+    let status = loop {
+        if let Some(status) = child.try_wait()? {
+            break status;
+        }
+        if Instant::now() >= deadline {
+            let _ = child.kill();
+            let _ = child.wait();
+            return Err(timed_out());
+        }
+        thread::sleep(Duration::from_millis(10));
+    };
+    // The pipes stay open while a process started by the child (that outlives it) holds them,
+    // hence the output is only waited for until the deadline.
+    let collect = |receiver: mpsc::Receiver<Vec<u8>>| {
+        receiver
+            .recv_timeout(deadline.saturating_duration_since(Instant::now()))
+            .map_err(|_| timed_out())
+    };
+    Ok(Output {
+        status,
+        stdout: collect(stdout)?,
+        stderr: collect(stderr)?,
+    })
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -514,4 +619,66 @@ mod tests {
 
         let _ = fs::remove_dir_all(&temp_dir);
     }
+
+    #[test]
+    fn test_is_ignored_directory() {
+        let ignored = vec![
+            PathBuf::from("build"),
+            PathBuf::from("data/cache"),
+            #[cfg(unix)]
+            PathBuf::from("/mnt/data"),
+        ];
+        assert!(is_ignored_directory(
+            Path::new("/home/user/project/build"),
+            &ignored
+        ));
+        assert!(is_ignored_directory(
+            Path::new("/home/user/project/data/cache"),
+            &ignored
+        ));
+        assert!(!is_ignored_directory(
+            Path::new("/home/user/project/cache"),
+            &ignored
+        ));
+        assert!(!is_ignored_directory(
+            Path::new("/home/user/project/rebuild"),
+            &ignored
+        ));
+        #[cfg(unix)]
+        assert!(is_ignored_directory(
+            Path::new("/mnt/data/project"),
+            &ignored
+        ));
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_output_within_timeout() {
+        let output = output_within(
+            Command::new("sh").args(["-c", "echo hello"]),
+            Duration::from_secs(10),
+        )
+        .unwrap();
+        assert!(output.status.success());
+        assert_eq!(String::from_utf8_lossy(&output.stdout).trim(), "hello");
+
+        let start = Instant::now();
+        let err =
+            output_within(Command::new("sleep").arg("10"), Duration::from_millis(100)).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::TimedOut);
+        assert!(start.elapsed() < Duration::from_secs(5));
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_output_within_timeout_when_a_grandchild_holds_the_pipes() {
+        let start = Instant::now();
+        let err = output_within(
+            Command::new("sh").args(["-c", "sleep 10 & echo hello"]),
+            Duration::from_millis(500),
+        )
+        .unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::TimedOut);
+        assert!(start.elapsed() < Duration::from_secs(5));
+    }
 }
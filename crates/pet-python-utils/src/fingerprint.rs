@@ -0,0 +1,148 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT License.
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::{
+    fs::{self, File},
+    io::Read,
+    path::{Path, PathBuf},
+};
+
+use crate::env::ResolvedPythonEnv;
+
+/// Number of bytes read from the start of a file to generate its hash.
+/// Enough to cover the headers of an executable, while keeping the cost of validation low.
+const HASHED_BLOCK_SIZE: u64 = 4096;
+
+/// How cached interpreter details are validated before being used.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum CacheValidation {
+    /// Compare the mtime (and ctime where available) of the executables.
+    #[default]
+    Timestamps,
+    /// In addition to the timestamps, compare the inode, size & a hash of the first block of the executables,
+    /// as well as the contents of the `pyvenv.cfg` file in the prefix.
+    /// Useful when binaries are replaced while preserving mtimes (containers, `rsync -a`, Nix stores, etc.).
+    Content,
+}
+
+/// Identifies the contents of a file without reading the whole file.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FileFingerprint {
+    pub path: PathBuf,
+    pub inode: Option<u64>,
+    pub size: u64,
+    pub hash: String,
+}
+
+impl FileFingerprint {
+    pub fn from(path: &Path) -> Option<FileFingerprint> {
+        let metadata = fs::metadata(path).ok()?;
+        let mut block = Vec::new();
+        File::open(path)
+            .ok()?
+            .take(HASHED_BLOCK_SIZE)
+            .read_to_end(&mut block)
+            .ok()?;
+        let mut hasher = Sha256::new();
+        hasher.update(&block);
+        Some(FileFingerprint {
+            path: path.to_path_buf(),
+            inode: get_inode(&metadata),
+            size: metadata.len(),
+            hash: format!("{:x}", hasher.finalize()),
+        })
+    }
+}
+
+/// Fingerprint of the files that make up a Python environment.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EnvironmentFingerprint {
+    pub executables: Vec<FileFingerprint>,
+    pub pyvenv_cfg: Option<FileFingerprint>,
+}
+
+impl EnvironmentFingerprint {
+    pub fn from(environment: &ResolvedPythonEnv) -> EnvironmentFingerprint {
+        let mut executables = environment.symlinks.clone().unwrap_or_default();
+        executables.push(environment.executable.clone());
+        executables.sort();
+        executables.dedup();
+        EnvironmentFingerprint {
+            executables: executables
+                .iter()
+                .filter_map(|exe| FileFingerprint::from(exe))
+                .collect(),
+            pyvenv_cfg: FileFingerprint::from(&environment.prefix.join("pyvenv.cfg")),
+        }
+    }
+
+    /// Whether the files of the environment are still the same as when this fingerprint was generated.
+    pub fn is_current(&self, environment: &ResolvedPythonEnv) -> bool {
+        *self == EnvironmentFingerprint::from(environment)
+    }
+}
+
+#[cfg(unix)]
+fn get_inode(metadata: &fs::Metadata) -> Option<u64> {
+    use std::os::unix::fs::MetadataExt;
+    Some(metadata.ino())
+}
+
+#[cfg(not(unix))]
+fn get_inode(_metadata: &fs::Metadata) -> Option<u64> {
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn create_env(prefix: &Path) -> ResolvedPythonEnv {
+        let executable = prefix.join("python");
+        fs::write(&executable, "#!binary v1").unwrap();
+        ResolvedPythonEnv {
+            executable: executable.clone(),
+            prefix: prefix.to_path_buf(),
+            version: "3.12.0".to_string(),
+            is64_bit: true,
+            symlinks: Some(vec![executable]),
+        }
+    }
+
+    #[test]
+    fn fingerprint_changes_when_executable_is_replaced() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let env = create_env(temp_dir.path());
+        let fingerprint = EnvironmentFingerprint::from(&env);
+        assert!(fingerprint.is_current(&env));
+
+        // Same size, different contents, as if a rebuilt binary was copied over preserving mtime.
+        let modified = fs::metadata(&env.executable).unwrap().modified().unwrap();
+        fs::write(&env.executable, "#!binary v2").unwrap();
+        File::options()
+            .write(true)
+            .open(&env.executable)
+            .unwrap()
+            .set_modified(modified)
+            .unwrap();
+
+        assert!(!fingerprint.is_current(&env));
+    }
+
+    #[test]
+    fn fingerprint_changes_when_pyvenv_cfg_changes() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let env = create_env(temp_dir.path());
+        let fingerprint = EnvironmentFingerprint::from(&env);
+        assert!(fingerprint.pyvenv_cfg.is_none());
+
+        fs::write(temp_dir.path().join("pyvenv.cfg"), "home = /usr/bin").unwrap();
+
+        assert!(!fingerprint.is_current(&env));
+    }
+}
@@ -1,18 +1,22 @@
 // Copyright (c) Microsoft Corporation.
 // Licensed under the MIT License.
 
-use log::{error, trace};
+use log::{error, trace, warn};
 use pet_fs::path::norm_case;
 use serde::{Deserialize, Serialize};
 use sha2::{Digest, Sha256};
 use std::{
-    fs::{self, File},
-    io::BufReader,
+    fs::{self, File, TryLockError},
+    io::{self, BufReader, BufWriter, Write},
     path::{Path, PathBuf},
-    time::SystemTime,
+    process, thread,
+    time::{Duration, Instant, SystemTime},
 };
 
-use crate::env::ResolvedPythonEnv;
+use crate::{
+    env::ResolvedPythonEnv,
+    fingerprint::{CacheValidation, EnvironmentFingerprint},
+};
 
 /// Represents a file path with its modification time and optional creation time.
 /// Creation time (ctime) is optional because many Linux filesystems (ext4, etc.)
@@ -20,11 +24,32 @@ use crate::env::ResolvedPythonEnv;
 /// See: https://github.com/microsoft/python-environment-tools/issues/223
 type FilePathWithMTimeCTime = (PathBuf, SystemTime, Option<SystemTime>);
 
+/// Suffix of the temporary files written before being renamed into the final cache file.
+const TEMP_FILE_SUFFIX: &str = ".tmp";
+/// Temporary files older than this are assumed to have been left behind by a crashed process.
+const STALE_TEMP_FILE_AGE: Duration = Duration::from_secs(60 * 10);
+/// Name of the file used to coordinate access to the cache directory across processes.
+/// Readers & writers hold a shared lock, clearing & evicting requires an exclusive lock.
+const DIRECTORY_LOCK_FILE: &str = "cache.lock";
+/// Extension of the files used to ensure only one process resolves an executable at a time.
+const ENTRY_LOCK_FILE_EXTENSION: &str = "lock";
+/// How long to wait for another process to finish resolving an executable, before giving up.
+const ENTRY_LOCK_TIMEOUT: Duration = Duration::from_secs(30);
+const ENTRY_LOCK_POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// An advisory lock on a file in the cache directory, released when dropped.
+pub struct CacheFileLock {
+    _file: File,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 struct CacheEntry {
     pub environment: ResolvedPythonEnv,
     pub symlinks: Vec<FilePathWithMTimeCTime>,
+    /// Only available when the entry was cached with content based validation.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fingerprint: Option<EnvironmentFingerprint>,
 }
 
 pub fn generate_cache_file(cache_directory: &Path, executable: &PathBuf) -> PathBuf {
@@ -33,19 +58,147 @@ pub fn generate_cache_file(cache_directory: &Path, executable: &PathBuf) -> Path
     cache_directory.join(format!("{}.4.json", generate_hash(executable)))
 }
 
+fn generate_entry_lock_file(cache_directory: &Path, executable: &PathBuf) -> PathBuf {
+    generate_cache_file(cache_directory, executable).with_extension(ENTRY_LOCK_FILE_EXTENSION)
+}
+
 pub fn delete_cache_file(cache_directory: &Path, executable: &PathBuf) {
     let cache_file = generate_cache_file(cache_directory, executable);
+    let _lock = lock_cache_directory(cache_directory, false);
     let _ = fs::remove_file(cache_file);
 }
 
+fn open_lock_file(lock_file: &Path) -> io::Result<File> {
+    if let Some(parent) = lock_file.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    File::options()
+        .read(true)
+        .write(true)
+        .create(true)
+        .truncate(false)
+        .open(lock_file)
+}
+
+/// Locks the cache directory, blocking until the lock is available.
+/// Returns None if the lock file could not be created (e.g. read-only directory),
+/// in which case we carry on without any cross-process coordination.
+fn lock_cache_directory(cache_directory: &Path, exclusive: bool) -> Option<CacheFileLock> {
+    let lock_file = cache_directory.join(DIRECTORY_LOCK_FILE);
+    let file = match open_lock_file(&lock_file) {
+        Ok(file) => file,
+        Err(err) => {
+            trace!("Unable to open cache lock file {:?} {:?}", lock_file, err);
+            return None;
+        }
+    };
+    let result = if exclusive {
+        file.lock()
+    } else {
+        file.lock_shared()
+    };
+    match result {
+        Ok(_) => Some(CacheFileLock { _file: file }),
+        Err(err) => {
+            warn!("Unable to lock cache directory {:?} {:?}", lock_file, err);
+            None
+        }
+    }
+}
+
+fn try_lock_file_exclusive(lock_file: &Path) -> Option<CacheFileLock> {
+    let file = open_lock_file(lock_file).ok()?;
+    match file.try_lock() {
+        Ok(_) => Some(CacheFileLock { _file: file }),
+        Err(_) => None,
+    }
+}
+
+/// Locks the cache entry of an executable, so that other processes sharing the same cache directory
+/// wait for this process to resolve & cache the executable instead of spawning Python themselves.
+/// Gives up waiting after a while, as the other process could be stuck spawning Python.
+pub fn lock_cache_entry(cache_directory: &Path, executable: &PathBuf) -> Option<CacheFileLock> {
+    let lock_file = generate_entry_lock_file(cache_directory, executable);
+    let file = open_lock_file(&lock_file).ok()?;
+    let start = Instant::now();
+    loop {
+        match file.try_lock() {
+            Ok(_) => return Some(CacheFileLock { _file: file }),
+            Err(TryLockError::WouldBlock) if start.elapsed() < ENTRY_LOCK_TIMEOUT => {
+                thread::sleep(ENTRY_LOCK_POLL_INTERVAL)
+            }
+            Err(err) => {
+                warn!(
+                    "Unable to lock cache entry {:?} for {:?} {:?}",
+                    lock_file, executable, err
+                );
+                return None;
+            }
+        }
+    }
+}
+
+/// Deletes all of the entries in the cache directory.
+/// The directory itself is left in place, so that other processes using it are not affected.
+pub fn clear_cache_directory(cache_directory: &Path) -> io::Result<()> {
+    if !cache_directory.exists() {
+        return Ok(());
+    }
+    let _lock = lock_cache_directory(cache_directory, true);
+    for entry in fs::read_dir(cache_directory)?.filter_map(Result::ok) {
+        let path = entry.path();
+        if path.file_name().and_then(|name| name.to_str()) == Some(DIRECTORY_LOCK_FILE) {
+            continue;
+        }
+        if path.is_dir() {
+            fs::remove_dir_all(&path)?;
+        } else if let Err(err) = fs::remove_file(&path) {
+            // Another process may be holding a lock on an entry (Windows does not allow deleting such files).
+            if path.extension().and_then(|ext| ext.to_str()) != Some(ENTRY_LOCK_FILE_EXTENSION) {
+                return Err(err);
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Result of looking up the cache file of an executable.
+#[derive(Debug)]
+#[allow(clippy::large_enum_variant)]
+pub enum CacheFileLookup {
+    /// The cache file exists and none of the tracked executables have changed.
+    Valid(
+        ResolvedPythonEnv,
+        Vec<FilePathWithMTimeCTime>,
+        Option<EnvironmentFingerprint>,
+    ),
+    /// There is no cache file for this executable (or it belongs to another executable).
+    Missing,
+    /// The cache file was stale or could not be parsed, and has been deleted.
+    Invalidated,
+}
+
 pub fn get_cache_from_file(
     cache_directory: &Path,
     executable: &PathBuf,
-) -> Option<(ResolvedPythonEnv, Vec<FilePathWithMTimeCTime>)> {
+    validation: CacheValidation,
+) -> CacheFileLookup {
     let cache_file = generate_cache_file(cache_directory, executable);
-    let file = File::open(cache_file.clone()).ok()?;
+    let _lock = lock_cache_directory(cache_directory, false);
+    let file = match File::open(cache_file.clone()) {
+        Ok(file) => file,
+        Err(_) => return CacheFileLookup::Missing,
+    };
     let reader = BufReader::new(file);
-    let cache: CacheEntry = serde_json::from_reader(reader).ok()?;
+    let cache: CacheEntry = match serde_json::from_reader(reader) {
+        Ok(cache) => cache,
+        Err(err) => {
+            // Most likely a file left behind by a process that crashed while writing it.
+            warn!("Deleting corrupt cache file {:?} {:?}", cache_file, err);
+            let _ = fs::remove_file(cache_file);
+            return CacheFileLookup::Invalidated;
+        }
+    };
     // Account for conflicts in the cache file
     // i.e. the hash generated is same for another file, remember we only take the first 16 chars.
     if !cache
@@ -61,7 +214,7 @@ pub fn get_cache_from_file(
             cache.environment,
             executable
         );
-        return None;
+        return CacheFileLookup::Missing;
     }
 
     // Check if any of the exes have changed since we last cached them.
@@ -79,13 +232,23 @@ pub fn get_cache_from_file(
             false
         }
     });
+    // Entries cached without a fingerprint cannot be validated, hence treated as stale.
+    let cache_is_valid = cache_is_valid
+        && match validation {
+            CacheValidation::Timestamps => true,
+            CacheValidation::Content => cache
+                .fingerprint
+                .as_ref()
+                .is_some_and(|fingerprint| fingerprint.is_current(&cache.environment)),
+        };
 
     if cache_is_valid {
         trace!("Using cache from {:?} for {:?}", cache_file, executable);
-        Some((cache.environment, cache.symlinks))
+        touch_cache_file(&cache_file);
+        CacheFileLookup::Valid(cache.environment, cache.symlinks, cache.fingerprint)
     } else {
         let _ = fs::remove_file(cache_file);
-        None
+        CacheFileLookup::Invalidated
     }
 }
 
@@ -94,6 +257,7 @@ pub fn store_cache_in_file(
     executable: &PathBuf,
     environment: &ResolvedPythonEnv,
     symlinks_with_times: Vec<FilePathWithMTimeCTime>,
+    fingerprint: Option<EnvironmentFingerprint>,
 ) {
     let cache_file = generate_cache_file(cache_directory, executable);
     match std::fs::create_dir_all(cache_directory) {
@@ -101,16 +265,12 @@ pub fn store_cache_in_file(
             let cache = CacheEntry {
                 environment: environment.clone(),
                 symlinks: symlinks_with_times,
+                fingerprint,
             };
-            match std::fs::File::create(cache_file.clone()) {
-                Ok(file) => {
-                    trace!("Caching {:?} in {:?}", executable, cache_file);
-                    match serde_json::to_writer_pretty(file, &cache) {
-                        Ok(_) => (),
-                        Err(err) => error!("Error writing cache file {:?} {:?}", cache_file, err),
-                    }
-                }
-                Err(err) => error!("Error creating cache file {:?} {:?}", cache_file, err),
+            trace!("Caching {:?} in {:?}", executable, cache_file);
+            let _lock = lock_cache_directory(cache_directory, false);
+            if let Err(err) = write_atomically(&cache_file, &cache) {
+                error!("Error writing cache file {:?} {:?}", cache_file, err);
             }
         }
         Err(err) => error!(
@@ -120,6 +280,110 @@ pub fn store_cache_in_file(
     }
 }
 
+/// Writes the cache entry into a temporary file & then renames it to the final name.
+/// This ensures readers never see a partially written file (e.g. if we crash half way).
+fn write_atomically(cache_file: &Path, cache: &CacheEntry) -> std::io::Result<()> {
+    let mut temp_file = cache_file.as_os_str().to_owned();
+    temp_file.push(format!(".{}{}", process::id(), TEMP_FILE_SUFFIX));
+    let temp_file = PathBuf::from(temp_file);
+
+    let result = File::create(&temp_file).and_then(|file| {
+        let mut writer = BufWriter::new(file);
+        serde_json::to_writer_pretty(&mut writer, cache)?;
+        writer.flush()?;
+        writer.get_ref().sync_all()
+    });
+    match result.and_then(|_| fs::rename(&temp_file, cache_file)) {
+        Ok(_) => Ok(()),
+        Err(err) => {
+            let _ = fs::remove_file(&temp_file);
+            Err(err)
+        }
+    }
+}
+
+/// Updates the mtime of the cache file, so that the least recently used entries are evicted first.
+fn touch_cache_file(cache_file: &Path) {
+    if let Ok(file) = File::options().write(true).open(cache_file) {
+        let _ = file.set_modified(SystemTime::now());
+    }
+}
+
+/// Returns the cache files in the cache directory along with their size & last time they were used.
+/// Temporary files that are still being written are excluded.
+pub fn list_cache_files(cache_directory: &Path) -> Vec<(PathBuf, u64, SystemTime)> {
+    let mut files = vec![];
+    if let Ok(entries) = fs::read_dir(cache_directory) {
+        for entry in entries.filter_map(Result::ok) {
+            let path = entry.path();
+            if path.extension().and_then(|ext| ext.to_str()) != Some("json") {
+                continue;
+            }
+            if let Ok(metadata) = entry.metadata() {
+                if metadata.is_file() {
+                    let modified = metadata.modified().unwrap_or(SystemTime::UNIX_EPOCH);
+                    files.push((path, metadata.len(), modified));
+                }
+            }
+        }
+    }
+    files
+}
+
+/// Deletes the least recently used cache files, so that at most `max_entries` remain.
+/// Temporary files left behind by processes that crashed while writing them are deleted as well.
+/// Returns the number of cache files deleted.
+pub fn evict_cache_files(cache_directory: &Path, max_entries: usize) -> usize {
+    // Eviction is opportunistic, if some other thread or process is using the cache, then try again later.
+    let _lock = match try_lock_file_exclusive(&cache_directory.join(DIRECTORY_LOCK_FILE)) {
+        Some(lock) => lock,
+        None => return 0,
+    };
+    delete_stale_temp_files(cache_directory);
+
+    let mut files = list_cache_files(cache_directory);
+    if files.len() <= max_entries {
+        return 0;
+    }
+    files.sort_by_key(|(_, _, modified)| *modified);
+    let excess = files.len() - max_entries;
+    let mut evicted = 0;
+    for (file, _, _) in files.into_iter().take(excess) {
+        trace!("Evicting cache file {:?}", file);
+        if fs::remove_file(&file).is_ok() {
+            evicted += 1;
+        }
+        // Delete the corresponding entry lock file, unless some process is still resolving this executable.
+        let lock_file = file.with_extension(ENTRY_LOCK_FILE_EXTENSION);
+        if lock_file.exists() {
+            if let Some(_lock) = try_lock_file_exclusive(&lock_file) {
+                let _ = fs::remove_file(lock_file);
+            }
+        }
+    }
+    evicted
+}
+
+fn delete_stale_temp_files(cache_directory: &Path) {
+    if let Ok(entries) = fs::read_dir(cache_directory) {
+        for entry in entries.filter_map(Result::ok) {
+            let path = entry.path();
+            if !path.to_string_lossy().ends_with(TEMP_FILE_SUFFIX) {
+                continue;
+            }
+            let is_stale = entry
+                .metadata()
+                .and_then(|metadata| metadata.modified())
+                .map(|modified| modified.elapsed().unwrap_or_default() > STALE_TEMP_FILE_AGE)
+                .unwrap_or_default();
+            if is_stale {
+                trace!("Deleting stale temporary cache file {:?}", path);
+                let _ = fs::remove_file(path);
+            }
+        }
+    }
+}
+
 fn generate_hash(executable: &PathBuf) -> String {
     let mut hasher = Sha256::new();
     hasher.update(norm_case(executable).to_string_lossy().as_bytes());
@@ -132,6 +396,209 @@ fn generate_hash(executable: &PathBuf) -> String {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::time::Duration;
+
+    fn create_resolved_env(executable: &Path) -> ResolvedPythonEnv {
+        ResolvedPythonEnv {
+            executable: executable.to_path_buf(),
+            prefix: executable.parent().unwrap().to_path_buf(),
+            version: "3.12.0".to_string(),
+            is64_bit: true,
+            symlinks: Some(vec![executable.to_path_buf()]),
+        }
+    }
+
+    fn store(cache_directory: &Path, executable: &Path) {
+        fs::write(executable, "").unwrap();
+        let modified = executable.metadata().unwrap().modified().unwrap();
+        store_cache_in_file(
+            cache_directory,
+            &executable.to_path_buf(),
+            &create_resolved_env(executable),
+            vec![(executable.to_path_buf(), modified, None)],
+            None,
+        );
+    }
+
+    #[test]
+    fn store_and_read_cache_file_without_leaving_temp_files() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let cache_directory = temp_dir.path().join("cache");
+        let executable = temp_dir.path().join("python");
+        store(&cache_directory, &executable);
+
+        assert!(matches!(
+            get_cache_from_file(&cache_directory, &executable, CacheValidation::Timestamps),
+            CacheFileLookup::Valid(..)
+        ));
+        let files: Vec<PathBuf> = fs::read_dir(&cache_directory)
+            .unwrap()
+            .map(|e| e.unwrap().path())
+            .filter(|path| path.file_name().unwrap() != DIRECTORY_LOCK_FILE)
+            .collect();
+        assert_eq!(
+            files,
+            vec![generate_cache_file(&cache_directory, &executable)]
+        );
+    }
+
+    #[test]
+    fn content_validation_requires_a_current_fingerprint() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let cache_directory = temp_dir.path().join("cache");
+        let executable = temp_dir.path().join("python");
+
+        // Entries cached without a fingerprint are not trusted.
+        store(&cache_directory, &executable);
+        assert!(matches!(
+            get_cache_from_file(&cache_directory, &executable, CacheValidation::Content),
+            CacheFileLookup::Invalidated
+        ));
+
+        let env = create_resolved_env(&executable);
+        let modified = executable.metadata().unwrap().modified().unwrap();
+        store_cache_in_file(
+            &cache_directory,
+            &executable,
+            &env,
+            vec![(executable.clone(), modified, None)],
+            Some(EnvironmentFingerprint::from(&env)),
+        );
+        assert!(matches!(
+            get_cache_from_file(&cache_directory, &executable, CacheValidation::Content),
+            CacheFileLookup::Valid(_, _, Some(_))
+        ));
+
+        // Replace the contents while preserving the mtime.
+        fs::write(&executable, "rebuilt").unwrap();
+        File::options()
+            .write(true)
+            .open(&executable)
+            .unwrap()
+            .set_modified(modified)
+            .unwrap();
+        assert!(matches!(
+            get_cache_from_file(&cache_directory, &executable, CacheValidation::Content),
+            CacheFileLookup::Invalidated
+        ));
+    }
+
+    #[test]
+    fn corrupt_cache_file_is_invalidated_and_deleted() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let executable = temp_dir.path().join("python");
+        let cache_file = generate_cache_file(temp_dir.path(), &executable);
+        fs::write(&cache_file, "{\"environment\": {\"execu").unwrap();
+
+        assert!(matches!(
+            get_cache_from_file(temp_dir.path(), &executable, CacheValidation::Timestamps),
+            CacheFileLookup::Invalidated
+        ));
+        assert!(!cache_file.exists());
+        assert!(matches!(
+            get_cache_from_file(temp_dir.path(), &executable, CacheValidation::Timestamps),
+            CacheFileLookup::Missing
+        ));
+    }
+
+    #[test]
+    fn evicts_least_recently_used_cache_files() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let cache_directory = temp_dir.path().join("cache");
+        let executables: Vec<PathBuf> = (0..4)
+            .map(|i| temp_dir.path().join(format!("python{i}")))
+            .collect();
+        for (i, executable) in executables.iter().enumerate() {
+            store(&cache_directory, executable);
+            // Make the age of each entry explicit, oldest first.
+            let cache_file = generate_cache_file(&cache_directory, executable);
+            let modified = SystemTime::now() - Duration::from_secs(100 - i as u64);
+            File::options()
+                .write(true)
+                .open(cache_file)
+                .unwrap()
+                .set_modified(modified)
+                .unwrap();
+        }
+        // Reading an entry marks it as recently used.
+        assert!(matches!(
+            get_cache_from_file(
+                &cache_directory,
+                &executables[0],
+                CacheValidation::Timestamps
+            ),
+            CacheFileLookup::Valid(..)
+        ));
+
+        assert_eq!(evict_cache_files(&cache_directory, 2), 2);
+        let remaining: Vec<bool> = executables
+            .iter()
+            .map(|exe| generate_cache_file(&cache_directory, exe).exists())
+            .collect();
+        assert_eq!(remaining, vec![true, false, false, true]);
+        assert_eq!(evict_cache_files(&cache_directory, 2), 0);
+    }
+
+    #[test]
+    fn clear_deletes_entries_but_keeps_the_directory() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let cache_directory = temp_dir.path().join("cache");
+        let executable = temp_dir.path().join("python");
+        store(&cache_directory, &executable);
+        let entry_lock = lock_cache_entry(&cache_directory, &executable);
+        assert!(entry_lock.is_some());
+        drop(entry_lock);
+
+        clear_cache_directory(&cache_directory).unwrap();
+
+        assert!(cache_directory.exists());
+        assert!(list_cache_files(&cache_directory).is_empty());
+        assert!(!generate_entry_lock_file(&cache_directory, &executable).exists());
+    }
+
+    #[test]
+    fn entry_lock_waits_for_other_holder() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let cache_directory = temp_dir.path().to_path_buf();
+        let executable = temp_dir.path().join("python");
+
+        let lock = lock_cache_entry(&cache_directory, &executable).unwrap();
+        let lock_file = generate_entry_lock_file(&cache_directory, &executable);
+        assert!(try_lock_file_exclusive(&lock_file).is_none());
+
+        let waiter = {
+            let cache_directory = cache_directory.clone();
+            let executable = executable.clone();
+            thread::spawn(move || {
+                let start = Instant::now();
+                let lock = lock_cache_entry(&cache_directory, &executable);
+                (lock.is_some(), start.elapsed())
+            })
+        };
+        thread::sleep(Duration::from_millis(200));
+        drop(lock);
+
+        let (acquired, waited) = waiter.join().unwrap();
+        assert!(acquired);
+        assert!(waited >= Duration::from_millis(100));
+    }
+
+    #[test]
+    fn eviction_is_skipped_while_cache_is_in_use() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let cache_directory = temp_dir.path().join("cache");
+        for i in 0..3 {
+            store(
+                &cache_directory,
+                &temp_dir.path().join(format!("python{i}")),
+            );
+        }
+
+        let reader = lock_cache_directory(&cache_directory, false);
+        assert_eq!(evict_cache_files(&cache_directory, 1), 0);
+        drop(reader);
+        assert_eq!(evict_cache_files(&cache_directory, 1), 2);
+    }
 
     #[test]
     #[cfg(unix)]
@@ -4,6 +4,7 @@
 pub mod cache;
 pub mod env;
 pub mod executable;
+pub mod fingerprint;
 pub mod fs_cache;
 mod headers;
 pub mod platform_dirs;
@@ -5,4 +5,5 @@ pub mod cache;
 pub mod collect;
 pub mod environment;
 pub mod jsonrpc;
+pub mod ndjson;
 pub mod stdio;
@@ -0,0 +1,163 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT License.
+
+//! Streams the managers & environments as newline delimited JSON (one JSON object per line),
+//! as soon as they are reported, so that scripts can act on results before the search completes.
+//! Each line has a `type` of `manager`, `environment` or `summary` (always the last line).
+
+use pet_core::{manager::EnvManager, python_environment::PythonEnvironment, reporter::Reporter};
+use serde::Serialize;
+use std::{
+    io::{self, Write},
+    sync::Mutex,
+    time::Duration,
+};
+
+#[derive(Serialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+enum Line<'a> {
+    Manager(&'a EnvManager),
+    Environment(&'a PythonEnvironment),
+    #[serde(rename_all = "camelCase")]
+    Summary {
+        managers: usize,
+        environments: usize,
+        duration_ms: u128,
+    },
+}
+
+#[derive(Default)]
+struct Counts {
+    managers: usize,
+    environments: usize,
+}
+
+pub struct NdjsonReporter {
+    writer: Mutex<Box<dyn Write + Send>>,
+    counts: Mutex<Counts>,
+}
+
+impl NdjsonReporter {
+    pub fn new(writer: Box<dyn Write + Send>) -> Self {
+        NdjsonReporter {
+            writer: Mutex::new(writer),
+            counts: Mutex::new(Counts::default()),
+        }
+    }
+
+    /// Writes the summary line, this must be the last line written.
+    pub fn report_summary(&self, duration: Duration) {
+        let counts = self.counts.lock().expect("counts mutex poisoned");
+        self.write(&Line::Summary {
+            managers: counts.managers,
+            environments: counts.environments,
+            duration_ms: duration.as_millis(),
+        });
+    }
+
+    fn write(&self, line: &Line) {
+        let line = serde_json::to_string(line).expect("failed to serialize line as JSON");
+        let mut writer = self.writer.lock().expect("writer mutex poisoned");
+        // Flush after every line, else consumers would not get the results until the buffer is full.
+        // Errors are ignored, e.g. the consumer (`head -n 1`) has closed the pipe.
+        let _ = writeln!(writer, "{line}").and_then(|_| writer.flush());
+    }
+}
+
+impl Reporter for NdjsonReporter {
+    fn report_telemetry(&self, _event: &pet_core::telemetry::TelemetryEvent) {
+        //
+    }
+    fn report_manager(&self, manager: &EnvManager) {
+        self.counts.lock().expect("counts mutex poisoned").managers += 1;
+        self.write(&Line::Manager(manager));
+    }
+
+    fn report_environment(&self, env: &PythonEnvironment) {
+        self.counts
+            .lock()
+            .expect("counts mutex poisoned")
+            .environments += 1;
+        self.write(&Line::Environment(env));
+    }
+}
+
+/// Reporter that writes to the standard output.
+pub fn create_reporter() -> NdjsonReporter {
+    NdjsonReporter::new(Box::new(io::stdout()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pet_core::{
+        manager::EnvManagerType,
+        python_environment::{PythonEnvironmentBuilder, PythonEnvironmentKind},
+    };
+    use serde_json::Value;
+    use std::{path::PathBuf, sync::Arc};
+
+    /// Writer that only makes the data visible once flushed, to check each line is flushed as it is written.
+    #[derive(Clone, Default)]
+    struct SharedWriter {
+        pending: Arc<Mutex<Vec<u8>>>,
+        flushed: Arc<Mutex<Vec<u8>>>,
+    }
+
+    impl SharedWriter {
+        fn lines(&self) -> Vec<Value> {
+            String::from_utf8(self.flushed.lock().unwrap().clone())
+                .unwrap()
+                .lines()
+                .map(|line| serde_json::from_str(line).unwrap())
+                .collect()
+        }
+    }
+
+    impl Write for SharedWriter {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.pending.lock().unwrap().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+        fn flush(&mut self) -> io::Result<()> {
+            let mut pending = self.pending.lock().unwrap();
+            self.flushed.lock().unwrap().append(&mut pending);
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn each_manager_and_environment_is_flushed_as_a_line() {
+        let writer = SharedWriter::default();
+        let reporter = NdjsonReporter::new(Box::new(writer.clone()));
+
+        reporter.report_manager(&EnvManager::new(
+            PathBuf::from("/opt/conda/bin/conda"),
+            EnvManagerType::Conda,
+            None,
+        ));
+        let lines = writer.lines();
+        assert_eq!(lines.len(), 1);
+        assert_eq!(lines[0]["type"], "manager");
+        assert_eq!(lines[0]["executable"], "/opt/conda/bin/conda");
+
+        reporter.report_environment(
+            &PythonEnvironmentBuilder::new(Some(PythonEnvironmentKind::Conda))
+                .prefix(Some(PathBuf::from("/opt/conda")))
+                .build(),
+        );
+        let lines = writer.lines();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[1]["type"], "environment");
+        assert_eq!(lines[1]["prefix"], "/opt/conda");
+
+        reporter.report_summary(Duration::from_millis(42));
+        let lines = writer.lines();
+        assert_eq!(lines.len(), 3);
+        let summary = lines.last().unwrap();
+        assert_eq!(summary["type"], "summary");
+        assert_eq!(summary["managers"], 1);
+        assert_eq!(summary["environments"], 1);
+        assert_eq!(summary["durationMs"], 42);
+    }
+}
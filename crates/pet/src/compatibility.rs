@@ -0,0 +1,115 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT License.
+
+use std::{path::PathBuf, sync::Arc};
+
+use pet_core::{
+    manager::EnvManager,
+    python_environment::{PythonEnvironment, WorkspaceCompatibility},
+    reporter::Reporter,
+    telemetry::TelemetryEvent,
+};
+
+use crate::requires_python::{get_requires_python, is_compatible};
+
+/// Decorator that annotates the reported environments with their compatibility
+/// with the Python versions supported (`requires-python`) by each of the workspace folders.
+/// Workspace folders that do not declare the supported Python versions are ignored.
+pub struct CompatibilityReporter {
+    reporter: Arc<dyn Reporter>,
+    requirements: Vec<(PathBuf, String)>,
+}
+
+impl CompatibilityReporter {
+    pub fn new(reporter: Arc<dyn Reporter>, workspace_directories: &[PathBuf]) -> Self {
+        Self {
+            reporter,
+            requirements: workspace_directories
+                .iter()
+                .filter_map(|dir| Some((dir.clone(), get_requires_python(dir)?)))
+                .collect(),
+        }
+    }
+}
+
+impl Reporter for CompatibilityReporter {
+    fn report_telemetry(&self, event: &TelemetryEvent) {
+        self.reporter.report_telemetry(event);
+    }
+    fn report_manager(&self, manager: &EnvManager) {
+        self.reporter.report_manager(manager);
+    }
+    fn report_environment(&self, env: &PythonEnvironment) {
+        if self.requirements.is_empty() {
+            return self.reporter.report_environment(env);
+        }
+        let mut env = env.clone();
+        env.compatibility = Some(get_compatibility(&self.requirements, &env));
+        self.reporter.report_environment(&env);
+    }
+}
+
+fn get_compatibility(
+    requirements: &[(PathBuf, String)],
+    env: &PythonEnvironment,
+) -> Vec<WorkspaceCompatibility> {
+    requirements
+        .iter()
+        .map(|(workspace, requires_python)| WorkspaceCompatibility {
+            workspace: workspace.clone(),
+            requires_python: requires_python.clone(),
+            compatible: env
+                .version
+                .as_deref()
+                .and_then(|version| is_compatible(requires_python, version)),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pet_reporter::collect;
+    use std::fs;
+
+    #[test]
+    fn environments_are_annotated_for_workspaces_with_requires_python() {
+        let workspace = tempfile::tempdir().unwrap();
+        let other_workspace = tempfile::tempdir().unwrap();
+        fs::write(
+            workspace.path().join("pyproject.toml"),
+            "[project]\nrequires-python = \">=3.11\"\n",
+        )
+        .unwrap();
+        let collect_reporter = Arc::new(collect::create_reporter());
+        let reporter = CompatibilityReporter::new(
+            collect_reporter.clone(),
+            &[
+                workspace.path().to_path_buf(),
+                other_workspace.path().to_path_buf(),
+            ],
+        );
+
+        for version in [Some("3.8.10"), Some("3.12.1"), None] {
+            reporter.report_environment(&PythonEnvironment {
+                executable: Some(PathBuf::from("/usr/bin/python3")),
+                version: version.map(|v| v.to_string()),
+                ..Default::default()
+            });
+        }
+
+        let compatible: Vec<Option<bool>> = collect_reporter
+            .environments
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|env| {
+                let compatibility = env.compatibility.clone().unwrap();
+                assert_eq!(compatibility.len(), 1);
+                assert_eq!(compatibility[0].workspace, workspace.path());
+                compatibility[0].compatible
+            })
+            .collect();
+        assert_eq!(compatible, vec![Some(false), Some(true), None]);
+    }
+}
@@ -0,0 +1,344 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT License.
+
+//! Layered configuration files for the CLI & the server.
+//!
+//! Settings are taken from the following layers, highest precedence first:
+//! 1. Command line flags (`pet find`) or the `configure` request (`pet server`).
+//! 2. Environment variables, e.g. `PET_CONDA_EXECUTABLE` or `PET_ENVIRONMENT_DIRECTORIES`.
+//! 3. Workspace config file, the nearest `.pet.toml` in the workspace directory or its parents.
+//! 4. User config file, `$XDG_CONFIG_HOME/pet/config.toml` (defaults to `~/.config/pet/config.toml`).
+//!
+//! Each setting is taken from the highest layer that sets it, lists are not merged across layers.
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use pet_core::{os_environment::Environment, Configuration, LocatorKind};
+use pet_fs::path::expand_path;
+use pet_python_utils::{
+    cache::set_cache_directory,
+    executable::{set_ignored_directories, set_spawn_timeout},
+};
+use serde::{Deserialize, Serialize};
+
+/// Name of the workspace config file.
+pub const WORKSPACE_CONFIG_FILE: &str = ".pet.toml";
+
+/// Settings of a single layer, settings that are not set are taken from the lower layers.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub struct Settings {
+    pub workspace_directories: Option<Vec<PathBuf>>,
+    pub executables: Option<Vec<PathBuf>>,
+    pub conda_executable: Option<PathBuf>,
+    pub pipenv_executable: Option<PathBuf>,
+    pub poetry_executable: Option<PathBuf>,
+    /// Additional directories where virtual environments can be found.
+    pub environment_directories: Option<Vec<PathBuf>>,
+    pub cache_directory: Option<PathBuf>,
+    /// Directories that are never searched for environments, folder names (e.g. `build`) or absolute paths.
+    pub ignored_directories: Option<Vec<PathBuf>>,
+    pub disabled_locators: Option<Vec<LocatorKind>>,
+    /// Maximum number of seconds to wait for spawned processes (such as Python, conda & poetry) to exit,
+    /// 0 means there is no limit.
+    pub spawn_timeout: Option<u64>,
+}
+
+impl Settings {
+    /// Reads the settings from the config file, None if the file does not exist.
+    /// Paths starting with `~` are relative to the home directory, other relative paths are relative to the directory of the file.
+    pub fn read(file: &Path) -> Result<Option<Settings>, String> {
+        let contents = match fs::read_to_string(file) {
+            Ok(contents) => contents,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(err) => return Err(format!("Unable to read {file:?}: {err}")),
+        };
+        let settings: Settings = toml::from_str(&contents)
+            .map_err(|err| format!("Invalid config file {file:?}: {err}"))?;
+        let dir = file.parent().unwrap_or(Path::new(""));
+        Ok(Some(settings.resolve_paths(dir)))
+    }
+
+    /// Settings from the `PET_*` environment variables, as used by the command line flags.
+    pub fn from_env(environment: &dyn Environment) -> Settings {
+        let get_path = |key: &str| environment.get_env_var(key.to_string()).map(PathBuf::from);
+        Settings {
+            conda_executable: get_path("PET_CONDA_EXECUTABLE"),
+            pipenv_executable: get_path("PET_PIPENV_EXECUTABLE"),
+            poetry_executable: get_path("PET_POETRY_EXECUTABLE"),
+            environment_directories: environment
+                .get_env_var("PET_ENVIRONMENT_DIRECTORIES".to_string())
+                .map(|dirs| dirs.split(',').map(PathBuf::from).collect()),
+            cache_directory: get_path("PET_CACHE_DIRECTORY"),
+            ..Default::default()
+        }
+    }
+
+    /// Settings of `self`, with the settings that are not set taken from `lower`.
+    pub fn or(self, lower: Settings) -> Settings {
+        Settings {
+            workspace_directories: self.workspace_directories.or(lower.workspace_directories),
+            executables: self.executables.or(lower.executables),
+            conda_executable: self.conda_executable.or(lower.conda_executable),
+            pipenv_executable: self.pipenv_executable.or(lower.pipenv_executable),
+            poetry_executable: self.poetry_executable.or(lower.poetry_executable),
+            environment_directories: self
+                .environment_directories
+                .or(lower.environment_directories),
+            cache_directory: self.cache_directory.or(lower.cache_directory),
+            ignored_directories: self.ignored_directories.or(lower.ignored_directories),
+            disabled_locators: self.disabled_locators.or(lower.disabled_locators),
+            spawn_timeout: self.spawn_timeout.or(lower.spawn_timeout),
+        }
+    }
+
+    pub fn to_configuration(&self) -> Configuration {
+        Configuration {
+            workspace_directories: self.workspace_directories.clone(),
+            executables: self.executables.clone(),
+            conda_executable: self.conda_executable.clone(),
+            pipenv_executable: self.pipenv_executable.clone(),
+            poetry_executable: self.poetry_executable.clone(),
+            environment_directories: self.environment_directories.clone(),
+            cache_directory: self.cache_directory.clone(),
+            ignored_directories: self.ignored_directories.clone(),
+            disabled_locators: self.disabled_locators.clone(),
+            spawn_timeout: self
+                .spawn_timeout
+                .filter(|secs| *secs > 0)
+                .map(Duration::from_secs),
+        }
+    }
+
+    fn resolve_paths(self, dir: &Path) -> Settings {
+        let resolve = |path: PathBuf| {
+            let path = expand_path(path);
+            if path.is_absolute() {
+                path
+            } else {
+                dir.join(path)
+            }
+        };
+        let resolve_all = |paths: Option<Vec<PathBuf>>| {
+            paths.map(|paths| paths.into_iter().map(resolve).collect::<Vec<PathBuf>>())
+        };
+        // Tools can be referred to by name, e.g. `conda`, in which case they are looked up in the PATH.
+        let resolve_tool = |path: Option<PathBuf>| {
+            path.map(|path| {
+                if path.components().count() > 1 {
+                    resolve(path)
+                } else {
+                    path
+                }
+            })
+        };
+        Settings {
+            workspace_directories: resolve_all(self.workspace_directories),
+            executables: resolve_all(self.executables),
+            conda_executable: resolve_tool(self.conda_executable),
+            pipenv_executable: resolve_tool(self.pipenv_executable),
+            poetry_executable: resolve_tool(self.poetry_executable),
+            environment_directories: resolve_all(self.environment_directories),
+            cache_directory: self.cache_directory.map(resolve),
+            // Relative directories are folder names, matched anywhere.
+            ignored_directories: self.ignored_directories,
+            disabled_locators: self.disabled_locators,
+            spawn_timeout: self.spawn_timeout,
+        }
+    }
+}
+
+/// Settings of the config files & environment variables, merged.
+#[derive(Debug, Default, Clone)]
+pub struct LoadedSettings {
+    /// Config files that were found, lowest precedence first.
+    pub files: Vec<PathBuf>,
+    pub settings: Settings,
+}
+
+/// Loads the user config file, the workspace config file for the directory & the environment variables.
+pub fn load_settings(
+    environment: &dyn Environment,
+    workspace_directory: Option<&Path>,
+) -> Result<LoadedSettings, String> {
+    let mut loaded = LoadedSettings::default();
+    let workspace_directory =
+        workspace_directory.map(|dir| std::path::absolute(dir).unwrap_or(dir.to_path_buf()));
+    let files = [
+        get_user_config_file(environment),
+        workspace_directory
+            .as_deref()
+            .and_then(find_workspace_config_file),
+    ];
+    for file in files.into_iter().flatten() {
+        if let Some(settings) = Settings::read(&file)? {
+            loaded.settings = settings.or(loaded.settings);
+            loaded.files.push(file);
+        }
+    }
+    loaded.settings = Settings::from_env(environment).or(loaded.settings);
+    Ok(loaded)
+}
+
+/// Applies the settings that are global to the process, such as the cache directory & the spawn timeout.
+pub fn apply_global_configuration(config: &Configuration) {
+    // Changing the cache directory once set is not supported, hence an unset directory is ignored.
+    if let Some(cache_directory) = config.cache_directory.clone() {
+        set_cache_directory(cache_directory);
+    }
+    set_ignored_directories(config.ignored_directories.clone().unwrap_or_default());
+    set_spawn_timeout(config.spawn_timeout);
+}
+
+/// `$XDG_CONFIG_HOME/pet/config.toml`, defaults to `~/.config/pet/config.toml`.
+pub fn get_user_config_file(environment: &dyn Environment) -> Option<PathBuf> {
+    let config_home = environment
+        .get_env_var("XDG_CONFIG_HOME".to_string())
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| environment.get_user_home().map(|home| home.join(".config")))?;
+    Some(config_home.join("pet").join("config.toml"))
+}
+
+/// Nearest `.pet.toml` in the directory or its parents.
+pub fn find_workspace_config_file(directory: &Path) -> Option<PathBuf> {
+    directory
+        .ancestors()
+        .map(|dir| dir.join(WORKSPACE_CONFIG_FILE))
+        .find(|file| file.is_file())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::TestEnvironment;
+    use std::collections::HashMap;
+    use tempfile::tempdir;
+
+    #[test]
+    fn layers_in_order_of_precedence() {
+        let dir = tempdir().unwrap();
+        let home = dir.path().join("home");
+        let project = dir.path().join("project");
+        fs::create_dir_all(home.join(".config").join("pet")).unwrap();
+        fs::create_dir_all(project.join("src")).unwrap();
+        fs::write(
+            home.join(".config").join("pet").join("config.toml"),
+            r#"
+conda-executable = "/opt/conda/bin/conda"
+poetry-executable = "/usr/bin/poetry"
+pipenv-executable = "/usr/bin/pipenv"
+spawn-timeout = 30
+disabled-locators = ["Homebrew"]
+"#,
+        )
+        .unwrap();
+        fs::write(
+            project.join(WORKSPACE_CONFIG_FILE),
+            r#"
+poetry-executable = "tools/poetry"
+pipenv-executable = "pipenv"
+environment-directories = ["envs", "~/envs/*"]
+ignored-directories = ["build"]
+spawn-timeout = 10
+"#,
+        )
+        .unwrap();
+        let environment = TestEnvironment {
+            home: home.clone(),
+            vars: HashMap::from([(
+                "PET_CONDA_EXECUTABLE".to_string(),
+                "/usr/local/bin/conda".to_string(),
+            )]),
+        };
+
+        let loaded = load_settings(&environment, Some(&project.join("src"))).unwrap();
+        assert_eq!(
+            loaded.files,
+            vec![
+                home.join(".config").join("pet").join("config.toml"),
+                project.join(WORKSPACE_CONFIG_FILE)
+            ]
+        );
+        let settings = loaded.settings;
+        // Environment variables take precedence over the config files.
+        assert_eq!(
+            settings.conda_executable,
+            Some(PathBuf::from("/usr/local/bin/conda"))
+        );
+        // The workspace config file takes precedence over the user config file,
+        // with relative paths resolved relative to the workspace config file.
+        assert_eq!(
+            settings.poetry_executable,
+            Some(project.join("tools").join("poetry"))
+        );
+        assert_eq!(settings.pipenv_executable, Some(PathBuf::from("pipenv")));
+        assert_eq!(
+            settings.environment_directories,
+            Some(vec![
+                project.join("envs"),
+                expand_path(PathBuf::from("~/envs/*"))
+            ])
+        );
+        assert_eq!(
+            settings.ignored_directories,
+            Some(vec![PathBuf::from("build")])
+        );
+        assert_eq!(settings.spawn_timeout, Some(10));
+        assert_eq!(
+            settings.disabled_locators,
+            Some(vec![LocatorKind::Homebrew])
+        );
+
+        // Command line flags (or the configure request) take precedence over everything else.
+        let flags = Settings {
+            conda_executable: Some(PathBuf::from("/usr/bin/conda")),
+            ..Default::default()
+        };
+        let config = flags.or(settings).to_configuration();
+        assert_eq!(
+            config.conda_executable,
+            Some(PathBuf::from("/usr/bin/conda"))
+        );
+        assert_eq!(config.spawn_timeout, Some(Duration::from_secs(10)));
+    }
+
+    #[test]
+    fn zero_spawn_timeout_is_no_limit() {
+        let settings = Settings {
+            spawn_timeout: Some(0),
+            ..Default::default()
+        };
+        assert_eq!(settings.to_configuration().spawn_timeout, None);
+    }
+
+    #[test]
+    fn xdg_config_home_and_invalid_config_files() {
+        let dir = tempdir().unwrap();
+        let environment = TestEnvironment {
+            home: dir.path().join("home"),
+            vars: HashMap::from([(
+                "XDG_CONFIG_HOME".to_string(),
+                dir.path().join("xdg").to_string_lossy().to_string(),
+            )]),
+        };
+        let file = dir.path().join("xdg").join("pet").join("config.toml");
+        assert_eq!(get_user_config_file(&environment), Some(file.clone()));
+        assert_eq!(
+            load_settings(&environment, None).unwrap().settings,
+            Settings::default()
+        );
+
+        fs::create_dir_all(file.parent().unwrap()).unwrap();
+        fs::write(&file, "conda-exe = \"conda\"\n").unwrap();
+        assert!(load_settings(&environment, None)
+            .unwrap_err()
+            .contains("conda-exe"));
+        fs::write(&file, "disabled-locators = [\"Unknown\"]\n").unwrap();
+        assert!(load_settings(&environment, None).is_err());
+    }
+}
@@ -0,0 +1,304 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT License.
+
+//! In depth checks of the environments found, to explain why an environment is broken & how to fix it.
+//!
+//! Discovery only flags a few problems (such as a `python` symlink that no longer resolves),
+//! the checks here go further, e.g. venvs whose base interpreter was deleted or upgraded
+//! & conda environments whose conda installation no longer exists.
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use pet_conda::environments::get_conda_dir_from_history;
+use pet_core::{
+    python_environment::{PythonEnvironment, PythonEnvironmentKind},
+    pyvenv_cfg::PyVenvCfg,
+};
+use pet_python_utils::{env::ResolvedPythonEnv, executable::find_executable};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ProblemKind {
+    /// Error reported when the environment was discovered, e.g. the executable is a broken symlink.
+    DiscoveryError,
+    /// The prefix of the environment cannot be read, e.g. it was deleted or the permissions changed.
+    UnreadablePrefix,
+    /// The `home` directory in `pyvenv.cfg` no longer exists.
+    DanglingHome,
+    /// The `home` directory in `pyvenv.cfg` exists, but the Python the environment was created from does not.
+    MissingBaseInterpreter,
+    /// The version in `pyvenv.cfg` is not the version of the Python executable of the environment.
+    VersionMismatch,
+    /// The conda installation used to create the conda environment no longer exists.
+    MissingCondaInstallation,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Problem {
+    pub kind: ProblemKind,
+    pub message: String,
+    /// Suggested fix.
+    pub fix: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Diagnosis {
+    pub environment: PythonEnvironment,
+    pub problems: Vec<Problem>,
+}
+
+/// Checks the environment, spawning its Python executable to get the actual version when required.
+pub fn diagnose_environment(env: &PythonEnvironment) -> Vec<Problem> {
+    diagnose(env, &|executable| {
+        ResolvedPythonEnv::from(executable).map(|resolved| resolved.version)
+    })
+}
+
+fn diagnose(
+    env: &PythonEnvironment,
+    get_executable_version: &dyn Fn(&Path) -> Option<String>,
+) -> Vec<Problem> {
+    let mut problems = vec![];
+    let mut add = |kind: ProblemKind, message: String, fix: String| {
+        problems.push(Problem { kind, message, fix })
+    };
+
+    if let Some(error) = &env.error {
+        add(
+            ProblemKind::DiscoveryError,
+            error.clone(),
+            get_recreate_fix(env),
+        );
+    }
+
+    let prefix = match &env.prefix {
+        Some(prefix) => prefix,
+        None => return problems,
+    };
+    if let Err(err) = fs::read_dir(prefix) {
+        add(
+            ProblemKind::UnreadablePrefix,
+            format!("Unable to read the prefix {prefix:?}: {err}"),
+            format!("Check the permissions of {prefix:?}, or remove the environment if it is no longer required"),
+        );
+        // Nothing else can be checked.
+        return problems;
+    }
+
+    if let Some(cfg) = PyVenvCfg::find(prefix) {
+        if let Some(home) = &cfg.home {
+            if !home.exists() {
+                add(
+                    ProblemKind::DanglingHome,
+                    format!(
+                        "The home {home:?} in {:?} no longer exists, the Python the environment was created from was deleted or upgraded",
+                        cfg.file_path
+                    ),
+                    get_recreate_fix(env),
+                );
+            } else if !has_base_interpreter(home, &cfg) {
+                add(
+                    ProblemKind::MissingBaseInterpreter,
+                    format!(
+                        "The Python the environment was created from is no longer in {home:?}, it was deleted or upgraded"
+                    ),
+                    get_recreate_fix(env),
+                );
+            }
+        }
+        if let (Some(expected), Some(executable)) = (&cfg.version, &env.executable) {
+            if let Some(actual) = get_executable_version(executable) {
+                if get_release(expected) != get_release(&actual) {
+                    let fix = if get_release(expected).get(..2) == get_release(&actual).get(..2) {
+                        format!(
+                            "Upgrade the environment, e.g. `{} -m venv --upgrade {}`",
+                            executable.to_string_lossy(),
+                            prefix.to_string_lossy()
+                        )
+                    } else {
+                        // Packages installed for one minor version of Python are not available to another.
+                        get_recreate_fix(env)
+                    };
+                    add(
+                        ProblemKind::VersionMismatch,
+                        format!(
+                            "The version in {:?} is {expected}, however {executable:?} is version {actual}",
+                            cfg.file_path
+                        ),
+                        fix,
+                    );
+                }
+            }
+        }
+    }
+
+    if env.kind == Some(PythonEnvironmentKind::Conda) {
+        if let Some(conda_dir) = get_conda_dir_from_history(prefix) {
+            if !conda_dir.exists() {
+                add(
+                    ProblemKind::MissingCondaInstallation,
+                    format!("The conda installation {conda_dir:?} used to create the environment no longer exists"),
+                    format!(
+                        "Reinstall conda in {conda_dir:?}, or manage the environment by its path using another conda installation, e.g. `conda activate {}`",
+                        prefix.to_string_lossy()
+                    ),
+                );
+            }
+        }
+    }
+
+    problems
+}
+
+/// Whether the Python the virtual environment was created from still exists in the `home` directory.
+fn has_base_interpreter(home: &Path, cfg: &PyVenvCfg) -> bool {
+    if find_executable(home).is_some() {
+        return true;
+    }
+    match (cfg.version_major, cfg.version_minor) {
+        (Some(major), Some(minor)) => home.join(format!("python{major}.{minor}")).is_file(),
+        _ => false,
+    }
+}
+
+/// Major, minor & micro versions, e.g. `[3, 12, 1]` for `3.12.1.final.0`.
+fn get_release(version: &str) -> Vec<&str> {
+    version.split('.').take(3).collect()
+}
+
+/// Command to recreate the environment, using the tool that manages the environment.
+fn get_recreate_fix(env: &PythonEnvironment) -> String {
+    let prefix = env
+        .prefix
+        .clone()
+        .or_else(|| env.executable.clone())
+        .unwrap_or_default();
+    let project = env.project.clone().unwrap_or(PathBuf::from("<project>"));
+    match env.kind {
+        Some(PythonEnvironmentKind::Poetry) => format!(
+            "Recreate the environment by running `poetry env remove --all && poetry install` in {project:?}"
+        ),
+        Some(PythonEnvironmentKind::Pipenv) => format!(
+            "Recreate the environment by running `pipenv --rm && pipenv install` in {project:?}"
+        ),
+        Some(PythonEnvironmentKind::Uv) | Some(PythonEnvironmentKind::UvWorkspace) => {
+            format!("Recreate the environment by running `uv venv && uv sync` in {project:?}")
+        }
+        Some(PythonEnvironmentKind::Conda) => format!(
+            "Recreate the environment, e.g. `conda env export --prefix {0} > environment.yml`, then `conda env create --prefix {0} --file environment.yml --yes`",
+            prefix.to_string_lossy()
+        ),
+        _ => format!(
+            "Recreate the environment using an installed Python, e.g. `python3 -m venv --clear {}` & reinstall its packages",
+            prefix.to_string_lossy()
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    fn create_venv(prefix: &Path, home: &Path, version: &str) -> PythonEnvironment {
+        fs::create_dir_all(prefix.join("bin")).unwrap();
+        fs::write(prefix.join("bin").join("python"), "").unwrap();
+        fs::write(
+            prefix.join("pyvenv.cfg"),
+            format!("home = {}\nversion = {version}\n", home.to_string_lossy()),
+        )
+        .unwrap();
+        PythonEnvironment {
+            kind: Some(PythonEnvironmentKind::Venv),
+            prefix: Some(prefix.to_path_buf()),
+            executable: Some(prefix.join("bin").join("python")),
+            ..Default::default()
+        }
+    }
+
+    fn kinds(problems: Vec<Problem>) -> Vec<ProblemKind> {
+        problems.into_iter().map(|problem| problem.kind).collect()
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn venv_with_deleted_or_upgraded_base_interpreter() {
+        let dir = tempdir().unwrap();
+        let home = dir.path().join("base").join("bin");
+        fs::create_dir_all(&home).unwrap();
+        let version = |_: &Path| Some("3.12.1.final.0".to_string());
+
+        let env = create_venv(&dir.path().join("ok"), &home, "3.12.1");
+        fs::write(home.join("python3.12"), "").unwrap();
+        assert_eq!(diagnose(&env, &version), vec![]);
+
+        // The base interpreter was upgraded to 3.13, & the venv still points to 3.12.
+        fs::remove_file(home.join("python3.12")).unwrap();
+        fs::write(home.join("python3.13"), "").unwrap();
+        assert_eq!(
+            kinds(diagnose(&env, &|_| Some("3.13.0".to_string()))),
+            vec![
+                ProblemKind::MissingBaseInterpreter,
+                ProblemKind::VersionMismatch
+            ]
+        );
+
+        let env = create_venv(
+            &dir.path().join("dangling"),
+            &dir.path().join("gone"),
+            "3.12.1",
+        );
+        assert_eq!(
+            kinds(diagnose(&env, &version)),
+            vec![ProblemKind::DanglingHome]
+        );
+    }
+
+    #[test]
+    fn unreadable_prefix() {
+        let dir = tempdir().unwrap();
+        let env = PythonEnvironment {
+            kind: Some(PythonEnvironmentKind::Venv),
+            prefix: Some(dir.path().join("deleted")),
+            error: Some("Python executable is a broken symlink".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(
+            kinds(diagnose(&env, &|_| None)),
+            vec![ProblemKind::DiscoveryError, ProblemKind::UnreadablePrefix]
+        );
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn conda_env_with_deleted_conda_installation() {
+        let dir = tempdir().unwrap();
+        let prefix = dir.path().join("env");
+        fs::create_dir_all(prefix.join("conda-meta")).unwrap();
+        let conda = dir.path().join("miniconda3").join("bin").join("conda");
+        fs::write(
+            prefix.join("conda-meta").join("history"),
+            format!(
+                "==> 2024-02-28 23:05:07 <==\n# cmd: {} create -n env python=3.12\n",
+                conda.to_string_lossy()
+            ),
+        )
+        .unwrap();
+        let env = PythonEnvironment {
+            kind: Some(PythonEnvironmentKind::Conda),
+            prefix: Some(prefix),
+            ..Default::default()
+        };
+
+        let problems = diagnose(&env, &|_| None);
+        assert_eq!(problems.len(), 1);
+        assert_eq!(problems[0].kind, ProblemKind::MissingCondaInstallation);
+        assert!(problems[0].message.contains("miniconda3"));
+    }
+}
@@ -0,0 +1,272 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT License.
+
+use std::{path::PathBuf, sync::Arc};
+
+use log::trace;
+use pet_core::{
+    manager::{EnvManager, EnvManagerType},
+    python_environment::{PythonEnvironment, PythonEnvironmentKind},
+    reporter::Reporter,
+    telemetry::TelemetryEvent,
+};
+use pet_fs::path::norm_case;
+use serde::{Deserialize, Serialize};
+
+use crate::pep440::VersionSpecifiers;
+
+/// Criteria the reported environments must satisfy, all of the criteria provided must be satisfied.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EnvironmentFilter {
+    /// Environments of any of these kinds.
+    pub kinds: Option<Vec<PythonEnvironmentKind>>,
+    /// Version specifiers the version of the environment must satisfy, e.g. `>=3.11,<3.13`.
+    /// Environments with an unknown version are excluded.
+    pub version: Option<String>,
+    /// Environments of this project, i.e. the project of the environment or the folder containing the environment.
+    pub project: Option<PathBuf>,
+    /// Environments managed by any of these tools.
+    pub managers: Option<Vec<EnvManagerType>>,
+    /// Environments with (true) or without (false) an error.
+    pub has_error: Option<bool>,
+}
+
+impl EnvironmentFilter {
+    pub fn is_empty(&self) -> bool {
+        *self == EnvironmentFilter::default()
+    }
+
+    /// Validates the filter, returning the parsed version specifiers.
+    pub fn get_version_specifiers(&self) -> Result<Option<VersionSpecifiers>, String> {
+        match &self.version {
+            Some(version) => VersionSpecifiers::parse(version)
+                .filter(VersionSpecifiers::is_valid)
+                .map(Some)
+                .ok_or(format!("Invalid version specifiers {version:?}")),
+            None => Ok(None),
+        }
+    }
+}
+
+/// Decorator that only reports the environments that satisfy the filter, managers are always reported.
+pub struct FilterReporter {
+    reporter: Arc<dyn Reporter>,
+    filter: EnvironmentFilter,
+    version: Option<VersionSpecifiers>,
+}
+
+impl FilterReporter {
+    pub fn new(reporter: Arc<dyn Reporter>, filter: EnvironmentFilter) -> Result<Self, String> {
+        let version = filter.get_version_specifiers()?;
+        Ok(Self {
+            reporter,
+            filter: EnvironmentFilter {
+                project: filter.project.as_ref().map(norm_case),
+                ..filter
+            },
+            version,
+        })
+    }
+
+    fn matches(&self, env: &PythonEnvironment) -> bool {
+        let filter = &self.filter;
+        if let Some(kinds) = &filter.kinds {
+            if !env.kind.is_some_and(|kind| kinds.contains(&kind)) {
+                return false;
+            }
+        }
+        if let Some(version) = &self.version {
+            let contains = env
+                .version
+                .as_deref()
+                .and_then(|env_version| version.contains(env_version));
+            if contains != Some(true) {
+                return false;
+            }
+        }
+        if let Some(project) = &filter.project {
+            let in_project = env.project.as_ref().map(norm_case).as_ref() == Some(project)
+                || env
+                    .prefix
+                    .as_ref()
+                    .is_some_and(|prefix| norm_case(prefix).starts_with(project));
+            if !in_project {
+                return false;
+            }
+        }
+        if let Some(managers) = &filter.managers {
+            if !env
+                .manager
+                .as_ref()
+                .is_some_and(|manager| managers.contains(&manager.tool))
+            {
+                return false;
+            }
+        }
+        if let Some(has_error) = filter.has_error {
+            if env.error.is_some() != has_error {
+                return false;
+            }
+        }
+        true
+    }
+}
+
+impl Reporter for FilterReporter {
+    fn report_telemetry(&self, event: &TelemetryEvent) {
+        self.reporter.report_telemetry(event);
+    }
+    fn report_manager(&self, manager: &EnvManager) {
+        self.reporter.report_manager(manager);
+    }
+    fn report_environment(&self, env: &PythonEnvironment) {
+        if self.matches(env) {
+            self.reporter.report_environment(env);
+        } else {
+            trace!(
+                "Skip reporting environment {:?} as it does not match the filter {:?}",
+                env.executable.as_ref().or(env.prefix.as_ref()),
+                self.filter
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils;
+    use pet_reporter::collect;
+
+    fn create_env(
+        kind: PythonEnvironmentKind,
+        version: &str,
+        prefix: &str,
+        manager: Option<EnvManagerType>,
+    ) -> PythonEnvironment {
+        test_utils::create_env(kind, prefix, Some(version))
+            .manager(manager.map(|tool| EnvManager::new(PathBuf::from("/bin/tool"), tool, None)))
+            .build()
+    }
+
+    fn report(filter: EnvironmentFilter, envs: &[PythonEnvironment]) -> Vec<PathBuf> {
+        let collect_reporter = Arc::new(collect::create_reporter());
+        let reporter = FilterReporter::new(collect_reporter.clone(), filter).unwrap();
+        for env in envs {
+            reporter.report_environment(env);
+        }
+        let environments = collect_reporter.environments.lock().unwrap();
+        environments
+            .iter()
+            .map(|env| env.prefix.clone().unwrap())
+            .collect()
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn all_criteria_must_be_satisfied() {
+        let envs = [
+            create_env(
+                PythonEnvironmentKind::Conda,
+                "3.12.1",
+                "/home/user/src/foo/.conda",
+                Some(EnvManagerType::Conda),
+            ),
+            create_env(
+                PythonEnvironmentKind::Venv,
+                "3.11.9",
+                "/home/user/src/foo/.venv",
+                None,
+            ),
+            create_env(
+                PythonEnvironmentKind::Venv,
+                "3.10.4",
+                "/home/user/src/foo/.venv310",
+                None,
+            ),
+            create_env(
+                PythonEnvironmentKind::Venv,
+                "3.12.1",
+                "/home/user/src/bar/.venv",
+                None,
+            ),
+            create_env(
+                PythonEnvironmentKind::Pyenv,
+                "3.12.1",
+                "/home/user/.pyenv/versions/3.12.1",
+                Some(EnvManagerType::Pyenv),
+            ),
+        ];
+
+        assert_eq!(
+            report(
+                EnvironmentFilter {
+                    kinds: Some(vec![
+                        PythonEnvironmentKind::Conda,
+                        PythonEnvironmentKind::Venv
+                    ]),
+                    version: Some(">=3.11,<3.13".to_string()),
+                    project: Some(PathBuf::from("/home/user/src/foo")),
+                    ..Default::default()
+                },
+                &envs
+            ),
+            vec![
+                PathBuf::from("/home/user/src/foo/.conda"),
+                PathBuf::from("/home/user/src/foo/.venv")
+            ]
+        );
+        assert_eq!(
+            report(
+                EnvironmentFilter {
+                    managers: Some(vec![EnvManagerType::Conda, EnvManagerType::Pyenv]),
+                    ..Default::default()
+                },
+                &envs
+            ),
+            vec![
+                PathBuf::from("/home/user/src/foo/.conda"),
+                PathBuf::from("/home/user/.pyenv/versions/3.12.1")
+            ]
+        );
+        assert_eq!(
+            report(EnvironmentFilter::default(), &envs).len(),
+            envs.len()
+        );
+    }
+
+    #[test]
+    fn environments_with_errors_or_unknown_versions() {
+        let broken = PythonEnvironment {
+            prefix: Some(PathBuf::from("broken")),
+            error: Some("Python executable is a broken symlink".to_string()),
+            ..Default::default()
+        };
+        let envs = [
+            broken,
+            create_env(PythonEnvironmentKind::Venv, "3.12.1", "ok", None),
+        ];
+
+        let has_error = EnvironmentFilter {
+            has_error: Some(true),
+            ..Default::default()
+        };
+        assert_eq!(report(has_error, &envs), vec![PathBuf::from("broken")]);
+        // The version of the broken environment is not known.
+        let version = EnvironmentFilter {
+            version: Some(">=3".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(
+            report(version, &envs),
+            vec![envs[1].prefix.clone().unwrap()]
+        );
+
+        let invalid = EnvironmentFilter {
+            version: Some(">=three".to_string()),
+            ..Default::default()
+        };
+        assert!(FilterReporter::new(Arc::new(collect::create_reporter()), invalid).is_err());
+    }
+}
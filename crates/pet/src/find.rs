@@ -16,16 +16,19 @@ use pet_python_utils::executable::{
 };
 use pet_venv::try_environment_from_venv_dir;
 use pet_virtualenv::is_virtualenv_dir;
+use rayon::{ThreadPool, ThreadPoolBuilder};
 use serde::{Deserialize, Serialize};
 use std::collections::BTreeMap;
 use std::fs;
 use std::path::PathBuf;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
 use std::sync::Mutex;
+use std::thread;
 use std::time::Duration;
-use std::{sync::Arc, thread};
 use tracing::{info_span, instrument};
 
-use crate::locators::identify_python_environment_using_locators;
+use crate::locators::{get_enabled_locators, identify_python_environment_using_locators};
 
 pub struct Summary {
     pub total: Duration,
@@ -33,6 +36,48 @@ pub struct Summary {
     pub breakdown: BTreeMap<&'static str, Duration>,
 }
 
+/// Maximum number of threads used to discover environments, 0 means the number of CPUs.
+static PARALLELISM: AtomicUsize = AtomicUsize::new(0);
+/// Thread pool used to discover environments, along with the number of threads in the pool.
+static THREAD_POOL: Mutex<Option<(usize, Arc<ThreadPool>)>> = Mutex::new(None);
+
+/// Sets the maximum number of threads used to discover environments.
+/// Defaults to the number of CPUs if not provided (or 0).
+pub fn set_parallelism(jobs: Option<usize>) {
+    PARALLELISM.store(jobs.unwrap_or_default(), Ordering::Relaxed);
+}
+
+pub fn get_parallelism() -> usize {
+    match PARALLELISM.load(Ordering::Relaxed) {
+        0 => thread::available_parallelism()
+            .map(|n| n.get())
+            .unwrap_or(1),
+        jobs => jobs,
+    }
+}
+
+/// Returns the thread pool used to discover environments.
+/// Instead of spawning a thread per folder/locator, all of the work is queued in this bounded (work stealing) pool.
+fn get_thread_pool() -> Arc<ThreadPool> {
+    let parallelism = get_parallelism();
+    let mut thread_pool = THREAD_POOL.lock().expect("thread pool mutex poisoned");
+    if let Some((threads, pool)) = thread_pool.as_ref() {
+        if *threads == parallelism {
+            return pool.clone();
+        }
+    }
+    trace!("Creating thread pool with {} threads", parallelism);
+    let pool = Arc::new(
+        ThreadPoolBuilder::new()
+            .num_threads(parallelism)
+            .thread_name(|i| format!("pet-discovery-{i}"))
+            .build()
+            .expect("failed to create the thread pool"),
+    );
+    *thread_pool = Some((parallelism, pool.clone()));
+    pool
+}
+
 #[derive(Debug, Clone, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub enum SearchScope {
@@ -57,6 +102,8 @@ pub fn find_and_report_envs(
     }));
     let start = std::time::Instant::now();
 
+    let locators = &get_enabled_locators(locators, &configuration);
+
     // From settings
     let environment_directories = configuration.environment_directories.unwrap_or_default();
     let workspace_directories = configuration.workspace_directories.unwrap_or_default();
@@ -71,14 +118,14 @@ pub fn find_and_report_envs(
         _ => None,
     };
 
-    thread::scope(|s| {
+    get_thread_pool().install(|| rayon::scope(|s| {
         // 1. Find using known global locators.
-        s.spawn(|| {
+        s.spawn(|_| {
             // Find in all the finders
             let _span = info_span!("locators_phase").entered();
             let start = std::time::Instant::now();
             if search_global {
-                thread::scope(|s| {
+                rayon::scope(|s| {
                     for locator in locators.iter() {
                         if let Some(kind) = &search_kind {
                             if !locator.supported_categories().contains(kind) {
@@ -93,7 +140,7 @@ pub fn find_and_report_envs(
 
                         let locator = locator.clone();
                         let summary = summary.clone();
-                        s.spawn(move || {
+                        s.spawn(move |_| {
                             let locator_name = format!("{:?}", locator.get_kind());
                             let _span = info_span!("locator_find", locator = %locator_name).entered();
                             let start = std::time::Instant::now();
@@ -120,7 +167,7 @@ pub fn find_and_report_envs(
                 .insert("Locators", start.elapsed());
         });
         // Step 2: Search in PATH variable
-        s.spawn(|| {
+        s.spawn(|_| {
             let _span = info_span!("path_search_phase").entered();
             let start = std::time::Instant::now();
             if search_global {
@@ -150,7 +197,7 @@ pub fn find_and_report_envs(
         let environment_directories: Arc<[PathBuf]> = environment_directories.into();
         let environment_directories_for_step3 = environment_directories.clone();
         let summary_for_step3 = summary.clone();
-        s.spawn(move || {
+        s.spawn(move |_| {
             let _span = info_span!("global_virtualenvs_phase").entered();
             let start = std::time::Instant::now();
             if search_global {
@@ -211,10 +258,10 @@ pub fn find_and_report_envs(
         // & users can have a lot of workspace folders and can have a large number fo files/directories
         // that could the discovery.
         let summary_for_step4 = summary.clone();
-        s.spawn(move || {
+        s.spawn(move |_| {
             let _span = info_span!("workspace_search_phase").entered();
             let start = std::time::Instant::now();
-            thread::scope(|s| {
+            rayon::scope(|s| {
                 // Find environments in the workspace folders.
                 if !workspace_directories.is_empty() {
                     trace!(
@@ -227,7 +274,7 @@ pub fn find_and_report_envs(
                     for workspace_folder in workspace_directories {
                         let global_env_search_paths = global_env_search_paths.clone();
                         let environment_directories = environment_directories.clone();
-                        s.spawn(move || {
+                        s.spawn(move |_| {
                             find_python_environments_in_workspace_folder_recursive(
                                 &workspace_folder,
                                 reporter,
@@ -258,7 +305,7 @@ pub fn find_and_report_envs(
                 .breakdown
                 .insert("Workspaces", start.elapsed());
         });
-    });
+    }));
     summary.lock().expect("summary mutex poisoned").total = start.elapsed();
 
     summary
@@ -343,20 +390,22 @@ fn find_python_environments(
     if paths.is_empty() {
         return;
     }
-    thread::scope(|s| {
-        for item in paths {
-            let locators = locators.clone();
-            let item = item.clone();
-            s.spawn(move || {
-                find_python_environments_in_paths_with_locators(
-                    &[item],
-                    &locators,
-                    reporter,
-                    is_workspace_folder,
-                    global_env_search_paths,
-                );
-            });
-        }
+    get_thread_pool().install(|| {
+        rayon::scope(|s| {
+            for item in paths {
+                let locators = locators.clone();
+                let item = item.clone();
+                s.spawn(move |_| {
+                    find_python_environments_in_paths_with_locators(
+                        &[item],
+                        &locators,
+                        reporter,
+                        is_workspace_folder,
+                        global_env_search_paths,
+                    );
+                });
+            }
+        })
     });
 }
 
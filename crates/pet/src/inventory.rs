@@ -0,0 +1,235 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT License.
+
+//! Inventory of the managers & environments found on a machine (`pet export`),
+//! so that inventories exported on different machines or at different times can be compared (`pet diff`).
+
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use pet_core::{
+    manager::EnvManager, os_environment::Environment, python_environment::PythonEnvironment,
+};
+use serde::{Deserialize, Serialize};
+
+/// Version of the inventory format, to be bumped when the format changes in a way older versions cannot read.
+pub const SCHEMA_VERSION: u32 = 1;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Host {
+    pub hostname: Option<String>,
+    /// Operating system, e.g. `linux`, `macos` or `windows`.
+    pub os: String,
+    /// Architecture of the CPU, e.g. `x86_64` or `aarch64`.
+    pub arch: String,
+}
+
+impl Host {
+    pub fn from(environment: &dyn Environment) -> Host {
+        Host {
+            hostname: get_hostname(environment),
+            os: std::env::consts::OS.to_string(),
+            arch: std::env::consts::ARCH.to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Inventory {
+    pub schema_version: u32,
+    /// Version of pet that exported the inventory.
+    pub pet_version: String,
+    /// Seconds since the Unix epoch.
+    pub exported_at: u64,
+    pub host: Host,
+    pub managers: Vec<EnvManager>,
+    pub environments: Vec<PythonEnvironment>,
+}
+
+impl Inventory {
+    pub fn new(
+        host: Host,
+        mut managers: Vec<EnvManager>,
+        mut environments: Vec<PythonEnvironment>,
+    ) -> Inventory {
+        managers.sort_by(|a, b| a.executable.cmp(&b.executable));
+        managers.dedup();
+        environments.sort();
+        Inventory {
+            schema_version: SCHEMA_VERSION,
+            pet_version: env!("CARGO_PKG_VERSION").to_string(),
+            exported_at: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|duration| duration.as_secs())
+                .unwrap_or_default(),
+            host,
+            managers,
+            environments,
+        }
+    }
+
+    pub fn read(file: &Path) -> Result<Inventory, String> {
+        let contents =
+            fs::read_to_string(file).map_err(|err| format!("Unable to read {file:?}: {err}"))?;
+        let inventory: Inventory = serde_json::from_str(&contents)
+            .map_err(|err| format!("{file:?} is not an inventory exported by pet: {err}"))?;
+        if inventory.schema_version > SCHEMA_VERSION {
+            return Err(format!(
+                "{file:?} has schema version {}, however only versions up to {SCHEMA_VERSION} are supported, upgrade pet to compare it",
+                inventory.schema_version
+            ));
+        }
+        Ok(inventory)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VersionChange {
+    /// The environment in the newer inventory.
+    pub environment: PythonEnvironment,
+    pub previous_version: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InventoryDiff {
+    pub added: Vec<PythonEnvironment>,
+    pub removed: Vec<PythonEnvironment>,
+    pub version_changed: Vec<VersionChange>,
+}
+
+impl InventoryDiff {
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.version_changed.is_empty()
+    }
+}
+
+/// Environments added, removed or whose version changed between the two inventories.
+/// Environments are matched by their prefix (or executable when there is no prefix).
+pub fn diff(old: &Inventory, new: &Inventory) -> InventoryDiff {
+    let old_envs = get_environments_by_key(&old.environments);
+    let new_envs = get_environments_by_key(&new.environments);
+
+    let mut result = InventoryDiff::default();
+    for (key, env) in new_envs.iter() {
+        match old_envs.get(key) {
+            None => result.added.push((*env).clone()),
+            Some(old_env) if old_env.version != env.version => {
+                result.version_changed.push(VersionChange {
+                    environment: (*env).clone(),
+                    previous_version: old_env.version.clone(),
+                })
+            }
+            Some(_) => {}
+        }
+    }
+    for (key, env) in old_envs.iter() {
+        if !new_envs.contains_key(key) {
+            result.removed.push((*env).clone());
+        }
+    }
+    result
+}
+
+fn get_environments_by_key(
+    environments: &[PythonEnvironment],
+) -> BTreeMap<PathBuf, &PythonEnvironment> {
+    environments
+        .iter()
+        .filter_map(|env| {
+            env.prefix
+                .clone()
+                .or_else(|| env.executable.clone())
+                .map(|key| (key, env))
+        })
+        .collect()
+}
+
+fn get_hostname(environment: &dyn Environment) -> Option<String> {
+    let hostname = environment
+        .get_env_var("HOSTNAME".to_string())
+        .or_else(|| environment.get_env_var("COMPUTERNAME".to_string()))
+        .or_else(|| fs::read_to_string("/etc/hostname").ok())?;
+    let hostname = hostname.trim();
+    (!hostname.is_empty()).then(|| hostname.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils;
+    use pet_core::python_environment::PythonEnvironmentKind;
+    use tempfile::tempdir;
+
+    fn create_env(prefix: &str, version: Option<&str>) -> PythonEnvironment {
+        test_utils::create_env(PythonEnvironmentKind::Venv, prefix, version).build()
+    }
+
+    fn create_inventory(environments: Vec<PythonEnvironment>) -> Inventory {
+        let host = Host {
+            hostname: Some("build-01".to_string()),
+            os: "linux".to_string(),
+            arch: "x86_64".to_string(),
+        };
+        Inventory::new(host, vec![], environments)
+    }
+
+    #[test]
+    fn diff_added_removed_and_version_changed() {
+        let old = create_inventory(vec![
+            create_env("/envs/same", Some("3.12.1")),
+            create_env("/envs/upgraded", Some("3.11.4")),
+            create_env("/envs/deleted", Some("3.10.0")),
+        ]);
+        let new = create_inventory(vec![
+            create_env("/envs/created", None),
+            create_env("/envs/upgraded", Some("3.12.1")),
+            create_env("/envs/same", Some("3.12.1")),
+        ]);
+
+        let result = diff(&old, &new);
+        assert_eq!(result.added, vec![create_env("/envs/created", None)]);
+        assert_eq!(
+            result.removed,
+            vec![create_env("/envs/deleted", Some("3.10.0"))]
+        );
+        assert_eq!(
+            result.version_changed,
+            vec![VersionChange {
+                environment: create_env("/envs/upgraded", Some("3.12.1")),
+                previous_version: Some("3.11.4".to_string()),
+            }]
+        );
+        assert!(diff(&new, &new).is_empty());
+    }
+
+    #[test]
+    fn read_exported_inventory() {
+        let dir = tempdir().unwrap();
+        let file = dir.path().join("inventory.json");
+        let inventory = create_inventory(vec![create_env("/envs/venv", Some("3.12.1"))]);
+        fs::write(&file, serde_json::to_string_pretty(&inventory).unwrap()).unwrap();
+
+        let read = Inventory::read(&file).unwrap();
+        assert_eq!(read.schema_version, SCHEMA_VERSION);
+        assert_eq!(read.host, inventory.host);
+        assert_eq!(read.environments, inventory.environments);
+
+        let mut newer = serde_json::to_value(&inventory).unwrap();
+        newer["schemaVersion"] = (SCHEMA_VERSION + 1).into();
+        fs::write(&file, newer.to_string()).unwrap();
+        assert!(Inventory::read(&file)
+            .unwrap_err()
+            .contains("schema version"));
+
+        fs::write(&file, "[]").unwrap();
+        assert!(Inventory::read(&file).is_err());
+    }
+}
@@ -4,12 +4,18 @@
 use crate::find::find_and_report_envs;
 use crate::find::find_python_environments_in_workspace_folder_recursive;
 use crate::find::identify_python_executables_using_locators;
+use crate::find::set_parallelism;
 use crate::find::SearchScope;
-use crate::locators::create_locators;
+use crate::locators::{create_locators, get_enabled_locators};
 use lazy_static::lazy_static;
 use log::{error, info, trace, warn};
+use pet::compatibility::CompatibilityReporter;
+use pet::config::{apply_global_configuration, load_settings, Settings};
+use pet::filter::{EnvironmentFilter, FilterReporter};
 use pet::initialize_tracing;
+use pet::rank::rank_environments;
 use pet::resolve::resolve_environment;
+use pet::suggest::suggest_environment;
 use pet_conda::Conda;
 use pet_conda::CondaLocator;
 use pet_core::python_environment::PythonEnvironment;
@@ -19,7 +25,7 @@ use pet_core::telemetry::TelemetryEvent;
 use pet_core::{
     os_environment::{Environment, EnvironmentApi},
     reporter::Reporter,
-    Configuration, Locator,
+    Configuration, Locator, LocatorKind,
 };
 use pet_env_var_path::get_search_paths_from_env_variables;
 use pet_fs::glob::expand_glob_patterns;
@@ -30,7 +36,10 @@ use pet_jsonrpc::{
 use pet_poetry::Poetry;
 use pet_poetry::PoetryLocator;
 use pet_python_utils::cache::clear_cache;
-use pet_python_utils::cache::set_cache_directory;
+use pet_python_utils::cache::get_cache_stats;
+use pet_python_utils::cache::set_cache_max_entries;
+use pet_python_utils::cache::set_cache_validation;
+use pet_python_utils::fingerprint::CacheValidation;
 use pet_reporter::collect;
 use pet_reporter::{cache::CacheReporter, jsonrpc};
 use pet_telemetry::report_inaccuracies_identified_after_resolving;
@@ -43,7 +52,7 @@ use std::sync::Mutex;
 use std::time::Duration;
 use std::{
     ops::Deref,
-    path::PathBuf,
+    path::{Path, PathBuf},
     sync::{Arc, RwLock},
     thread,
     time::{Instant, SystemTime},
@@ -63,6 +72,13 @@ pub struct Context {
     os_environment: Arc<dyn Environment>,
 }
 
+impl Context {
+    /// Locators that are not disabled in the current configuration.
+    fn enabled_locators(&self) -> Arc<Vec<Arc<dyn Locator>>> {
+        get_enabled_locators(&self.locators, &self.configuration.read().unwrap())
+    }
+}
+
 static MISSING_ENVS_REPORTED: AtomicBool = AtomicBool::new(false);
 
 pub fn start_jsonrpc_server() {
@@ -88,8 +104,12 @@ pub fn start_jsonrpc_server() {
     handlers.add_request_handler("refresh", handle_refresh);
     handlers.add_request_handler("resolve", handle_resolve);
     handlers.add_request_handler("find", handle_find);
+    handlers.add_request_handler("suggest", handle_suggest);
+    handlers.add_request_handler("rank", handle_rank);
     handlers.add_request_handler("condaInfo", handle_conda_telemetry);
+    handlers.add_request_handler("condaEnvironmentDetails", handle_conda_environment_details);
     handlers.add_request_handler("clear", handle_clear_cache);
+    handlers.add_request_handler("cacheStats", handle_cache_stats);
     start_server(&handlers)
 }
 
@@ -108,6 +128,20 @@ pub struct ConfigureOptions {
     pub environment_directories: Option<Vec<PathBuf>>,
     /// Directory to cache the Python environment details.
     pub cache_directory: Option<PathBuf>,
+    /// Maximum number of entries to keep in the cache directory.
+    /// Least recently used entries are evicted first.
+    pub cache_max_entries: Option<usize>,
+    /// How cached Python environment details are validated before being used.
+    pub cache_validation: Option<CacheValidation>,
+    /// Maximum number of threads used to discover environments.
+    /// Defaults to the number of CPUs.
+    pub jobs: Option<usize>,
+    /// Directories that are never searched for environments, folder names (e.g. `build`) or absolute paths.
+    pub ignored_directories: Option<Vec<PathBuf>>,
+    /// Locators that are not used to discover environments.
+    pub disabled_locators: Option<Vec<LocatorKind>>,
+    /// Maximum number of seconds to wait for spawned processes (such as Python, conda & poetry) to exit.
+    pub spawn_timeout: Option<u64>,
 }
 
 /// Threshold for glob expansion duration before emitting a warning.
@@ -122,36 +156,62 @@ pub fn handle_configure(context: Arc<Context>, id: u32, params: Value) {
             thread::spawn(move || {
                 let now = Instant::now();
 
-                // Expand glob patterns before acquiring the write lock so we
-                // don't block readers/writers while traversing the filesystem.
-                let workspace_directories = configure_options.workspace_directories.map(|dirs| {
+                // Settings not provided are taken from the config files (of the first workspace folder) & environment variables.
+                let workspace_directory = configure_options
+                    .workspace_directories
+                    .as_ref()
+                    .and_then(|dirs| dirs.first().cloned());
+                let loaded = match load_settings(
+                    context.os_environment.as_ref(),
+                    workspace_directory.as_deref(),
+                ) {
+                    Ok(loaded) => {
+                        trace!("Loaded config files: {:?}", loaded.files);
+                        loaded.settings
+                    }
+                    Err(err) => {
+                        error!("Ignoring the config files: {}", err);
+                        Settings::from_env(context.os_environment.as_ref())
+                    }
+                };
+                let mut settings = Settings {
+                    workspace_directories: configure_options.workspace_directories,
+                    conda_executable: configure_options.conda_executable,
+                    pipenv_executable: configure_options.pipenv_executable,
+                    poetry_executable: configure_options.poetry_executable,
+                    environment_directories: configure_options.environment_directories,
+                    cache_directory: configure_options.cache_directory,
+                    ignored_directories: configure_options.ignored_directories,
+                    disabled_locators: configure_options.disabled_locators,
+                    spawn_timeout: configure_options.spawn_timeout,
+                    ..Default::default()
+                }
+                .or(loaded);
+
+                // Expand glob patterns (including those from the config files) before acquiring
+                // the write lock so we don't block readers/writers while traversing the filesystem.
+                let expand = |dirs: Vec<PathBuf>, name: &str| {
                     let start = Instant::now();
                     let result: Vec<PathBuf> = expand_glob_patterns(&dirs)
                         .into_iter()
                         .filter(|p| p.is_dir())
                         .collect();
                     trace!(
-                        "Expanded workspace directory patterns ({:?}) in {:?}",
+                        "Expanded {} directory patterns ({:?}) in {:?}",
+                        name,
                         dirs,
                         start.elapsed()
                     );
                     result
-                });
-                let environment_directories =
-                    configure_options.environment_directories.map(|dirs| {
-                        let start = Instant::now();
-                        let result: Vec<PathBuf> = expand_glob_patterns(&dirs)
-                            .into_iter()
-                            .filter(|p| p.is_dir())
-                            .collect();
-                        trace!(
-                            "Expanded environment directory patterns ({:?}) in {:?}",
-                            dirs,
-                            start.elapsed()
-                        );
-                        result
-                    });
-                let glob_elapsed = now.elapsed();
+                };
+                let glob_start = Instant::now();
+                settings.workspace_directories = settings
+                    .workspace_directories
+                    .map(|dirs| expand(dirs, "workspace"));
+                settings.environment_directories = settings
+                    .environment_directories
+                    .map(|dirs| expand(dirs, "environment"));
+                let glob_elapsed = glob_start.elapsed();
                 trace!("Glob expansion completed in {:?}", glob_elapsed);
                 if glob_elapsed >= GLOB_EXPANSION_WARN_THRESHOLD {
                     warn!(
@@ -161,19 +221,25 @@ pub fn handle_configure(context: Arc<Context>, id: u32, params: Value) {
                 }
 
                 let mut cfg = context.configuration.write().unwrap();
-                cfg.workspace_directories = workspace_directories;
-                cfg.conda_executable = configure_options.conda_executable;
-                cfg.environment_directories = environment_directories;
-                cfg.pipenv_executable = configure_options.pipenv_executable;
-                cfg.poetry_executable = configure_options.poetry_executable;
                 // We will not support changing the cache directories once set.
                 // No point, supporting such a use case.
-                if let Some(cache_directory) = configure_options.cache_directory {
-                    set_cache_directory(cache_directory.clone());
-                    cfg.cache_directory = Some(cache_directory);
+                let cache_directory = cfg.cache_directory.clone();
+                *cfg = settings.to_configuration();
+                cfg.cache_directory = cfg.cache_directory.clone().or(cache_directory);
+                apply_global_configuration(&cfg);
+                if let Some(validation) = configure_options.cache_validation {
+                    set_cache_validation(validation);
+                }
+                // Omitted when only some of the options change, e.g. the workspace folders.
+                if let Some(jobs) = configure_options.jobs {
+                    set_parallelism(Some(jobs));
                 }
                 trace!("Configuring locators: {:?}", cfg);
                 drop(cfg);
+                // Evicting lists the cache directory, hence not done while holding the lock.
+                if let Some(max_entries) = configure_options.cache_max_entries {
+                    set_cache_max_entries(max_entries);
+                }
                 let config = context.configuration.read().unwrap().clone();
                 for locator in context.locators.iter() {
                     locator.configure(&config);
@@ -199,6 +265,8 @@ pub struct RefreshOptions {
     /// Traditionally, search paths are workspace folders.
     /// Glob patterns are supported (e.g., "/home/user/*/venv", "**/.venv").
     pub search_paths: Option<Vec<PathBuf>>,
+    /// If provided, then only the environments that satisfy all of these criteria are reported.
+    pub filter: Option<EnvironmentFilter>,
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
@@ -225,12 +293,20 @@ pub fn handle_refresh(context: Arc<Context>, id: u32, params: Value) {
             let refresh_options = refresh_options.unwrap_or(RefreshOptions {
                 search_kind: None,
                 search_paths: None,
+                filter: None,
             });
+            let filter = refresh_options.filter.clone().unwrap_or_default();
+            if let Err(e) = filter.get_version_specifiers() {
+                error!("Invalid filter for refresh {:?}: {}", filter, e);
+                send_error(Some(id), -4, e);
+                return;
+            }
             // Start in a new thread, we can have multiple requests.
             thread::spawn(move || {
                 let _span = info_span!("handle_refresh",
                     search_kind = ?refresh_options.search_kind,
-                    has_search_paths = refresh_options.search_paths.is_some()
+                    has_search_paths = refresh_options.search_paths.is_some(),
+                    has_filter = !filter.is_empty()
                 )
                 .entered();
 
@@ -238,11 +314,17 @@ pub fn handle_refresh(context: Arc<Context>, id: u32, params: Value) {
                 let lock = REFRESH_LOCK.lock().expect("REFRESH_LOCK mutex poisoned");
 
                 let config = context.configuration.read().unwrap().clone();
-                let reporter = Arc::new(CacheReporter::new(Arc::new(jsonrpc::create_reporter(
-                    refresh_options.search_kind,
+                let (config, search_scope) = build_refresh_config(&refresh_options, config);
+                let filter_reporter = FilterReporter::new(
+                    Arc::new(jsonrpc::create_reporter(refresh_options.search_kind)),
+                    filter,
+                )
+                .expect("filter validated before refreshing");
+                let reporter = Arc::new(CacheReporter::new(Arc::new(CompatibilityReporter::new(
+                    Arc::new(filter_reporter),
+                    config.workspace_directories.as_deref().unwrap_or_default(),
                 ))));
 
-                let (config, search_scope) = build_refresh_config(&refresh_options, config);
                 if refresh_options.search_paths.is_some() {
                     trace!(
                         "Expanded search paths to {} workspace dirs, {} executables",
@@ -365,9 +447,11 @@ pub fn handle_resolve(context: Arc<Context>, id: u32, params: Value) {
             thread::spawn(move || {
                 let now = SystemTime::now();
                 trace!("Resolving env {:?}", executable);
-                if let Some(result) =
-                    resolve_environment(&executable, &context.locators, environment.deref())
-                {
+                if let Some(result) = resolve_environment(
+                    &executable,
+                    &context.enabled_locators(),
+                    environment.deref(),
+                ) {
                     if let Some(resolved) = result.resolved {
                         // Gather telemetry of this resolved env and see what we got wrong.
                         let jsonrpc_reporter = jsonrpc::create_reporter(None);
@@ -428,12 +512,21 @@ pub fn handle_find(context: Arc<Context>, id: u32, params: Value) {
                 let global_env_search_paths: Vec<PathBuf> =
                     get_search_paths_from_env_variables(context.os_environment.as_ref());
 
+                let locators = context.enabled_locators();
                 let collect_reporter = Arc::new(collect::create_reporter());
-                let reporter = CacheReporter::new(collect_reporter.clone());
+                let workspace_directories = if find_options.search_path.is_dir() {
+                    vec![find_options.search_path.clone()]
+                } else {
+                    vec![]
+                };
+                let reporter = CacheReporter::new(Arc::new(CompatibilityReporter::new(
+                    collect_reporter.clone(),
+                    &workspace_directories,
+                )));
                 if find_options.search_path.is_file() {
                     identify_python_executables_using_locators(
                         vec![find_options.search_path.clone()],
-                        &context.locators,
+                        &locators,
                         &reporter,
                         &global_env_search_paths,
                     );
@@ -441,7 +534,7 @@ pub fn handle_find(context: Arc<Context>, id: u32, params: Value) {
                     find_python_environments_in_workspace_folder_recursive(
                         &find_options.search_path,
                         &reporter,
-                        &context.locators,
+                        &locators,
                         &global_env_search_paths,
                         context
                             .configuration
@@ -483,6 +576,123 @@ pub fn handle_find(context: Arc<Context>, id: u32, params: Value) {
     );
 }
 
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SuggestOptions {
+    /// Folder (generally a workspace folder) to suggest an environment for.
+    pub search_path: PathBuf,
+}
+
+pub fn handle_suggest(context: Arc<Context>, id: u32, params: Value) {
+    match serde_json::from_value::<SuggestOptions>(params.clone()) {
+        Ok(suggest_options) => {
+            thread::spawn(move || {
+                let now = Instant::now();
+                let suggestion = suggest_environment(
+                    &suggest_options.search_path,
+                    &context.enabled_locators(),
+                    context.os_environment.deref(),
+                );
+                trace!(
+                    "Suggest completed in {:?} for {:?}, suggested {:?}",
+                    now.elapsed(),
+                    suggest_options.search_path,
+                    suggestion
+                        .as_ref()
+                        .map(|s| s.environment.executable.clone())
+                );
+                send_reply(id, suggestion);
+            });
+        }
+        Err(e) => {
+            error!("Failed to parse suggest {params:?}: {e}");
+            send_error(
+                Some(id),
+                -4,
+                format!("Failed to parse suggest {params:?}: {e}"),
+            );
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RankOptions {
+    /// Workspace folder to rank the environments for.
+    pub search_path: PathBuf,
+    /// Environments to rank (e.g. environments from a previous refresh).
+    /// If not provided, the environments are discovered first (including the workspace folder).
+    pub environments: Option<Vec<PythonEnvironment>>,
+}
+
+pub fn handle_rank(context: Arc<Context>, id: u32, params: Value) {
+    match serde_json::from_value::<RankOptions>(params.clone()) {
+        Ok(rank_options) => {
+            thread::spawn(move || {
+                let now = Instant::now();
+                let environments = match rank_options.environments {
+                    Some(environments) => environments,
+                    None => find_environments_to_rank(&context, &rank_options.search_path),
+                };
+                let ranked = rank_environments(&rank_options.search_path, environments);
+                trace!(
+                    "Rank completed in {:?} for {:?}, ranked {} environments",
+                    now.elapsed(),
+                    rank_options.search_path,
+                    ranked.len()
+                );
+                send_reply(id, ranked.into());
+            });
+        }
+        Err(e) => {
+            error!("Failed to parse rank {params:?}: {e}");
+            send_error(
+                Some(id),
+                -4,
+                format!("Failed to parse rank {params:?}: {e}"),
+            );
+        }
+    }
+}
+
+fn find_environments_to_rank(context: &Context, workspace_folder: &Path) -> Vec<PythonEnvironment> {
+    // Ensure we do not run in parallel with a refresh, as the locators are configured for this search.
+    let lock = REFRESH_LOCK.lock().expect("REFRESH_LOCK mutex poisoned");
+
+    let original_config = context.configuration.read().unwrap().clone();
+    let mut config = original_config.clone();
+    let mut workspace_directories = config.workspace_directories.unwrap_or_default();
+    if !workspace_directories.iter().any(|d| d == workspace_folder) {
+        workspace_directories.push(workspace_folder.to_path_buf());
+    }
+    config.workspace_directories = Some(workspace_directories);
+    for locator in context.locators.iter() {
+        locator.configure(&config);
+    }
+
+    let collect_reporter = Arc::new(collect::create_reporter());
+    let reporter = CacheReporter::new(collect_reporter.clone());
+    find_and_report_envs(
+        &reporter,
+        config,
+        &context.locators,
+        context.os_environment.deref(),
+        None,
+    );
+
+    for locator in context.locators.iter() {
+        locator.configure(&original_config);
+    }
+    drop(lock);
+
+    let environments = collect_reporter
+        .environments
+        .lock()
+        .expect("environments mutex poisoned")
+        .clone();
+    environments
+}
+
 pub fn handle_conda_telemetry(context: Arc<Context>, id: u32, _params: Value) {
     thread::spawn(move || {
         trace!("Gathering conda telemetry");
@@ -499,6 +709,36 @@ pub fn handle_conda_telemetry(context: Arc<Context>, id: u32, _params: Value) {
     });
 }
 
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct CondaEnvironmentDetailsOptions {
+    pub prefix: PathBuf,
+}
+
+pub fn handle_conda_environment_details(context: Arc<Context>, id: u32, params: Value) {
+    match serde_json::from_value::<CondaEnvironmentDetailsOptions>(params.clone()) {
+        Ok(request_options) => {
+            thread::spawn(move || {
+                let prefix = request_options.prefix;
+                trace!("Getting details of conda env {:?}", prefix);
+                if let Some(details) = context.conda_locator.get_environment_details(&prefix) {
+                    send_reply(id, details.into());
+                } else {
+                    error!("Failed to get details of conda env {prefix:?}");
+                    send_error(Some(id), -4, format!("Not a conda environment {prefix:?}"));
+                }
+            });
+        }
+        Err(e) => {
+            error!("Failed to parse conda environment details {params:?}: {e}");
+            send_error(
+                Some(id),
+                -4,
+                format!("Failed to parse conda environment details {params:?}: {e}"),
+            );
+        }
+    }
+}
+
 pub fn handle_clear_cache(_context: Arc<Context>, id: u32, _params: Value) {
     thread::spawn(move || {
         if let Err(e) = clear_cache() {
@@ -511,6 +751,14 @@ pub fn handle_clear_cache(_context: Arc<Context>, id: u32, _params: Value) {
     });
 }
 
+pub fn handle_cache_stats(_context: Arc<Context>, id: u32, _params: Value) {
+    thread::spawn(move || {
+        let stats = get_cache_stats();
+        trace!("Cache stats {:?}", stats);
+        send_reply(id, stats.into());
+    });
+}
+
 /// Builds the configuration and search scope based on refresh options.
 /// This is extracted from handle_refresh to enable unit testing.
 ///
@@ -579,6 +827,7 @@ mod tests {
         let refresh_options = RefreshOptions {
             search_kind: Some(PythonEnvironmentKind::Venv),
             search_paths: None,
+            filter: None,
         };
 
         let (result_config, search_scope) = build_refresh_config(&refresh_options, config);
@@ -616,6 +865,7 @@ mod tests {
         let refresh_options = RefreshOptions {
             search_kind: None,
             search_paths: Some(vec![search_dir.clone()]),
+            filter: None,
         };
 
         let (result_config, search_scope) = build_refresh_config(&refresh_options, config);
@@ -646,6 +896,7 @@ mod tests {
         let refresh_options = RefreshOptions {
             search_kind: None,
             search_paths: None,
+            filter: None,
         };
 
         let (result_config, search_scope) = build_refresh_config(&refresh_options, config);
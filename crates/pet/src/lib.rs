@@ -1,28 +1,52 @@
 // Copyright (c) Microsoft Corporation.
 // Licensed under the MIT License.
 
+use compatibility::CompatibilityReporter;
+use config::{apply_global_configuration, get_user_config_file, LoadedSettings, Settings};
+use doctor::{diagnose_environment, Diagnosis};
+use filter::{EnvironmentFilter, FilterReporter};
 use find::find_and_report_envs;
+use find::set_parallelism;
 use find::SearchScope;
-use locators::create_locators;
+use inventory::{diff, Host, Inventory, InventoryDiff};
+use locators::{create_locators, get_enabled_locators};
+use output::{parse_template, render_tabular, render_template, TabularFormat, DEFAULT_COLUMNS};
 use pet_conda::Conda;
 use pet_conda::CondaLocator;
+use pet_core::manager::EnvManager;
 use pet_core::os_environment::Environment;
-use pet_core::python_environment::PythonEnvironmentKind;
+use pet_core::python_environment::PythonEnvironment;
 use pet_core::Locator;
 use pet_core::{os_environment::EnvironmentApi, reporter::Reporter, Configuration};
 use pet_poetry::Poetry;
 use pet_poetry::PoetryLocator;
-use pet_python_utils::cache::set_cache_directory;
-use pet_reporter::{self, cache::CacheReporter, collect, stdio};
+use pet_reporter::{self, cache::CacheReporter, collect, ndjson, stdio};
+use rank::rank_environments;
 use resolve::resolve_environment;
 use serde::Serialize;
 use std::path::PathBuf;
-use std::{collections::BTreeMap, env, sync::Arc, time::SystemTime};
+use std::str::FromStr;
+use std::{collections::BTreeMap, env, fs, sync::Arc, time::SystemTime};
+use suggest::suggest_environment;
 use tracing_subscriber::{fmt, layer::SubscriberExt, util::SubscriberInitExt, EnvFilter};
+use which::which;
 
+pub mod compatibility;
+pub mod config;
+pub mod doctor;
+pub mod filter;
 pub mod find;
+pub mod inventory;
 pub mod locators;
+pub mod output;
+pub mod pep440;
+pub mod rank;
+pub mod requires_python;
 pub mod resolve;
+pub mod suggest;
+#[cfg(test)]
+mod test_utils;
+pub mod which;
 
 /// Initialize tracing subscriber for performance profiling.
 /// Set RUST_LOG=info or RUST_LOG=pet=debug for more detailed traces.
@@ -64,6 +88,44 @@ pub fn initialize_tracing(verbose: bool) {
     });
 }
 
+/// Format of the environments & managers written to the standard output.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub enum OutputFormat {
+    /// Human readable text.
+    #[default]
+    Text,
+    /// A single JSON document, written once the search has completed.
+    Json,
+    /// One JSON object per line, written as soon as each manager & environment is found,
+    /// followed by a summary line.
+    Ndjson,
+    /// One row per environment (`table`, `csv` or `tsv`), with the columns of the find options.
+    Tabular(TabularFormat),
+    /// One line per environment, with the `{{field}}` placeholders replaced (`template={{kind}} {{executable}}`).
+    Template(String),
+}
+
+impl FromStr for OutputFormat {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        if let Some(template) = value.strip_prefix("template=") {
+            return Ok(OutputFormat::Template(parse_template(template)?));
+        }
+        match value.to_lowercase().as_str() {
+            "text" => Ok(OutputFormat::Text),
+            "json" => Ok(OutputFormat::Json),
+            "ndjson" => Ok(OutputFormat::Ndjson),
+            "table" => Ok(OutputFormat::Tabular(TabularFormat::Table)),
+            "csv" => Ok(OutputFormat::Tabular(TabularFormat::Csv)),
+            "tsv" => Ok(OutputFormat::Tabular(TabularFormat::Tsv)),
+            _ => Err(format!(
+                "unknown format {value:?}, expected one of text, json, ndjson, table, csv, tsv or template=<template>"
+            )),
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct FindOptions {
     pub print_list: bool,
@@ -73,12 +135,21 @@ pub struct FindOptions {
     pub search_paths: Option<Vec<PathBuf>>,
     pub workspace_only: bool,
     pub cache_directory: Option<PathBuf>,
-    pub kind: Option<PythonEnvironmentKind>,
-    pub json: bool,
+    /// Only the environments that satisfy the filter are reported.
+    pub filter: EnvironmentFilter,
+    pub format: OutputFormat,
+    /// Columns of the tabular formats, defaults to `DEFAULT_COLUMNS`.
+    pub columns: Option<Vec<String>>,
+    /// Rank the environments for the workspace folder (first search path or the current directory).
+    pub ranked: bool,
     pub conda_executable: Option<PathBuf>,
     pub pipenv_executable: Option<PathBuf>,
     pub poetry_executable: Option<PathBuf>,
     pub environment_directories: Option<Vec<PathBuf>>,
+    /// Maximum number of threads used to discover environments, defaults to the number of CPUs.
+    pub jobs: Option<usize>,
+    /// Settings of the config files & environment variables, used for the options that are not provided.
+    pub settings: Settings,
 }
 
 pub fn find_and_report_envs_stdio(options: FindOptions) {
@@ -94,12 +165,15 @@ pub fn find_and_report_envs_stdio(options: FindOptions) {
     let search_scope = if options.workspace_only {
         Some(SearchScope::Workspace)
     } else {
-        options.kind.map(SearchScope::Global)
+        // Only search the locators of the kind, when filtering by a single kind.
+        match options.filter.kinds.as_deref() {
+            Some([kind]) => Some(SearchScope::Global(*kind)),
+            _ => None,
+        }
     };
 
-    if let Some(cache_directory) = options.cache_directory.clone() {
-        set_cache_directory(cache_directory);
-    }
+    apply_global_configuration(&config);
+    set_parallelism(options.jobs);
     let environment = EnvironmentApi::new();
     let conda_locator = Arc::new(Conda::from(&environment));
     let poetry_locator = Arc::new(Poetry::from(&environment));
@@ -109,7 +183,43 @@ pub fn find_and_report_envs_stdio(options: FindOptions) {
         locator.configure(&config);
     }
 
-    if options.json {
+    if options.ranked {
+        find_envs_ranked(
+            &options,
+            &locators,
+            config,
+            conda_locator.as_ref(),
+            poetry_locator.as_ref(),
+            &environment,
+            search_scope,
+        );
+        if options.format == OutputFormat::Text {
+            println!("Completed in {}ms", now.elapsed().unwrap().as_millis())
+        }
+    } else if options.format == OutputFormat::Ndjson {
+        find_envs_ndjson(
+            &options,
+            &locators,
+            config,
+            conda_locator.as_ref(),
+            poetry_locator.as_ref(),
+            &environment,
+            search_scope,
+        );
+    } else if matches!(
+        options.format,
+        OutputFormat::Tabular(_) | OutputFormat::Template(_)
+    ) {
+        find_envs_tabular(
+            &options,
+            &locators,
+            config,
+            conda_locator.as_ref(),
+            poetry_locator.as_ref(),
+            &environment,
+            search_scope,
+        );
+    } else if options.format == OutputFormat::Json {
         find_envs_json(
             &options,
             &locators,
@@ -135,11 +245,23 @@ pub fn find_and_report_envs_stdio(options: FindOptions) {
 }
 
 fn create_config(options: &FindOptions) -> Configuration {
-    let mut config = Configuration::default();
+    let flags = Settings {
+        conda_executable: options.conda_executable.clone(),
+        pipenv_executable: options.pipenv_executable.clone(),
+        poetry_executable: options.poetry_executable.clone(),
+        environment_directories: options.environment_directories.clone(),
+        cache_directory: options.cache_directory.clone(),
+        ..Default::default()
+    };
+    let settings = flags.or(options.settings.clone());
+    let mut config = settings.to_configuration();
 
     let mut search_paths = vec![];
     if let Some(dirs) = options.search_paths.clone() {
         search_paths.extend(dirs);
+    } else {
+        search_paths.extend(settings.workspace_directories.unwrap_or_default());
+        search_paths.extend(settings.executables.unwrap_or_default());
     }
     // If workspace folders have been provided do not add cwd.
     if search_paths.is_empty() {
@@ -164,18 +286,65 @@ fn create_config(options: &FindOptions) -> Configuration {
             .cloned()
             .collect(),
     );
-
-    config.conda_executable = options.conda_executable.clone();
-    config.pipenv_executable = options.pipenv_executable.clone();
-    config.poetry_executable = options.poetry_executable.clone();
-    config.environment_directories = options
+    config.environment_directories = config
         .environment_directories
-        .clone()
         .map(|dirs| dirs.into_iter().filter(|p| p.is_dir()).collect());
 
     config
 }
 
+/// Configuration of the commands other than find, the settings of the config files & environment variables
+/// along with the `--cache-directory` flag. These commands search their own workspace folder (or executable),
+/// hence the configured workspace directories & executables are not used.
+fn create_command_config(
+    config: Configuration,
+    settings: Settings,
+    cache_directory: Option<PathBuf>,
+) -> Configuration {
+    let flags = Settings {
+        cache_directory,
+        ..Default::default()
+    };
+    let mut merged = flags.or(settings).to_configuration();
+    merged.workspace_directories = config.workspace_directories;
+    merged.executables = config.executables;
+    merged.environment_directories = merged
+        .environment_directories
+        .map(|dirs| dirs.into_iter().filter(|p| p.is_dir()).collect());
+    merged
+}
+
+/// By now all conda & poetry environments have been found, when requested spawn conda & poetry
+/// to see if we can find more environments.
+fn report_missing_envs(
+    options: &FindOptions,
+    conda_locator: &Conda,
+    poetry_locator: &Poetry,
+    reporter: &dyn Reporter,
+) {
+    if options.report_missing {
+        let _ =
+            conda_locator.find_and_report_missing_envs(reporter, options.conda_executable.clone());
+        let _ = poetry_locator
+            .find_and_report_missing_envs(reporter, options.poetry_executable.clone());
+    }
+}
+
+/// Reports each environment once, annotated with its compatibility with the workspace folders,
+/// & only if it satisfies the filter.
+fn create_reporter(
+    options: &FindOptions,
+    config: &Configuration,
+    reporter: Arc<dyn Reporter>,
+) -> CacheReporter {
+    let reporter = FilterReporter::new(reporter, options.filter.clone())
+        .expect("filter is validated when parsing the arguments");
+    CacheReporter::new(Arc::new(CompatibilityReporter::new(
+        Arc::new(reporter),
+        config.workspace_directories.as_deref().unwrap_or_default(),
+    )))
+}
+
 fn find_envs(
     options: &FindOptions,
     locators: &Arc<Vec<Arc<dyn Locator>>>,
@@ -185,23 +354,11 @@ fn find_envs(
     environment: &dyn Environment,
     search_scope: Option<SearchScope>,
 ) {
-    let kind = match search_scope {
-        Some(SearchScope::Global(kind)) => Some(kind),
-        _ => None,
-    };
-    let stdio_reporter = Arc::new(stdio::create_reporter(options.print_list, kind));
-    let reporter = CacheReporter::new(stdio_reporter.clone());
+    let stdio_reporter = Arc::new(stdio::create_reporter(options.print_list, None));
+    let reporter = create_reporter(options, &config, stdio_reporter.clone());
 
     let summary = find_and_report_envs(&reporter, config, locators, environment, search_scope);
-    if options.report_missing {
-        // By now all conda envs have been found
-        // Spawn conda
-        // & see if we can find more environments by spawning conda.
-        let _ =
-            conda_locator.find_and_report_missing_envs(&reporter, options.conda_executable.clone());
-        let _ = poetry_locator
-            .find_and_report_missing_envs(&reporter, options.poetry_executable.clone());
-    }
+    report_missing_envs(options, conda_locator, poetry_locator, &reporter);
 
     if options.print_summary {
         let summary = summary.lock().expect("summary mutex poisoned");
@@ -289,7 +446,7 @@ fn find_envs(
 #[serde(rename_all = "camelCase")]
 struct JsonOutput {
     managers: Vec<pet_core::manager::EnvManager>,
-    environments: Vec<pet_core::python_environment::PythonEnvironment>,
+    environments: Vec<PythonEnvironment>,
 }
 
 fn find_envs_json(
@@ -302,31 +459,22 @@ fn find_envs_json(
     search_scope: Option<SearchScope>,
 ) {
     let collect_reporter = Arc::new(collect::create_reporter());
-    let reporter = CacheReporter::new(collect_reporter.clone());
+    let reporter = create_reporter(options, &config, collect_reporter.clone());
 
     find_and_report_envs(&reporter, config, locators, environment, search_scope);
-    if options.report_missing {
-        let _ =
-            conda_locator.find_and_report_missing_envs(&reporter, options.conda_executable.clone());
-        let _ = poetry_locator
-            .find_and_report_missing_envs(&reporter, options.poetry_executable.clone());
-    }
+    report_missing_envs(options, conda_locator, poetry_locator, &reporter);
 
     let managers = collect_reporter
         .managers
         .lock()
         .expect("managers mutex poisoned")
         .clone();
-    let mut environments = collect_reporter
+    let environments = collect_reporter
         .environments
         .lock()
         .expect("environments mutex poisoned")
         .clone();
 
-    if let Some(kind) = options.kind {
-        environments.retain(|e| e.kind == Some(kind));
-    }
-
     let output = JsonOutput {
         managers,
         environments,
@@ -337,12 +485,145 @@ fn find_envs_json(
     );
 }
 
+fn find_envs_tabular(
+    options: &FindOptions,
+    locators: &Arc<Vec<Arc<dyn Locator>>>,
+    config: Configuration,
+    conda_locator: &Conda,
+    poetry_locator: &Poetry,
+    environment: &dyn Environment,
+    search_scope: Option<SearchScope>,
+) {
+    let collect_reporter = Arc::new(collect::create_reporter());
+    let reporter = create_reporter(options, &config, collect_reporter.clone());
+
+    find_and_report_envs(&reporter, config, locators, environment, search_scope);
+    report_missing_envs(options, conda_locator, poetry_locator, &reporter);
+
+    let mut environments = collect_reporter
+        .environments
+        .lock()
+        .expect("environments mutex poisoned")
+        .clone();
+    environments.sort();
+
+    print_environments(options, &environments);
+}
+
+/// Writes the environments in the tabular or template format, one row/line per environment.
+fn print_environments(options: &FindOptions, environments: &[PythonEnvironment]) {
+    match &options.format {
+        OutputFormat::Tabular(format) => {
+            let columns = options.columns.clone().unwrap_or_else(|| {
+                DEFAULT_COLUMNS
+                    .iter()
+                    .map(|column| column.to_string())
+                    .collect()
+            });
+            print!("{}", render_tabular(environments, *format, &columns));
+        }
+        OutputFormat::Template(template) => print!("{}", render_template(environments, template)),
+        OutputFormat::Text | OutputFormat::Json | OutputFormat::Ndjson => {}
+    }
+}
+
+fn find_envs_ndjson(
+    options: &FindOptions,
+    locators: &Arc<Vec<Arc<dyn Locator>>>,
+    config: Configuration,
+    conda_locator: &Conda,
+    poetry_locator: &Poetry,
+    environment: &dyn Environment,
+    search_scope: Option<SearchScope>,
+) {
+    let now = SystemTime::now();
+    let ndjson_reporter = Arc::new(ndjson::create_reporter());
+    let reporter = create_reporter(options, &config, ndjson_reporter.clone());
+
+    find_and_report_envs(&reporter, config, locators, environment, search_scope);
+    report_missing_envs(options, conda_locator, poetry_locator, &reporter);
+
+    ndjson_reporter.report_summary(now.elapsed().unwrap_or_default());
+}
+
+fn find_envs_ranked(
+    options: &FindOptions,
+    locators: &Arc<Vec<Arc<dyn Locator>>>,
+    config: Configuration,
+    conda_locator: &Conda,
+    poetry_locator: &Poetry,
+    environment: &dyn Environment,
+    search_scope: Option<SearchScope>,
+) {
+    let workspace_folder = config
+        .workspace_directories
+        .clone()
+        .unwrap_or_default()
+        .first()
+        .cloned()
+        .or_else(|| env::current_dir().ok())
+        .unwrap_or_default();
+    let collect_reporter = Arc::new(collect::create_reporter());
+    let reporter = create_reporter(options, &config, collect_reporter.clone());
+
+    find_and_report_envs(&reporter, config, locators, environment, search_scope);
+    report_missing_envs(options, conda_locator, poetry_locator, &reporter);
+
+    let environments = collect_reporter
+        .environments
+        .lock()
+        .expect("environments mutex poisoned")
+        .clone();
+
+    let ranked = rank_environments(&workspace_folder, environments);
+    match &options.format {
+        OutputFormat::Json => {
+            println!(
+                "{}",
+                serde_json::to_string_pretty(&ranked)
+                    .expect("failed to serialize ranked environments as JSON")
+            );
+            return;
+        }
+        // Ranking requires all of the environments, hence the lines are written once the search has completed.
+        OutputFormat::Ndjson => {
+            for item in ranked.iter() {
+                println!(
+                    "{}",
+                    serde_json::to_string(item)
+                        .expect("failed to serialize ranked environment as JSON")
+                );
+            }
+            return;
+        }
+        OutputFormat::Tabular(_) | OutputFormat::Template(_) => {
+            let environments: Vec<PythonEnvironment> =
+                ranked.into_iter().map(|item| item.environment).collect();
+            print_environments(options, &environments);
+            return;
+        }
+        OutputFormat::Text => {}
+    }
+
+    println!("Environments ranked for {workspace_folder:?}:");
+    println!();
+    for (i, item) in ranked.iter().enumerate() {
+        println!("{}. Score: {}", i + 1, item.score);
+        print!("{}", item.environment);
+        for reason in item.reasons.iter() {
+            println!("   {:>+5} : {}", reason.score, reason.reason);
+        }
+        println!();
+    }
+}
+
 pub fn resolve_report_stdio(
     executable: PathBuf,
     verbose: bool,
     cache_directory: Option<PathBuf>,
     json: bool,
-) {
+    settings: Settings,
+) -> Result<(), String> {
     // Initialize tracing for performance profiling (includes log compatibility)
     initialize_tracing(verbose);
 
@@ -351,25 +632,59 @@ pub fn resolve_report_stdio(
 
     let now = SystemTime::now();
 
-    if let Some(cache_directory) = cache_directory.clone() {
-        set_cache_directory(cache_directory);
-    }
-
     let stdio_reporter = Arc::new(stdio::create_reporter(true, None));
     let reporter = CacheReporter::new(stdio_reporter.clone());
     let environment = EnvironmentApi::new();
     let conda_locator = Arc::new(Conda::from(&environment));
     let poetry_locator = Arc::new(Poetry::from(&environment));
 
-    let mut config = Configuration::default();
-    if let Ok(cwd) = env::current_dir() {
-        config.workspace_directories = Some(vec![cwd]);
-    }
+    let config = create_command_config(
+        Configuration {
+            workspace_directories: env::current_dir().ok().map(|cwd| vec![cwd]),
+            ..Default::default()
+        },
+        settings,
+        cache_directory,
+    );
+    apply_global_configuration(&config);
 
     let locators = create_locators(conda_locator.clone(), poetry_locator.clone(), &environment);
     for locator in locators.iter() {
         locator.configure(&config);
     }
+    let locators = get_enabled_locators(&locators, &config);
+
+    // Environments can also be resolved by name, e.g. `pet resolve base`.
+    let executable = if executable.exists() {
+        executable
+    } else {
+        let mut environments: Vec<PythonEnvironment> =
+            find_environments(config, &locators, &environment, None)
+                .into_iter()
+                .filter(|env| env.name.is_some() && env.name.as_deref() == executable.to_str())
+                .collect();
+        environments.sort();
+        match environments.as_slice() {
+            [] => executable,
+            [env] => env
+                .executable
+                .clone()
+                .or(env.prefix.clone())
+                .unwrap_or(executable),
+            // Names such as `base` are common to each conda installation.
+            _ => {
+                return Err(format!(
+                    "Multiple environments are named {executable:?}, resolve one of them by its prefix instead:\n{}",
+                    environments
+                        .iter()
+                        .filter_map(|env| env.prefix.as_ref().or(env.executable.as_ref()))
+                        .map(|path| format!("   {}", path.to_string_lossy()))
+                        .collect::<Vec<String>>()
+                        .join("\n")
+                ))
+            }
+        }
+    };
 
     if let Some(result) = resolve_environment(&executable, &locators, &environment) {
         let env = &result.resolved.unwrap_or(result.discovered);
@@ -397,4 +712,464 @@ pub fn resolve_report_stdio(
             now.elapsed().unwrap().as_millis()
         )
     }
+    Ok(())
+}
+
+pub fn suggest_report_stdio(
+    workspace_folder: PathBuf,
+    verbose: bool,
+    cache_directory: Option<PathBuf>,
+    json: bool,
+    settings: Settings,
+) {
+    initialize_tracing(verbose);
+
+    let now = SystemTime::now();
+
+    let stdio_reporter = Arc::new(stdio::create_reporter(true, None));
+    let reporter = CacheReporter::new(stdio_reporter.clone());
+    let environment = EnvironmentApi::new();
+    let conda_locator = Arc::new(Conda::from(&environment));
+    let poetry_locator = Arc::new(Poetry::from(&environment));
+
+    let config = create_command_config(
+        Configuration {
+            workspace_directories: Some(vec![workspace_folder.clone()]),
+            ..Default::default()
+        },
+        settings,
+        cache_directory,
+    );
+    apply_global_configuration(&config);
+
+    let locators = create_locators(conda_locator.clone(), poetry_locator.clone(), &environment);
+    for locator in locators.iter() {
+        locator.configure(&config);
+    }
+    let locators = get_enabled_locators(&locators, &config);
+
+    let suggestion = suggest_environment(&workspace_folder, &locators, &environment);
+    if json {
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&suggestion)
+                .expect("failed to serialize suggestion as JSON")
+        );
+    } else if let Some(suggestion) = suggestion {
+        println!(
+            "Environment suggested for {workspace_folder:?} based on {:?}",
+            suggestion.source
+        );
+        if let Some(manager) = &suggestion.environment.manager {
+            reporter.report_manager(manager);
+        }
+        reporter.report_environment(&suggestion.environment);
+    } else {
+        println!("No environment found for {workspace_folder:?}");
+    }
+
+    if !json {
+        println!(
+            "Suggest completed in {}ms",
+            now.elapsed().unwrap().as_millis()
+        )
+    }
+}
+
+/// Finds the environments (each environment once), without writing to the standard output.
+fn find_environments(
+    config: Configuration,
+    locators: &Arc<Vec<Arc<dyn Locator>>>,
+    environment: &dyn Environment,
+    search_scope: Option<SearchScope>,
+) -> Vec<PythonEnvironment> {
+    find_managers_and_environments(config, locators, environment, search_scope).1
+}
+
+fn find_managers_and_environments(
+    config: Configuration,
+    locators: &Arc<Vec<Arc<dyn Locator>>>,
+    environment: &dyn Environment,
+    search_scope: Option<SearchScope>,
+) -> (Vec<EnvManager>, Vec<PythonEnvironment>) {
+    let collect_reporter = Arc::new(collect::create_reporter());
+    let reporter = CacheReporter::new(collect_reporter.clone());
+    find_and_report_envs(&reporter, config, locators, environment, search_scope);
+    let managers = collect_reporter
+        .managers
+        .lock()
+        .expect("managers mutex poisoned")
+        .clone();
+    let environments = collect_reporter
+        .environments
+        .lock()
+        .expect("environments mutex poisoned")
+        .clone();
+    (managers, environments)
+}
+
+/// Finds the global environments & those in the current directory, e.g. to complete environment names in the shell.
+/// Conda & poetry are not spawned to find the environments that could not otherwise be found, to keep this cheap.
+pub fn list_environments(settings: Settings) -> Vec<PythonEnvironment> {
+    let environment = EnvironmentApi::new();
+    let conda_locator = Arc::new(Conda::from(&environment));
+    let poetry_locator = Arc::new(Poetry::from(&environment));
+    let config = create_command_config(
+        Configuration {
+            workspace_directories: env::current_dir().ok().map(|cwd| vec![cwd]),
+            ..Default::default()
+        },
+        settings,
+        None,
+    );
+    // Also sets the cache directory, so that Python is not spawned for the interpreters already cached.
+    apply_global_configuration(&config);
+
+    let locators = get_enabled_locators(
+        &create_locators(conda_locator.clone(), poetry_locator.clone(), &environment),
+        &config,
+    );
+    for locator in locators.iter() {
+        locator.configure(&config);
+    }
+    find_environments(config, &locators, &environment, None)
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct DoctorOutput {
+    /// Number of environments checked.
+    checked: usize,
+    /// Environments with problems.
+    diagnoses: Vec<Diagnosis>,
+}
+
+/// Checks the environments found in the path (or all environments) & reports the problems found.
+pub fn doctor_report_stdio(
+    path: Option<PathBuf>,
+    verbose: bool,
+    cache_directory: Option<PathBuf>,
+    json: bool,
+    settings: Settings,
+) {
+    initialize_tracing(verbose);
+
+    let now = SystemTime::now();
+
+    let environment = EnvironmentApi::new();
+    let conda_locator = Arc::new(Conda::from(&environment));
+    let poetry_locator = Arc::new(Poetry::from(&environment));
+
+    let (config, search_scope) = match &path {
+        Some(path) if path.is_file() => (
+            Configuration {
+                executables: Some(vec![path.clone()]),
+                ..Default::default()
+            },
+            Some(SearchScope::Workspace),
+        ),
+        Some(path) => (
+            Configuration {
+                workspace_directories: Some(vec![path.clone()]),
+                ..Default::default()
+            },
+            Some(SearchScope::Workspace),
+        ),
+        None => (
+            Configuration {
+                workspace_directories: env::current_dir().ok().map(|cwd| vec![cwd]),
+                ..Default::default()
+            },
+            None,
+        ),
+    };
+    let config = create_command_config(config, settings, cache_directory);
+    apply_global_configuration(&config);
+
+    let locators = create_locators(conda_locator.clone(), poetry_locator.clone(), &environment);
+    for locator in locators.iter() {
+        locator.configure(&config);
+    }
+    let mut environments = find_environments(config, &locators, &environment, search_scope);
+    environments.sort();
+    let checked = environments.len();
+    let diagnoses: Vec<Diagnosis> = environments
+        .into_iter()
+        .filter_map(|environment| {
+            let problems = diagnose_environment(&environment);
+            if problems.is_empty() {
+                None
+            } else {
+                Some(Diagnosis {
+                    environment,
+                    problems,
+                })
+            }
+        })
+        .collect();
+
+    if json {
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&DoctorOutput { checked, diagnoses })
+                .expect("failed to serialize diagnoses as JSON")
+        );
+        return;
+    }
+
+    for diagnosis in diagnoses.iter() {
+        let env = &diagnosis.environment;
+        println!(
+            "{} {}",
+            env.kind
+                .map(|kind| format!("{kind:?}"))
+                .unwrap_or("Unknown".to_string()),
+            env.prefix
+                .as_ref()
+                .or(env.executable.as_ref())
+                .map(|path| path.to_string_lossy().to_string())
+                .unwrap_or_default()
+        );
+        for problem in diagnosis.problems.iter() {
+            println!("   Problem : {}", problem.message);
+            println!("   Fix     : {}", problem.fix);
+        }
+        println!();
+    }
+    println!(
+        "Checked {} environments, found {} problems in {} environments",
+        checked,
+        diagnoses.iter().map(|d| d.problems.len()).sum::<usize>(),
+        diagnoses.len()
+    );
+    println!(
+        "Doctor completed in {}ms",
+        now.elapsed().unwrap().as_millis()
+    )
+}
+
+/// Reports the interpreter the command would run in the directory, along with the shims & symlinks that lead to it.
+pub fn which_report_stdio(
+    command: String,
+    cwd: PathBuf,
+    verbose: bool,
+    json: bool,
+    settings: Settings,
+) {
+    initialize_tracing(verbose);
+
+    let now = SystemTime::now();
+
+    let stdio_reporter = Arc::new(stdio::create_reporter(true, None));
+    let reporter = CacheReporter::new(stdio_reporter.clone());
+    let environment = EnvironmentApi::new();
+    let conda_locator = Arc::new(Conda::from(&environment));
+    let poetry_locator = Arc::new(Poetry::from(&environment));
+
+    let config = create_command_config(
+        Configuration {
+            workspace_directories: Some(vec![cwd.clone()]),
+            ..Default::default()
+        },
+        settings,
+        None,
+    );
+    apply_global_configuration(&config);
+
+    let locators = create_locators(conda_locator.clone(), poetry_locator.clone(), &environment);
+    for locator in locators.iter() {
+        locator.configure(&config);
+    }
+    let locators = get_enabled_locators(&locators, &config);
+
+    let result = which(&command, &cwd, &locators, &environment);
+    if json {
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&result).expect("failed to serialize result as JSON")
+        );
+        return;
+    }
+
+    match result {
+        Some(result) => {
+            println!("{command:?} in {cwd:?} resolves to:");
+            for (i, link) in result.links.iter().enumerate() {
+                let arrow = if i == 0 { " " } else { "→" };
+                println!(
+                    " {} {} ({})",
+                    arrow,
+                    link.path.to_string_lossy(),
+                    link.reason
+                );
+            }
+            println!();
+            match &result.environment {
+                Some(env) => {
+                    if let Some(manager) = &env.manager {
+                        reporter.report_manager(manager);
+                    }
+                    reporter.report_environment(env);
+                }
+                None => println!("Not a known Python environment"),
+            }
+        }
+        None => println!("{command:?} not found on the PATH"),
+    }
+
+    println!(
+        "Which completed in {}ms",
+        now.elapsed().unwrap().as_millis()
+    )
+}
+
+/// Exports the managers & environments found on this machine, along with details of the host,
+/// to the file (or the standard output).
+pub fn export_report_stdio(
+    out: Option<PathBuf>,
+    verbose: bool,
+    cache_directory: Option<PathBuf>,
+    settings: Settings,
+) -> Result<(), String> {
+    initialize_tracing(verbose);
+
+    let now = SystemTime::now();
+
+    let environment = EnvironmentApi::new();
+    let conda_locator = Arc::new(Conda::from(&environment));
+    let poetry_locator = Arc::new(Poetry::from(&environment));
+    // Only the environments of the machine, the environments of the current directory are not exported.
+    let config = create_command_config(Configuration::default(), settings, cache_directory);
+    apply_global_configuration(&config);
+
+    let locators = create_locators(conda_locator.clone(), poetry_locator.clone(), &environment);
+    for locator in locators.iter() {
+        locator.configure(&config);
+    }
+    let (managers, environments) =
+        find_managers_and_environments(config, &locators, &environment, None);
+    let inventory = Inventory::new(Host::from(&environment), managers, environments);
+    let contents =
+        serde_json::to_string_pretty(&inventory).expect("failed to serialize inventory as JSON");
+
+    match out {
+        Some(out) => {
+            fs::write(&out, contents + "\n")
+                .map_err(|err| format!("Unable to write the inventory to {out:?}: {err}"))?;
+            println!(
+                "Exported {} environments & {} managers to {:?} in {}ms",
+                inventory.environments.len(),
+                inventory.managers.len(),
+                out,
+                now.elapsed().unwrap().as_millis()
+            );
+        }
+        None => println!("{contents}"),
+    }
+    Ok(())
+}
+
+/// Reports the environments added, removed or whose version changed between two exported inventories.
+pub fn diff_report_stdio(old: PathBuf, new: PathBuf, json: bool) -> Result<(), String> {
+    let old = Inventory::read(&old)?;
+    let new = Inventory::read(&new)?;
+    let result = diff(&old, &new);
+
+    if json {
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&result).expect("failed to serialize diff as JSON")
+        );
+        return Ok(());
+    }
+
+    let hostname = |inventory: &Inventory| {
+        inventory
+            .host
+            .hostname
+            .clone()
+            .unwrap_or("<unknown host>".to_string())
+    };
+    println!("Comparing {} with {}", hostname(&old), hostname(&new));
+    print_diff(&result);
+    Ok(())
+}
+
+fn print_diff(result: &InventoryDiff) {
+    let describe = |env: &PythonEnvironment| {
+        format!(
+            "{} {}",
+            env.kind
+                .map(|kind| format!("{kind:?}"))
+                .unwrap_or("Unknown".to_string()),
+            env.prefix
+                .as_ref()
+                .or(env.executable.as_ref())
+                .map(|path| path.to_string_lossy().to_string())
+                .unwrap_or_default()
+        )
+    };
+    let version = |version: &Option<String>| version.clone().unwrap_or("unknown".to_string());
+
+    if result.is_empty() {
+        println!("No environments were added, removed or changed");
+        return;
+    }
+    if !result.added.is_empty() {
+        println!("Added ({}):", result.added.len());
+        for env in result.added.iter() {
+            println!("   {} ({})", describe(env), version(&env.version));
+        }
+    }
+    if !result.removed.is_empty() {
+        println!("Removed ({}):", result.removed.len());
+        for env in result.removed.iter() {
+            println!("   {} ({})", describe(env), version(&env.version));
+        }
+    }
+    if !result.version_changed.is_empty() {
+        println!("Version changed ({}):", result.version_changed.len());
+        for change in result.version_changed.iter() {
+            println!(
+                "   {} ({} -> {})",
+                describe(&change.environment),
+                version(&change.previous_version),
+                version(&change.environment.version)
+            );
+        }
+    }
+}
+
+/// Prints the effective configuration, along with the config files it was merged from.
+pub fn config_show_stdio(loaded: LoadedSettings, json: bool) {
+    if json {
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&loaded.settings)
+                .expect("failed to serialize configuration as JSON")
+        );
+        return;
+    }
+
+    if loaded.files.is_empty() {
+        let user_config_file = get_user_config_file(&EnvironmentApi::new())
+            .map(|file| file.to_string_lossy().to_string())
+            .unwrap_or("$XDG_CONFIG_HOME/pet/config.toml".to_string());
+        println!(
+            "# No config files found, looked for {user_config_file} & {}",
+            config::WORKSPACE_CONFIG_FILE
+        );
+    } else {
+        println!(
+            "# Merged from the config files (highest precedence last) & environment variables:"
+        );
+        for file in loaded.files.iter() {
+            println!("#   {}", file.to_string_lossy());
+        }
+    }
+    print!(
+        "{}",
+        toml::to_string_pretty(&loaded.settings)
+            .expect("failed to serialize configuration as TOML")
+    );
 }
@@ -9,7 +9,7 @@ use pet_core::os_environment::Environment;
 use pet_core::python_environment::{
     PythonEnvironment, PythonEnvironmentBuilder, PythonEnvironmentKind,
 };
-use pet_core::Locator;
+use pet_core::{Configuration, Locator};
 use pet_linux_global_python::LinuxGlobalPython;
 use pet_mac_commandlinetools::MacCmdLineTools;
 use pet_mac_python_org::MacPythonOrg;
@@ -99,6 +99,24 @@ pub fn create_locators(
     Arc::new(locators)
 }
 
+/// Locators that are not disabled in the configuration, disabled locators are neither used
+/// to search for environments nor to identify them.
+pub fn get_enabled_locators(
+    locators: &Arc<Vec<Arc<dyn Locator>>>,
+    configuration: &Configuration,
+) -> Arc<Vec<Arc<dyn Locator>>> {
+    match &configuration.disabled_locators {
+        Some(disabled) if !disabled.is_empty() => Arc::new(
+            locators
+                .iter()
+                .filter(|locator| !disabled.contains(&locator.get_kind()))
+                .cloned()
+                .collect(),
+        ),
+        _ => locators.clone(),
+    }
+}
+
 /// Identify the Python environment using the locators.
 /// search_path : Generally refers to original folder that was being searched when the env was found.
 #[instrument(skip(locators, global_env_search_paths), fields(executable = %env.executable.display()))]
@@ -237,3 +255,25 @@ fn find_symlinks(_executable: &PathBuf) -> Option<Vec<PathBuf>> {
     // Lets wait and see if this is necessary.
     None
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pet_core::LocatorKind;
+
+    #[test]
+    fn disabled_locators_are_excluded() {
+        let locators: Arc<Vec<Arc<dyn Locator>>> =
+            Arc::new(vec![Arc::new(Venv::new()), Arc::new(VirtualEnv::new())]);
+        let config = Configuration {
+            disabled_locators: Some(vec![LocatorKind::Venv]),
+            ..Default::default()
+        };
+        let enabled = get_enabled_locators(&locators, &config);
+        assert_eq!(enabled.len(), 1);
+        assert_eq!(enabled[0].get_kind(), LocatorKind::VirtualEnv);
+
+        let enabled = get_enabled_locators(&locators, &Configuration::default());
+        assert_eq!(enabled.len(), 2);
+    }
+}
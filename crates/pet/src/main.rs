@@ -1,12 +1,30 @@
 // Copyright (c) Microsoft Corporation.
 // Licensed under the MIT License.
 
-use std::path::PathBuf;
+use std::{io, path::PathBuf};
 
-use clap::{Parser, Subcommand};
+use clap::{CommandFactory, Parser, Subcommand};
+use clap_complete::{
+    engine::{ArgValueCandidates, CompletionCandidate},
+    env::Shells,
+    CompleteEnv, Shell,
+};
 use jsonrpc::start_jsonrpc_server;
-use pet::{find_and_report_envs_stdio, resolve_report_stdio, FindOptions};
-use pet_core::python_environment::PythonEnvironmentKind;
+use pet::output::parse_column;
+use pet::pep440::VersionSpecifiers;
+use pet::{
+    config::{load_settings, LoadedSettings, Settings},
+    config_show_stdio,
+};
+use pet::{
+    diff_report_stdio, doctor_report_stdio, export_report_stdio, filter::EnvironmentFilter,
+    find_and_report_envs_stdio, list_environments, resolve_report_stdio, suggest_report_stdio,
+    which_report_stdio, FindOptions, OutputFormat,
+};
+use pet_core::{
+    manager::EnvManagerType, os_environment::EnvironmentApi,
+    python_environment::PythonEnvironmentKind,
+};
 
 mod find;
 mod jsonrpc;
@@ -20,6 +38,7 @@ pub struct Cli {
 }
 
 #[derive(Subcommand, Debug)]
+#[allow(clippy::large_enum_variant)]
 enum Commands {
     /// Finds the environments and reports them to the standard output.
     Find {
@@ -46,18 +65,54 @@ enum Commands {
 
         /// Exclusively search just the workspace directories.
         /// I.e. exclude all global environments.
-        #[arg(short, long, conflicts_with = "kind")]
+        #[arg(short, long)]
         workspace: bool,
 
-        /// Exclusively search for a specific Python environment kind.
-        /// Will not search in the workspace directories.
-        #[arg(short, long, conflicts_with = "workspace")]
-        kind: Option<PythonEnvironmentKind>,
+        /// Only report environments of these kinds (repeat for multiple kinds).
+        /// When a single kind is provided without `--workspace`, only that kind is searched for
+        /// & the workspace directories are not searched.
+        #[arg(short, long, ignore_case = true)]
+        kind: Vec<PythonEnvironmentKind>,
 
-        /// Output results as JSON.
-        #[arg(short, long)]
+        /// Only report environments whose version satisfies these version specifiers, e.g. `>=3.11,<3.13`.
+        /// Environments with an unknown version are not reported.
+        #[arg(long, value_parser = parse_version_specifiers)]
+        version: Option<String>,
+
+        /// Only report environments of this project, i.e. environments created for the project or within the directory.
+        #[arg(long)]
+        project: Option<PathBuf>,
+
+        /// Only report environments managed by these tools (repeat for multiple managers).
+        #[arg(long, value_enum)]
+        manager: Vec<EnvManagerType>,
+
+        /// Only report environments with errors, e.g. broken symlinks or missing executables.
+        #[arg(long)]
+        has_error: bool,
+
+        /// Output results as JSON, same as `--format json`.
+        #[arg(short, long, conflicts_with = "format")]
         json: bool,
 
+        /// Format of the output, one of text, json, ndjson, table, csv, tsv or template=<template>.
+        /// `ndjson` writes each manager & environment as a JSON line as soon as it is found.
+        /// `table`, `csv` & `tsv` write a row for each environment with the `--columns`.
+        /// `template='{{kind}} {{executable}}'` writes a line for each environment with the placeholders replaced.
+        #[arg(long, add = ArgValueCandidates::new(get_format_candidates))]
+        format: Option<OutputFormat>,
+
+        /// Columns of the table, csv & tsv formats, the fields of the environment as in the JSON output,
+        /// e.g. `kind,version,prefix,project` or `manager.tool`.
+        /// Defaults to kind,name,version,prefix,executable.
+        #[arg(long, value_delimiter = ',', value_parser = parse_column)]
+        columns: Option<Vec<String>>,
+
+        /// Rank the environments found for the workspace folder (first search path or the current directory),
+        /// best environment first, along with the reasons for each score.
+        #[arg(long)]
+        ranked: bool,
+
         /// Path to the conda or mamba executable.
         #[arg(long, env = "PET_CONDA_EXECUTABLE")]
         conda_executable: Option<PathBuf>,
@@ -74,11 +129,16 @@ enum Commands {
         /// Use comma-separated values when setting via the environment variable.
         #[arg(long, env = "PET_ENVIRONMENT_DIRECTORIES", value_delimiter = ',')]
         environment_directories: Option<Vec<PathBuf>>,
+
+        /// Maximum number of threads used to discover environments.
+        /// Defaults to the number of CPUs.
+        #[arg(long, env = "PET_JOBS")]
+        jobs: Option<usize>,
     },
     /// Resolves & reports the details of the the environment to the standard output.
     Resolve {
-        /// Fully qualified path to the Python executable
-        #[arg(value_name = "PYTHON EXE")]
+        /// Fully qualified path to the Python executable, the prefix or the name of the environment.
+        #[arg(value_name = "PYTHON EXE", add = ArgValueCandidates::new(get_environment_candidates))]
         executable: PathBuf,
 
         /// Directory to cache the environment information after spawning Python.
@@ -93,11 +153,143 @@ enum Commands {
         #[arg(short, long)]
         json: bool,
     },
+    /// Suggests the best environment for a folder, without searching global locations.
+    Suggest {
+        /// Folder to suggest an environment for.
+        /// The current directory is used if none provided.
+        #[arg(value_name = "FOLDER")]
+        folder: Option<PathBuf>,
+
+        /// Directory to cache the environment information after spawning Python.
+        #[arg(short, long, env = "PET_CACHE_DIRECTORY")]
+        cache_directory: Option<PathBuf>,
+
+        /// Whether to display verbose output (defaults to warnings).
+        #[arg(short, long)]
+        verbose: bool,
+
+        /// Output results as JSON.
+        #[arg(short, long)]
+        json: bool,
+    },
+    /// Resolves the interpreter a command such as `python` would run in a directory,
+    /// following the shims of pyenv, asdf & mise as well as symlinks.
+    Which {
+        /// Command to resolve, e.g. `python3` or `python3.12`.
+        #[arg(value_name = "COMMAND", default_value = "python")]
+        command: String,
+
+        /// Directory the command is run in.
+        /// The current directory is used if none provided.
+        #[arg(long)]
+        cwd: Option<PathBuf>,
+
+        /// Whether to display verbose output (defaults to warnings).
+        #[arg(short, long)]
+        verbose: bool,
+
+        /// Output results as JSON.
+        #[arg(short, long)]
+        json: bool,
+    },
+    /// Checks the environments in depth & reports the problems found, along with a suggested fix.
+    /// E.g. virtual environments whose base interpreter was deleted or upgraded.
+    Doctor {
+        /// File/folder to search for environments to check.
+        /// All environments are checked if none provided.
+        #[arg(value_name = "PATH")]
+        path: Option<PathBuf>,
+
+        /// Directory to cache the environment information after spawning Python.
+        #[arg(short, long, env = "PET_CACHE_DIRECTORY")]
+        cache_directory: Option<PathBuf>,
+
+        /// Whether to display verbose output (defaults to warnings).
+        #[arg(short, long)]
+        verbose: bool,
+
+        /// Output results as JSON.
+        #[arg(short, long)]
+        json: bool,
+    },
+    /// Exports the managers & environments found on this machine, along with details of the host, as JSON.
+    /// Environments of the current directory are not exported.
+    Export {
+        /// File to write the inventory to.
+        /// The inventory is written to the standard output if none provided.
+        #[arg(short, long)]
+        out: Option<PathBuf>,
+
+        /// Directory to cache the environment information after spawning Python.
+        #[arg(short, long, env = "PET_CACHE_DIRECTORY")]
+        cache_directory: Option<PathBuf>,
+
+        /// Whether to display verbose output (defaults to warnings).
+        #[arg(short, long)]
+        verbose: bool,
+    },
+    /// Compares two inventories written by `pet export`,
+    /// reporting the environments added, removed or whose version changed.
+    Diff {
+        /// Older inventory.
+        #[arg(value_name = "OLD")]
+        old: PathBuf,
+
+        /// Newer inventory.
+        #[arg(value_name = "NEW")]
+        new: PathBuf,
+
+        /// Output results as JSON.
+        #[arg(short, long)]
+        json: bool,
+    },
+    /// Manages the configuration files.
+    Config {
+        #[command(subcommand)]
+        command: ConfigCommands,
+    },
+    /// Writes the script to register the completions for the shell, e.g. `source <(pet completions bash)`.
+    /// Environment names & prefixes are completed for `pet resolve`.
+    Completions {
+        #[arg(value_enum)]
+        shell: Shell,
+    },
+    /// Generates the man page.
+    Man {
+        /// Directory to write the man pages to, one for each command (e.g. `pet-find.1`).
+        /// The man page of `pet` is written to the standard output if none provided.
+        #[arg(long)]
+        output: Option<PathBuf>,
+    },
     /// Starts the JSON RPC Server.
     Server,
 }
 
+#[derive(Subcommand, Debug)]
+enum ConfigCommands {
+    /// Prints the effective configuration, merged from the config files & the environment variables.
+    /// Settings are taken from (highest precedence first) command line flags, `PET_*` environment variables,
+    /// the nearest `.pet.toml` in the workspace directory or its parents & `$XDG_CONFIG_HOME/pet/config.toml`.
+    Show {
+        /// Workspace directory, used to find the `.pet.toml` config file.
+        /// The current directory is used if none provided.
+        #[arg(long)]
+        cwd: Option<PathBuf>,
+
+        /// Output results as JSON.
+        #[arg(short, long)]
+        json: bool,
+    },
+}
+
+/// Variable set by the registered shell completions, to complete the command line instead of running the command.
+const COMPLETE_VAR: &str = "COMPLETE";
+
 fn main() {
+    CompleteEnv::with_factory(Cli::command)
+        .var(COMPLETE_VAR)
+        .complete();
+
     let cli = Cli::parse();
 
     match cli.command.unwrap_or(Commands::Find {
@@ -107,12 +299,20 @@ fn main() {
         search_paths: None,
         workspace: false,
         cache_directory: None,
-        kind: None,
+        kind: vec![],
+        version: None,
+        project: None,
+        manager: vec![],
+        has_error: false,
         json: false,
+        format: None,
+        columns: None,
+        ranked: false,
         conda_executable: None,
         pipenv_executable: None,
         poetry_executable: None,
         environment_directories: None,
+        jobs: None,
     }) {
         Commands::Find {
             list,
@@ -122,12 +322,30 @@ fn main() {
             workspace,
             cache_directory,
             kind,
+            version,
+            project,
+            manager,
+            has_error,
             json,
+            format,
+            columns,
+            ranked,
             conda_executable,
             pipenv_executable,
             poetry_executable,
             environment_directories,
+            jobs,
         } => {
+            // Config files are looked up from the first workspace folder.
+            let workspace_directory = search_paths
+                .as_deref()
+                .unwrap_or_default()
+                .iter()
+                .find(|path| path.is_dir())
+                .cloned()
+                .or_else(|| std::env::current_dir().ok());
+            let settings = load_settings_or_exit(workspace_directory);
+
             let mut workspace_only = workspace;
             if search_paths.clone().is_some()
                 && search_paths
@@ -147,12 +365,27 @@ fn main() {
                 search_paths,
                 workspace_only,
                 cache_directory,
-                kind,
-                json,
+                filter: EnvironmentFilter {
+                    kinds: (!kind.is_empty()).then_some(kind),
+                    version,
+                    project: project
+                        .map(|project| std::path::absolute(&project).unwrap_or(project)),
+                    managers: (!manager.is_empty()).then_some(manager),
+                    has_error: has_error.then_some(true),
+                },
+                format: format.unwrap_or(if json {
+                    OutputFormat::Json
+                } else {
+                    OutputFormat::Text
+                }),
+                columns,
+                ranked,
                 conda_executable,
                 pipenv_executable,
                 poetry_executable,
                 environment_directories,
+                jobs,
+                settings: settings.settings,
             });
         }
         Commands::Resolve {
@@ -160,7 +393,157 @@ fn main() {
             verbose,
             cache_directory,
             json,
-        } => resolve_report_stdio(executable, verbose, cache_directory, json),
+        } => {
+            let settings = load_settings_or_exit(std::env::current_dir().ok());
+            if let Err(err) = resolve_report_stdio(
+                executable,
+                verbose,
+                cache_directory,
+                json,
+                settings.settings,
+            ) {
+                eprintln!("{err}");
+                std::process::exit(1);
+            }
+        }
+        Commands::Suggest {
+            folder,
+            cache_directory,
+            verbose,
+            json,
+        } => {
+            let folder = folder
+                .or_else(|| std::env::current_dir().ok())
+                .unwrap_or_default();
+            let settings = load_settings_or_exit(Some(folder.clone()));
+            suggest_report_stdio(folder, verbose, cache_directory, json, settings.settings)
+        }
+        Commands::Which {
+            command,
+            cwd,
+            verbose,
+            json,
+        } => {
+            // Absolute, as the version files are looked up in the parent directories.
+            let cwd = cwd
+                .map(|cwd| std::path::absolute(&cwd).unwrap_or(cwd))
+                .or_else(|| std::env::current_dir().ok())
+                .unwrap_or_default();
+            let settings = load_settings_or_exit(Some(cwd.clone()));
+            which_report_stdio(command, cwd, verbose, json, settings.settings)
+        }
+        Commands::Doctor {
+            path,
+            cache_directory,
+            verbose,
+            json,
+        } => {
+            // Config files are looked up from the folder (or that of the executable) being checked.
+            let workspace_directory = match &path {
+                Some(path) if path.is_file() => path.parent().map(|dir| dir.to_path_buf()),
+                Some(path) => Some(path.clone()),
+                None => std::env::current_dir().ok(),
+            };
+            let settings = load_settings_or_exit(workspace_directory);
+            doctor_report_stdio(path, verbose, cache_directory, json, settings.settings)
+        }
+        Commands::Export {
+            out,
+            cache_directory,
+            verbose,
+        } => {
+            // Only the user config file, the environments of the machine are exported.
+            let settings = load_settings_or_exit(None);
+            if let Err(err) = export_report_stdio(out, verbose, cache_directory, settings.settings)
+            {
+                eprintln!("{err}");
+                std::process::exit(1);
+            }
+        }
+        Commands::Diff { old, new, json } => {
+            if let Err(err) = diff_report_stdio(old, new, json) {
+                eprintln!("{err}");
+                std::process::exit(1);
+            }
+        }
+        Commands::Config {
+            command: ConfigCommands::Show { cwd, json },
+        } => {
+            let cwd = cwd.or_else(|| std::env::current_dir().ok());
+            config_show_stdio(load_settings_or_exit(cwd), json)
+        }
+        Commands::Completions { shell } => {
+            let shells = Shells::builtins();
+            let completer = shells
+                .completer(&shell.to_string())
+                .expect("completer for each of the supported shells");
+            // The completions run this binary, so that environment names & prefixes can be discovered.
+            let bin = std::env::current_exe()
+                .map(|exe| exe.to_string_lossy().to_string())
+                .unwrap_or("pet".to_string());
+            completer
+                .write_registration(COMPLETE_VAR, "pet", "pet", &bin, &mut io::stdout())
+                .expect("failed to write the shell completions");
+        }
+        Commands::Man { output } => match output {
+            Some(output) => clap_mangen::generate_to(Cli::command(), &output)
+                .expect("failed to write the man pages"),
+            None => clap_mangen::Man::new(Cli::command())
+                .render(&mut io::stdout())
+                .expect("failed to write the man page"),
+        },
         Commands::Server => start_jsonrpc_server(),
     }
 }
+
+/// Loads the config files, exiting if a config file is invalid.
+fn load_settings_or_exit(workspace_directory: Option<PathBuf>) -> LoadedSettings {
+    match load_settings(&EnvironmentApi::new(), workspace_directory.as_deref()) {
+        Ok(settings) => settings,
+        Err(err) => {
+            eprintln!("{err}");
+            std::process::exit(1);
+        }
+    }
+}
+
+fn parse_version_specifiers(version: &str) -> Result<String, String> {
+    match VersionSpecifiers::parse(version).filter(VersionSpecifiers::is_valid) {
+        Some(_) => Ok(version.to_string()),
+        None => Err(format!(
+            "invalid version specifiers {version:?}, expected e.g. `>=3.11,<3.13`"
+        )),
+    }
+}
+
+/// Names & prefixes of the environments found, to complete the environment for `pet resolve`.
+fn get_environment_candidates() -> Vec<CompletionCandidate> {
+    // Completions must not print errors, an invalid config file is ignored instead.
+    let environment = EnvironmentApi::new();
+    let settings = load_settings(&environment, std::env::current_dir().ok().as_deref())
+        .map(|loaded| loaded.settings)
+        .unwrap_or_else(|_| Settings::from_env(&environment));
+    let mut candidates = vec![];
+    for env in list_environments(settings) {
+        let kind = env.kind.map(|kind| format!("{kind:?}")).unwrap_or_default();
+        if let Some(name) = env.name {
+            candidates.push((name, kind.clone()));
+        }
+        if let Some(prefix) = env.prefix {
+            candidates.push((prefix.to_string_lossy().to_string(), kind));
+        }
+    }
+    candidates.sort();
+    candidates.dedup_by(|a, b| a.0 == b.0);
+    candidates
+        .into_iter()
+        .map(|(value, kind)| CompletionCandidate::new(value).help(Some(kind.into())))
+        .collect()
+}
+
+fn get_format_candidates() -> Vec<CompletionCandidate> {
+    ["text", "json", "ndjson", "table", "csv", "tsv", "template="]
+        .into_iter()
+        .map(CompletionCandidate::new)
+        .collect()
+}
@@ -0,0 +1,260 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT License.
+
+//! Tabular (table, CSV, TSV) & template output of the environments, one row/line per environment.
+//!
+//! Columns & template placeholders are the fields of the environment as named in the JSON output,
+//! e.g. `kind`, `executable` or `requiresPython`, nested fields are separated by `.`, e.g. `manager.tool`.
+
+use pet_core::python_environment::PythonEnvironment;
+use serde_json::Value;
+
+/// Columns used when none are provided.
+pub const DEFAULT_COLUMNS: [&str; 5] = ["kind", "name", "version", "prefix", "executable"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TabularFormat {
+    /// Columns aligned with spaces, with a header.
+    Table,
+    Csv,
+    Tsv,
+}
+
+/// Validates the column (or template placeholder), returning the column.
+pub fn parse_column(column: &str) -> Result<String, String> {
+    let column = column.trim();
+    let field = column.split('.').next().unwrap_or_default();
+    let fields = get_field_names();
+    if fields.iter().any(|name| name == field) {
+        Ok(column.to_string())
+    } else {
+        Err(format!(
+            "unknown column {column:?}, expected one of {}",
+            fields.join(", ")
+        ))
+    }
+}
+
+/// Names of the fields of the environment (as in the JSON output).
+fn get_field_names() -> Vec<String> {
+    match serde_json::to_value(PythonEnvironment::default()) {
+        Ok(Value::Object(fields)) => fields.keys().cloned().collect(),
+        _ => vec![],
+    }
+}
+
+/// Validates the placeholders of the template, e.g. `{{kind}} {{executable}}`.
+pub fn parse_template(template: &str) -> Result<String, String> {
+    let mut result = Ok(template.to_string());
+    fill_template(template, |placeholder| {
+        if let Err(err) = parse_column(placeholder) {
+            result = Err(err);
+        }
+        String::new()
+    });
+    result
+}
+
+/// Replaces each `{{field}}` of the template with its value, whitespace around the field is ignored.
+fn fill_template(template: &str, mut get_value: impl FnMut(&str) -> String) -> String {
+    let mut output = String::new();
+    let mut rest = template;
+    while let Some(start) = rest.find("{{") {
+        let end = match rest[start + 2..].find("}}") {
+            Some(end) => start + 2 + end,
+            None => break,
+        };
+        output.push_str(&rest[..start]);
+        output.push_str(&get_value(rest[start + 2..end].trim()));
+        rest = &rest[end + 2..];
+    }
+    output.push_str(rest);
+    output
+}
+
+pub fn render_tabular(
+    environments: &[PythonEnvironment],
+    format: TabularFormat,
+    columns: &[String],
+) -> String {
+    let rows: Vec<Vec<String>> = environments
+        .iter()
+        .map(|env| {
+            let env = to_value(env);
+            columns
+                .iter()
+                .map(|column| get_field(&env, column))
+                .collect()
+        })
+        .collect();
+
+    let mut output = String::new();
+    match format {
+        TabularFormat::Table => {
+            let widths: Vec<usize> = columns
+                .iter()
+                .enumerate()
+                .map(|(i, column)| {
+                    rows.iter()
+                        .map(|row| row[i].chars().count())
+                        .chain([column.chars().count()])
+                        .max()
+                        .unwrap_or_default()
+                })
+                .collect();
+            let header: Vec<String> = columns.iter().map(|column| column.to_uppercase()).collect();
+            for row in [header].iter().chain(rows.iter()) {
+                let line: Vec<String> = row
+                    .iter()
+                    .zip(widths.iter())
+                    .map(|(value, width)| format!("{value:<width$}"))
+                    .collect();
+                output.push_str(line.join("  ").trim_end());
+                output.push('\n');
+            }
+        }
+        TabularFormat::Csv | TabularFormat::Tsv => {
+            let (separator, escape): (&str, fn(&str) -> String) = if format == TabularFormat::Csv {
+                (",", escape_csv)
+            } else {
+                ("\t", escape_tsv)
+            };
+            for row in [columns.to_vec()].iter().chain(rows.iter()) {
+                let line: Vec<String> = row.iter().map(|value| escape(value)).collect();
+                output.push_str(&line.join(separator));
+                output.push('\n');
+            }
+        }
+    }
+    output
+}
+
+/// One line per environment, with the `{{field}}` placeholders replaced by the values of the fields.
+pub fn render_template(environments: &[PythonEnvironment], template: &str) -> String {
+    let mut output = String::new();
+    for env in environments {
+        let env = to_value(env);
+        output.push_str(&fill_template(template, |field| get_field(&env, field)));
+        output.push('\n');
+    }
+    output
+}
+
+fn to_value(env: &PythonEnvironment) -> Value {
+    serde_json::to_value(env).unwrap_or_default()
+}
+
+/// Value of the (nested) field as text, lists are separated by `;` & missing values are empty.
+fn get_field(env: &Value, column: &str) -> String {
+    let value = column
+        .split('.')
+        .try_fold(env, |value, field| value.get(field));
+    match value {
+        None | Some(Value::Null) => String::new(),
+        Some(Value::String(value)) => value.clone(),
+        Some(Value::Array(values)) => values
+            .iter()
+            .map(|value| match value {
+                Value::String(value) => value.clone(),
+                value => value.to_string(),
+            })
+            .collect::<Vec<String>>()
+            .join(";"),
+        Some(value) => value.to_string(),
+    }
+}
+
+fn escape_csv(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+/// TSV has no quoting, hence tabs & new lines are replaced with spaces.
+fn escape_tsv(value: &str) -> String {
+    value.replace(['\t', '\n', '\r'], " ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::create_env;
+    use pet_core::{
+        manager::{EnvManager, EnvManagerType},
+        python_environment::PythonEnvironmentKind,
+    };
+    use std::path::PathBuf;
+
+    fn create_environments() -> Vec<PythonEnvironment> {
+        vec![
+            create_env(PythonEnvironmentKind::Conda, "/opt/conda", Some("3.12.1"))
+                .name(Some("base".to_string()))
+                .manager(Some(EnvManager::new(
+                    PathBuf::from("/opt/conda/bin/conda"),
+                    EnvManagerType::Conda,
+                    None,
+                )))
+                .build(),
+            create_env(
+                PythonEnvironmentKind::Venv,
+                "/home/user/my, project/.venv",
+                None,
+            )
+            .symlinks(Some(vec![
+                PathBuf::from("/home/user/my, project/.venv/bin/python"),
+                PathBuf::from("/home/user/my, project/.venv/bin/python3"),
+            ]))
+            .build(),
+        ]
+    }
+
+    fn columns(columns: &[&str]) -> Vec<String> {
+        columns.iter().map(|c| parse_column(c).unwrap()).collect()
+    }
+
+    #[test]
+    fn render_table_csv_and_tsv() {
+        let environments = create_environments();
+        let columns = columns(&["kind", "version", "prefix", "manager.tool"]);
+
+        assert_eq!(
+            render_tabular(&environments, TabularFormat::Table, &columns),
+            "KIND   VERSION  PREFIX                        MANAGER.TOOL\n\
+             Conda  3.12.1   /opt/conda                    Conda\n\
+             Venv            /home/user/my, project/.venv\n"
+        );
+        assert_eq!(
+            render_tabular(&environments, TabularFormat::Csv, &columns),
+            "kind,version,prefix,manager.tool\n\
+             Conda,3.12.1,/opt/conda,Conda\n\
+             Venv,,\"/home/user/my, project/.venv\",\n"
+        );
+        assert_eq!(
+            render_tabular(&environments, TabularFormat::Tsv, &columns),
+            "kind\tversion\tprefix\tmanager.tool\n\
+             Conda\t3.12.1\t/opt/conda\tConda\n\
+             Venv\t\t/home/user/my, project/.venv\t\n"
+        );
+    }
+
+    #[test]
+    fn render_templates() {
+        let environments = create_environments();
+
+        assert_eq!(
+            render_template(&environments, "{{kind}} {{ name }}: {{symlinks}}"),
+            "Conda base: \n\
+             Venv : /home/user/my, project/.venv/bin/python;/home/user/my, project/.venv/bin/python3\n"
+        );
+    }
+
+    #[test]
+    fn unknown_columns_are_rejected() {
+        assert!(parse_column("requiresPython").is_ok());
+        assert!(parse_column("manager.executable").is_ok());
+        assert!(parse_column("path").is_err());
+        assert!(parse_template("{{kind}} {{exe}}").is_err());
+    }
+}
@@ -0,0 +1,514 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT License.
+
+//! Minimal implementation of PEP 440 versions & version specifiers,
+//! sufficient to check the Python version of an environment against the `requires-python` of a project.
+//!
+//! Poetry constraints (`^3.9`, `~3.9`, `>=3.8 <4.0`, `3.9 || 3.10`) are supported as well,
+//! as projects managed by Poetry declare the Python versions in `tool.poetry.dependencies.python`.
+
+use std::cmp::Ordering;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum PreRelease {
+    Alpha,
+    Beta,
+    Candidate,
+}
+
+/// Sort key of the pre release part of a version, see `Version::suffix_sort_key`.
+type PreReleaseKey = (u8, Option<PreRelease>, u64);
+
+#[derive(Debug, Clone)]
+pub struct Version {
+    epoch: u64,
+    release: Vec<u64>,
+    pre: Option<(PreRelease, u64)>,
+    post: Option<u64>,
+    dev: Option<u64>,
+    local: Option<String>,
+}
+
+impl Version {
+    /// Parses versions such as `3.12.1`, `3.13.0rc1`, `1!2.0.post1.dev3+local`,
+    /// as well as the `3.8.1.final.0` & `3.8.1+` formats reported by some Python installations.
+    pub fn parse(version: &str) -> Option<Version> {
+        let version = version.trim().to_lowercase();
+        let version = version.strip_prefix('v').unwrap_or(&version);
+        let (version, local) = match version.split_once('+') {
+            Some((version, local)) => (version, Some(local.to_string())),
+            None => (version, None),
+        };
+        let (epoch, version) = match version.split_once('!') {
+            Some((epoch, version)) => (epoch.parse::<u64>().ok()?, version),
+            None => (0, version),
+        };
+
+        let release_len = version
+            .find(|c: char| !c.is_ascii_digit() && c != '.')
+            .unwrap_or(version.len());
+        let release_str = version[..release_len].trim_end_matches('.');
+        let release = release_str
+            .split('.')
+            .map(|segment| segment.parse::<u64>().ok())
+            .collect::<Option<Vec<u64>>>()?;
+        let mut rest = &version[release_str.len()..];
+
+        // `sys.version_info` formatted versions, e.g. `3.8.1.final.0`.
+        if rest.trim_start_matches('.').starts_with("final") {
+            rest = "";
+        }
+
+        let pre = parse_labelled_number(
+            &mut rest,
+            &[
+                ("alpha", PreRelease::Alpha),
+                ("a", PreRelease::Alpha),
+                ("beta", PreRelease::Beta),
+                ("b", PreRelease::Beta),
+                ("candidate", PreRelease::Candidate),
+                ("rc", PreRelease::Candidate),
+                ("c", PreRelease::Candidate),
+                ("preview", PreRelease::Candidate),
+                ("pre", PreRelease::Candidate),
+            ],
+        );
+        let post = parse_labelled_number(&mut rest, &[("post", ()), ("rev", ()), ("r", ())])
+            .map(|(_, number)| number)
+            .or_else(|| {
+                // Implicit post release, e.g. `1.0-1`.
+                let number = rest.strip_prefix('-')?;
+                let digits = number.len()
+                    - number
+                        .trim_start_matches(|c: char| c.is_ascii_digit())
+                        .len();
+                if digits == 0 {
+                    return None;
+                }
+                let value = number[..digits].parse::<u64>().ok()?;
+                rest = &number[digits..];
+                Some(value)
+            });
+        let dev = parse_labelled_number(&mut rest, &[("dev", ())]).map(|(_, number)| number);
+
+        if !rest.is_empty() {
+            return None;
+        }
+        Some(Version {
+            epoch,
+            release,
+            pre,
+            post,
+            dev,
+            local: local.filter(|local| !local.is_empty()),
+        })
+    }
+
+    pub fn release(&self) -> &[u64] {
+        &self.release
+    }
+
+    pub fn is_prerelease(&self) -> bool {
+        self.pre.is_some() || self.dev.is_some()
+    }
+
+    /// The version without the pre, post, dev & local parts, e.g. `3.13.0` for `3.13.0rc1`.
+    fn base(&self) -> Version {
+        Version {
+            epoch: self.epoch,
+            release: self.release.clone(),
+            pre: None,
+            post: None,
+            dev: None,
+            local: None,
+        }
+    }
+
+    fn without_local(&self) -> Version {
+        Version {
+            local: None,
+            ..self.clone()
+        }
+    }
+
+    fn suffix_sort_key(&self) -> (PreReleaseKey, (u8, u64), (u8, u64)) {
+        // Dev releases without a pre or post release come before the pre releases.
+        let pre = match self.pre {
+            None if self.post.is_none() && self.dev.is_some() => (0, None, 0),
+            Some((kind, number)) => (1, Some(kind), number),
+            None => (2, None, 0),
+        };
+        let post = match self.post {
+            Some(number) => (1, number),
+            None => (0, 0),
+        };
+        let dev = match self.dev {
+            Some(number) => (0, number),
+            None => (1, 0),
+        };
+        (pre, post, dev)
+    }
+}
+
+impl Ord for Version {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.epoch
+            .cmp(&other.epoch)
+            .then_with(|| compare_release(&self.release, &other.release))
+            .then_with(|| self.suffix_sort_key().cmp(&other.suffix_sort_key()))
+            .then_with(|| self.local.cmp(&other.local))
+    }
+}
+
+/// Versions are equal if they only differ by trailing zeros, e.g. `3.12` == `3.12.0`.
+impl PartialEq for Version {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Version {}
+
+impl PartialOrd for Version {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// Compares release segments, missing segments are treated as zeros (`3.12` == `3.12.0`).
+fn compare_release(a: &[u64], b: &[u64]) -> Ordering {
+    for i in 0..a.len().max(b.len()) {
+        let ordering = a.get(i).unwrap_or(&0).cmp(b.get(i).unwrap_or(&0));
+        if ordering != Ordering::Equal {
+            return ordering;
+        }
+    }
+    Ordering::Equal
+}
+
+/// Parses an optional separator, one of the labels & an optional number, e.g. `.post1`, `rc2`, `-dev`.
+fn parse_labelled_number<T: Copy>(rest: &mut &str, labels: &[(&str, T)]) -> Option<(T, u64)> {
+    let trimmed = rest.trim_start_matches(['.', '-', '_']);
+    let (value, remainder) = labels
+        .iter()
+        .find_map(|(label, value)| Some((*value, trimmed.strip_prefix(label)?)))?;
+    let remainder = remainder.trim_start_matches(['.', '-', '_']);
+    let digits = remainder.len()
+        - remainder
+            .trim_start_matches(|c: char| c.is_ascii_digit())
+            .len();
+    let number = if digits == 0 {
+        0
+    } else {
+        remainder[..digits].parse::<u64>().ok()?
+    };
+    *rest = &remainder[digits..];
+    Some((value, number))
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Operator {
+    Equal,
+    NotEqual,
+    LessThanEqual,
+    GreaterThanEqual,
+    LessThan,
+    GreaterThan,
+    Compatible,
+    ArbitraryEqual,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Specifier {
+    operator: Operator,
+    version: String,
+}
+
+impl Specifier {
+    /// Whether the version of the specifier can be parsed, e.g. `>=three` cannot.
+    fn is_valid(&self) -> bool {
+        if self.operator == Operator::ArbitraryEqual {
+            return true;
+        }
+        let version = match self.version.strip_suffix(".*") {
+            Some(prefix) => prefix,
+            None => &self.version,
+        };
+        Version::parse(version).is_some()
+    }
+
+    fn contains(&self, version: &Version, raw_version: &str) -> Option<bool> {
+        if self.operator == Operator::ArbitraryEqual {
+            return Some(raw_version.trim().eq_ignore_ascii_case(&self.version));
+        }
+        if let Some(prefix) = self.version.strip_suffix(".*") {
+            let prefix = Version::parse(prefix)?;
+            let matches = version.epoch == prefix.epoch
+                && prefix
+                    .release
+                    .iter()
+                    .enumerate()
+                    .all(|(i, segment)| version.release.get(i).unwrap_or(&0) == segment);
+            return match self.operator {
+                Operator::Equal => Some(matches),
+                Operator::NotEqual => Some(!matches),
+                _ => None,
+            };
+        }
+
+        let expected = Version::parse(&self.version)?;
+        // Local versions are ignored, unless the specifier has a local version.
+        let version = if expected.local.is_none() {
+            version.without_local()
+        } else {
+            version.clone()
+        };
+        Some(match self.operator {
+            Operator::Equal => version == expected,
+            Operator::NotEqual => version != expected,
+            Operator::LessThanEqual => version <= expected,
+            Operator::GreaterThanEqual => version >= expected,
+            Operator::LessThan => {
+                // `<3.13` should not match `3.13.0rc1`.
+                version < expected
+                    && (expected.is_prerelease()
+                        || !version.is_prerelease()
+                        || version.base() != expected.base())
+            }
+            Operator::GreaterThan => {
+                // `>3.12` should not match `3.12.post1`.
+                version > expected
+                    && (expected.post.is_some()
+                        || version.post.is_none()
+                        || version.base() != expected.base())
+            }
+            Operator::Compatible => {
+                if expected.release.len() < 2 {
+                    return None;
+                }
+                let prefix = &expected.release[..expected.release.len() - 1];
+                version >= expected
+                    && prefix
+                        .iter()
+                        .enumerate()
+                        .all(|(i, segment)| version.release.get(i).unwrap_or(&0) == segment)
+            }
+            Operator::ArbitraryEqual => unreachable!("handled above"),
+        })
+    }
+}
+
+/// A set of version constraints, such as `>=3.9,<3.13` or `^3.10 || ^4.0`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VersionSpecifiers {
+    /// Any of the groups must be satisfied, all the specifiers of a group must be satisfied.
+    groups: Vec<Vec<Specifier>>,
+}
+
+impl VersionSpecifiers {
+    pub fn parse(specifiers: &str) -> Option<VersionSpecifiers> {
+        let groups = specifiers
+            .split("||")
+            .map(parse_group)
+            .collect::<Option<Vec<_>>>()?;
+        Some(VersionSpecifiers { groups })
+    }
+
+    /// Whether all of the versions in the constraints can be parsed.
+    /// Unparsable versions are otherwise only detected when checking a version against the constraints.
+    pub fn is_valid(&self) -> bool {
+        self.groups
+            .iter()
+            .all(|group| group.iter().all(Specifier::is_valid))
+    }
+
+    /// Whether the version satisfies the constraints.
+    /// Returns None if the version could not be parsed.
+    pub fn contains(&self, version: &str) -> Option<bool> {
+        let parsed = Version::parse(version)?;
+        let mut contains = false;
+        for group in self.groups.iter() {
+            let mut group_contains = true;
+            for specifier in group.iter() {
+                group_contains &= specifier.contains(&parsed, version)?;
+            }
+            contains |= group_contains;
+        }
+        Some(contains)
+    }
+}
+
+fn parse_group(group: &str) -> Option<Vec<Specifier>> {
+    let mut specifiers = vec![];
+    for clause in split_clauses(group) {
+        specifiers.extend(parse_clause(&clause)?);
+    }
+    Some(specifiers)
+}
+
+/// Splits on commas as well as whitespace between clauses (Poetry allows `>=3.8 <4.0`),
+/// while keeping whitespace between an operator & its version (`>= 3.8`).
+fn split_clauses(group: &str) -> Vec<String> {
+    let mut clauses: Vec<String> = vec![];
+    for part in group.split(',') {
+        let mut current = String::new();
+        for word in part.split_whitespace() {
+            let is_operator_only = !current.is_empty()
+                && current
+                    .chars()
+                    .all(|c| matches!(c, '=' | '!' | '<' | '>' | '~' | '^'));
+            if current.is_empty() || is_operator_only {
+                current.push_str(word);
+            } else {
+                clauses.push(std::mem::take(&mut current));
+                current.push_str(word);
+            }
+        }
+        if !current.is_empty() {
+            clauses.push(current);
+        }
+    }
+    clauses
+}
+
+fn parse_clause(clause: &str) -> Option<Vec<Specifier>> {
+    let specifier = |operator: Operator, version: String| Specifier { operator, version };
+    const OPERATORS: [(&str, Operator); 8] = [
+        ("===", Operator::ArbitraryEqual),
+        ("==", Operator::Equal),
+        ("!=", Operator::NotEqual),
+        ("<=", Operator::LessThanEqual),
+        (">=", Operator::GreaterThanEqual),
+        ("~=", Operator::Compatible),
+        ("<", Operator::LessThan),
+        (">", Operator::GreaterThan),
+    ];
+    if clause == "*" {
+        return Some(vec![]);
+    }
+    if let Some((operator, version)) = OPERATORS
+        .iter()
+        .find_map(|(prefix, operator)| Some((*operator, clause.strip_prefix(prefix)?)))
+    {
+        return Some(vec![specifier(operator, version.trim().to_string())]);
+    }
+
+    // Poetry constraints.
+    if let Some(version) = clause.strip_prefix('^') {
+        let lower = Version::parse(version)?;
+        let release = lower.release();
+        // Bump the first non-zero segment, e.g. `^1.2` => `<2`, `^0.2` => `<0.3`.
+        let index = release
+            .iter()
+            .position(|segment| *segment != 0)
+            .unwrap_or(release.len() - 1);
+        let upper = bump(release, index);
+        return Some(vec![
+            specifier(Operator::GreaterThanEqual, version.to_string()),
+            specifier(Operator::LessThan, upper),
+        ]);
+    }
+    if let Some(version) = clause.strip_prefix('~') {
+        let lower = Version::parse(version)?;
+        let release = lower.release();
+        // `~3` => `<4`, `~3.9` & `~3.9.1` => `<3.10`.
+        let index = if release.len() == 1 { 0 } else { 1 };
+        let upper = bump(release, index);
+        return Some(vec![
+            specifier(Operator::GreaterThanEqual, version.to_string()),
+            specifier(Operator::LessThan, upper),
+        ]);
+    }
+    // Bare versions, e.g. `3.9` or `3.9.*`.
+    Some(vec![specifier(Operator::Equal, clause.to_string())])
+}
+
+fn bump(release: &[u64], index: usize) -> String {
+    let mut upper: Vec<u64> = release.iter().take(index + 1).cloned().collect();
+    upper[index] += 1;
+    upper
+        .iter()
+        .map(|segment| segment.to_string())
+        .collect::<Vec<String>>()
+        .join(".")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn contains(specifiers: &str, version: &str) -> Option<bool> {
+        VersionSpecifiers::parse(specifiers)?.contains(version)
+    }
+
+    #[test]
+    fn versions_are_ordered_as_per_pep440() {
+        let versions = [
+            "3.13.0.dev1",
+            "3.13.0a1",
+            "3.13.0b2",
+            "3.13.0rc1",
+            "3.13.0",
+            "3.13.0.post1",
+            "3.13.1",
+            "1!1.0",
+        ];
+        for pair in versions.windows(2) {
+            assert!(
+                Version::parse(pair[0]).unwrap() < Version::parse(pair[1]).unwrap(),
+                "{} < {}",
+                pair[0],
+                pair[1]
+            );
+        }
+        assert_eq!(
+            Version::parse("3.8.1.final.0").unwrap(),
+            Version::parse("3.8.1").unwrap()
+        );
+        assert_eq!(
+            Version::parse("3.12")
+                .unwrap()
+                .cmp(&Version::parse("3.12.0").unwrap()),
+            Ordering::Equal
+        );
+        assert!(Version::parse("unknown").is_none());
+    }
+
+    #[test]
+    fn pep440_specifiers() {
+        assert_eq!(contains(">=3.9, <3.13", "3.12.1"), Some(true));
+        assert_eq!(contains(">=3.9, <3.13", "3.13.0"), Some(false));
+        assert_eq!(contains(">= 3.11", "3.8.10"), Some(false));
+        assert_eq!(contains("<3.13", "3.13.0rc1"), Some(false));
+        assert_eq!(contains(">3.12", "3.12.0.post1"), Some(false));
+        assert_eq!(contains("==3.12.*", "3.12.4"), Some(true));
+        assert_eq!(contains("!=3.12.*", "3.12.4"), Some(false));
+        assert_eq!(contains("==3.12", "3.12.0+local"), Some(true));
+        assert_eq!(contains("~=3.10", "3.12.1"), Some(true));
+        assert_eq!(contains("~=3.10.2", "3.11.0"), Some(false));
+        assert_eq!(contains("===3.12.1", "3.12.1"), Some(true));
+        assert_eq!(contains(">=3.11", "unknown"), None);
+        assert!(VersionSpecifiers::parse(">=abc")
+            .unwrap()
+            .contains("3.12")
+            .is_none());
+        assert!(!VersionSpecifiers::parse(">=abc").unwrap().is_valid());
+        assert!(VersionSpecifiers::parse(">=3.11,<3.13 || ==3.9.*")
+            .unwrap()
+            .is_valid());
+    }
+
+    #[test]
+    fn poetry_constraints() {
+        assert_eq!(contains("^3.9", "3.12.1"), Some(true));
+        assert_eq!(contains("^3.9", "4.0.0"), Some(false));
+        assert_eq!(contains("^0.2", "0.3.0"), Some(false));
+        assert_eq!(contains("~3.9", "3.10.0"), Some(false));
+        assert_eq!(contains("~3.9", "3.9.18"), Some(true));
+        assert_eq!(contains(">=3.8 <3.11", "3.10.2"), Some(true));
+        assert_eq!(contains(">=3.8 <3.11", "3.11.0"), Some(false));
+        assert_eq!(contains("3.9 || 3.11.*", "3.11.4"), Some(true));
+        assert_eq!(contains("3.9", "3.9.0"), Some(true));
+        assert_eq!(contains("*", "3.12.0"), Some(true));
+    }
+}
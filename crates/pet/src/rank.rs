@@ -0,0 +1,287 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT License.
+
+//! Ranks the discovered environments, to recommend an environment for a workspace folder.
+//!
+//! The score of each environment is the sum of the scores of the signals that apply to it,
+//! & every signal is reported as a reason, so that clients can explain the recommendation.
+//! Environments with the same score are ordered by version, newest first.
+
+use std::{cmp::Ordering, path::Path};
+
+use pet_core::python_environment::{PythonEnvironment, PythonEnvironmentKind};
+use pet_fs::path::norm_case;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    pep440::Version,
+    requires_python::{get_requires_python, is_compatible},
+};
+
+const WORKSPACE_ENVIRONMENT_SCORE: i32 = 400;
+const PROJECT_ENVIRONMENT_SCORE: i32 = 300;
+const NAMED_CONDA_ENVIRONMENT_SCORE: i32 = 200;
+const GLOBAL_ENVIRONMENT_SCORE: i32 = 100;
+const COMPATIBLE_VERSION_SCORE: i32 = 150;
+const INCOMPATIBLE_VERSION_SCORE: i32 = -500;
+const UNUSABLE_ENVIRONMENT_SCORE: i32 = -1000;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RankReason {
+    pub score: i32,
+    pub reason: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RankedEnvironment {
+    pub environment: PythonEnvironment,
+    pub score: i32,
+    pub reasons: Vec<RankReason>,
+}
+
+/// Ranks the environments for the workspace folder, best environment first.
+pub fn rank_environments(
+    workspace_folder: &Path,
+    environments: Vec<PythonEnvironment>,
+) -> Vec<RankedEnvironment> {
+    let workspace_folder = norm_case(workspace_folder);
+    let requires_python = get_requires_python(&workspace_folder);
+    let mut ranked: Vec<RankedEnvironment> = environments
+        .into_iter()
+        .map(|environment| {
+            let reasons =
+                get_rank_reasons(&workspace_folder, requires_python.as_deref(), &environment);
+            RankedEnvironment {
+                score: reasons.iter().map(|r| r.score).sum(),
+                environment,
+                reasons,
+            }
+        })
+        .collect();
+    ranked.sort_by(|a, b| {
+        b.score
+            .cmp(&a.score)
+            .then_with(|| compare_versions(&b.environment, &a.environment))
+            .then_with(|| a.environment.cmp(&b.environment))
+    });
+    ranked
+}
+
+fn get_rank_reasons(
+    workspace_folder: &Path,
+    requires_python: Option<&str>,
+    env: &PythonEnvironment,
+) -> Vec<RankReason> {
+    let mut reasons = vec![];
+    let mut add = |score: i32, reason: String| reasons.push(RankReason { score, reason });
+
+    if is_in_workspace(workspace_folder, env) {
+        add(
+            WORKSPACE_ENVIRONMENT_SCORE,
+            "Environment is in the workspace folder".to_string(),
+        );
+    } else if is_linked_to_workspace(workspace_folder, env) {
+        add(
+            PROJECT_ENVIRONMENT_SCORE,
+            format!(
+                "Environment is linked to the workspace by {}",
+                kind_name(env)
+            ),
+        );
+    } else if env.kind == Some(PythonEnvironmentKind::Conda) && env.name.is_some() {
+        add(
+            NAMED_CONDA_ENVIRONMENT_SCORE,
+            "Environment is a named conda environment".to_string(),
+        );
+    } else if env.kind.map(is_global_kind).unwrap_or_default() {
+        add(
+            GLOBAL_ENVIRONMENT_SCORE,
+            format!("Environment is a global {} installation", kind_name(env)),
+        );
+    }
+
+    if let (Some(requires_python), Some(version)) = (requires_python, env.version.as_deref()) {
+        match is_compatible(requires_python, version) {
+            Some(true) => add(
+                COMPATIBLE_VERSION_SCORE,
+                format!("Version {version} satisfies requires-python {requires_python}"),
+            ),
+            Some(false) => add(
+                INCOMPATIBLE_VERSION_SCORE,
+                format!("Version {version} does not satisfy requires-python {requires_python}"),
+            ),
+            None => {}
+        }
+    }
+
+    if env.executable.is_none() {
+        add(
+            UNUSABLE_ENVIRONMENT_SCORE,
+            "Environment does not have a Python executable".to_string(),
+        );
+    }
+    if let Some(error) = &env.error {
+        add(
+            UNUSABLE_ENVIRONMENT_SCORE,
+            format!("Environment has an error: {error}"),
+        );
+    }
+
+    reasons
+}
+
+fn is_in_workspace(workspace_folder: &Path, env: &PythonEnvironment) -> bool {
+    env.prefix
+        .as_ref()
+        .or(env.executable.as_ref())
+        .map(|path| norm_case(path).starts_with(workspace_folder))
+        .unwrap_or_default()
+}
+
+fn is_linked_to_workspace(workspace_folder: &Path, env: &PythonEnvironment) -> bool {
+    env.project
+        .as_ref()
+        .map(|project| workspace_folder.starts_with(norm_case(project)))
+        .unwrap_or_default()
+}
+
+fn is_global_kind(kind: PythonEnvironmentKind) -> bool {
+    matches!(
+        kind,
+        PythonEnvironmentKind::GlobalPaths
+            | PythonEnvironmentKind::LinuxGlobal
+            | PythonEnvironmentKind::Homebrew
+            | PythonEnvironmentKind::Pyenv
+            | PythonEnvironmentKind::MacPythonOrg
+            | PythonEnvironmentKind::MacCommandLineTools
+            | PythonEnvironmentKind::MacXCode
+            | PythonEnvironmentKind::WinPython
+            | PythonEnvironmentKind::WindowsStore
+            | PythonEnvironmentKind::WindowsRegistry
+    )
+}
+
+fn kind_name(env: &PythonEnvironment) -> String {
+    env.kind
+        .map(|kind| format!("{kind:?}"))
+        .unwrap_or("Unknown".to_string())
+}
+
+fn compare_versions(a: &PythonEnvironment, b: &PythonEnvironment) -> Ordering {
+    let a = a.version.as_deref().and_then(Version::parse);
+    let b = b.version.as_deref().and_then(Version::parse);
+    // Environments without a known version come last.
+    a.cmp(&b)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils;
+    use pet_core::python_environment::PythonEnvironmentBuilder;
+    use std::{fs, path::PathBuf};
+
+    fn create_env(
+        kind: PythonEnvironmentKind,
+        prefix: PathBuf,
+        version: &str,
+    ) -> PythonEnvironmentBuilder {
+        let executable = prefix.join("bin").join("python");
+        test_utils::create_env(kind, prefix, Some(version)).executable(Some(executable))
+    }
+
+    #[test]
+    fn workspace_environments_are_ranked_above_project_conda_and_global() {
+        let workspace = tempfile::tempdir().unwrap();
+        let workspace = workspace.path();
+        let global = create_env(
+            PythonEnvironmentKind::LinuxGlobal,
+            PathBuf::from("/usr"),
+            "3.12.1",
+        )
+        .build();
+        let conda = create_env(
+            PythonEnvironmentKind::Conda,
+            PathBuf::from("/conda/envs/data"),
+            "3.12.1",
+        )
+        .name(Some("data".to_string()))
+        .build();
+        let poetry = create_env(
+            PythonEnvironmentKind::Poetry,
+            PathBuf::from("/poetry/app-abc-py3.12"),
+            "3.12.1",
+        )
+        .project(Some(workspace.to_path_buf()))
+        .build();
+        let venv = create_env(
+            PythonEnvironmentKind::Venv,
+            workspace.join(".venv"),
+            "3.12.1",
+        )
+        .build();
+
+        let ranked = rank_environments(
+            workspace,
+            vec![global.clone(), conda.clone(), poetry.clone(), venv.clone()],
+        );
+
+        let order: Vec<_> = ranked.iter().map(|r| r.environment.clone()).collect();
+        assert_eq!(order, vec![venv, poetry, conda, global]);
+        assert_eq!(ranked[0].score, WORKSPACE_ENVIRONMENT_SCORE);
+        assert_eq!(
+            ranked[0].reasons[0].reason,
+            "Environment is in the workspace folder"
+        );
+    }
+
+    #[test]
+    fn compatible_newest_version_without_errors_is_preferred() {
+        let workspace = tempfile::tempdir().unwrap();
+        let workspace = workspace.path();
+        fs::write(
+            workspace.join("pyproject.toml"),
+            "[project]\nrequires-python = \">=3.11\"\n",
+        )
+        .unwrap();
+        let old = create_env(
+            PythonEnvironmentKind::Pyenv,
+            "/pyenv/3.8.10".into(),
+            "3.8.10",
+        )
+        .build();
+        let new = create_env(
+            PythonEnvironmentKind::Pyenv,
+            "/pyenv/3.11.9".into(),
+            "3.11.9",
+        )
+        .build();
+        let newest = create_env(
+            PythonEnvironmentKind::Pyenv,
+            "/pyenv/3.12.4".into(),
+            "3.12.4",
+        )
+        .build();
+        let broken = create_env(
+            PythonEnvironmentKind::Pyenv,
+            "/pyenv/3.13.0".into(),
+            "3.13.0",
+        )
+        .error(Some("Python executable is a broken symlink".to_string()))
+        .build();
+
+        let ranked = rank_environments(
+            workspace,
+            vec![old.clone(), broken.clone(), new.clone(), newest.clone()],
+        );
+
+        let order: Vec<_> = ranked.iter().map(|r| r.environment.clone()).collect();
+        assert_eq!(order, vec![newest, new, old, broken]);
+        assert!(ranked[2]
+            .reasons
+            .iter()
+            .any(|r| r.score == INCOMPATIBLE_VERSION_SCORE));
+    }
+}
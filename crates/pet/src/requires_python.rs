@@ -0,0 +1,76 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT License.
+
+use std::{fs, path::Path};
+
+use log::trace;
+
+use crate::pep440::VersionSpecifiers;
+
+/// Reads the Python versions supported by the project in the workspace folder,
+/// i.e. the `project.requires-python` or `tool.poetry.dependencies.python` in `pyproject.toml`.
+pub fn get_requires_python(workspace_folder: &Path) -> Option<String> {
+    let file = workspace_folder.join("pyproject.toml");
+    let contents = fs::read_to_string(&file).ok()?;
+    let value = match toml::from_str::<toml::Value>(&contents) {
+        Ok(value) => value,
+        Err(e) => {
+            trace!("Failed to parse {:?}: {}", file, e);
+            return None;
+        }
+    };
+    value
+        .get("project")
+        .and_then(|project| project.get("requires-python"))
+        .or_else(|| {
+            value
+                .get("tool")?
+                .get("poetry")?
+                .get("dependencies")?
+                .get("python")
+        })?
+        .as_str()
+        .map(|s| s.trim().to_string())
+}
+
+/// Whether the Python version satisfies the version specifiers, such as `>=3.9,<4` or `^3.10`.
+/// Returns None if the specifiers or the version could not be parsed.
+pub fn is_compatible(requires_python: &str, version: &str) -> Option<bool> {
+    VersionSpecifiers::parse(requires_python)?.contains(version)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn requires_python_is_read_from_pyproject() {
+        let workspace = tempfile::tempdir().unwrap();
+        fs::write(
+            workspace.path().join("pyproject.toml"),
+            "[project]\nname = \"app\"\nrequires-python = \">=3.11\"\n",
+        )
+        .unwrap();
+
+        assert_eq!(
+            get_requires_python(workspace.path()),
+            Some(">=3.11".to_string())
+        );
+    }
+
+    #[test]
+    fn requires_python_is_read_from_poetry_dependencies() {
+        let workspace = tempfile::tempdir().unwrap();
+        fs::write(
+            workspace.path().join("pyproject.toml"),
+            "[tool.poetry]\nname = \"app\"\n\n[tool.poetry.dependencies]\npython = \"^3.10\"\nrequests = \"*\"\n",
+        )
+        .unwrap();
+
+        assert_eq!(
+            get_requires_python(workspace.path()),
+            Some("^3.10".to_string())
+        );
+        assert_eq!(is_compatible("^3.10", "3.8.10"), Some(false));
+    }
+}
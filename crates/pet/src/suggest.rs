@@ -0,0 +1,233 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT License.
+
+//! Fast path to answer "what is the best environment for this folder".
+//!
+//! Instead of running every locator & searching all global locations, only the
+//! markers in the folder (`.venv`, `.python-version`, Poetry/Pipenv/uv/Pixi files)
+//! are checked, and the first environment found in the order of preference is returned.
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use log::trace;
+use pet_core::{
+    env::PythonEnv, os_environment::Environment, python_environment::PythonEnvironment, Locator,
+};
+use pet_env_var_path::get_search_paths_from_env_variables;
+use pet_pipenv::PipEnv;
+use pet_poetry::{env_variables::EnvVariables, environment_locations::list_environments};
+use pet_pyenv::version_selection::{
+    find_installed_version, find_local_version_file, get_versions_dir, read_version_file,
+};
+use pet_python_utils::executable::find_executable;
+use serde::{Deserialize, Serialize};
+
+use crate::locators::identify_python_environment_using_locators;
+
+/// Virtual environment folders commonly created in a workspace, in order of preference.
+const WORKSPACE_ENVIRONMENT_FOLDERS: [&str; 4] = [".venv", "venv", ".conda", ".virtualenv"];
+
+/// Why an environment was suggested for a workspace folder.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum SuggestionSource {
+    /// The environment configured using the `UV_PROJECT_ENVIRONMENT` environment variable.
+    UvProjectEnvironment,
+    /// Pixi environment of a project with a `pixi.toml` or `pixi.lock`.
+    Pixi,
+    /// Environment in the workspace folder, such as `.venv`.
+    WorkspaceEnvironment,
+    /// Poetry environment of a project with a `pyproject.toml`.
+    Poetry,
+    /// Pipenv environment of a project with a `Pipfile`.
+    Pipenv,
+    /// pyenv version selected by a `.python-version` file.
+    PythonVersionFile,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Suggestion {
+    pub environment: PythonEnvironment,
+    pub source: SuggestionSource,
+}
+
+/// Returns the best environment for the workspace folder, without searching global locations.
+pub fn suggest_environment(
+    workspace_folder: &Path,
+    locators: &[Arc<dyn Locator>],
+    os_environment: &dyn Environment,
+) -> Option<Suggestion> {
+    let global_env_search_paths = get_search_paths_from_env_variables(os_environment);
+    let identify = |prefix: &Path| -> Option<PythonEnvironment> {
+        let executable = find_executable(prefix)?;
+        let env = PythonEnv::new(executable, Some(prefix.to_path_buf()), None);
+        identify_python_environment_using_locators(&env, locators, &global_env_search_paths)
+    };
+    let suggest = |environment: PythonEnvironment, source: SuggestionSource| {
+        trace!(
+            "Suggesting {:?} for {:?} based on {:?}",
+            environment.executable,
+            workspace_folder,
+            source
+        );
+        Some(Suggestion {
+            environment,
+            source,
+        })
+    };
+
+    if let Some(prefix) = get_uv_project_environment(workspace_folder, os_environment) {
+        if let Some(env) = identify(&prefix) {
+            return suggest(env, SuggestionSource::UvProjectEnvironment);
+        }
+    }
+
+    if workspace_folder.join("pixi.toml").is_file() || workspace_folder.join("pixi.lock").is_file()
+    {
+        for prefix in get_pixi_environments(workspace_folder) {
+            if let Some(env) = identify(&prefix) {
+                return suggest(env, SuggestionSource::Pixi);
+            }
+        }
+    }
+
+    for folder in WORKSPACE_ENVIRONMENT_FOLDERS {
+        if let Some(env) = identify(&workspace_folder.join(folder)) {
+            return suggest(env, SuggestionSource::WorkspaceEnvironment);
+        }
+    }
+
+    if workspace_folder.join("pyproject.toml").is_file() {
+        let env_vars = EnvVariables::from(os_environment);
+        let environments = list_environments(&env_vars, &[workspace_folder.to_path_buf()], None)
+            .unwrap_or_default();
+        if let Some(env) = environments
+            .into_iter()
+            .find(|env| env.executable.is_some() && env.error.is_none())
+        {
+            return suggest(env, SuggestionSource::Poetry);
+        }
+    }
+
+    if workspace_folder.join("Pipfile").is_file() {
+        if let Some(env) = PipEnv::from(os_environment).find_for_project(workspace_folder) {
+            return suggest(env, SuggestionSource::Pipenv);
+        }
+    }
+
+    if let Some(version_file) = find_local_version_file(workspace_folder) {
+        if let Some(versions_dir) = get_versions_dir(os_environment) {
+            for version in read_version_file(&version_file) {
+                if let Some(prefix) = find_installed_version(&versions_dir, &version) {
+                    if let Some(env) = identify(&prefix) {
+                        return suggest(env, SuggestionSource::PythonVersionFile);
+                    }
+                }
+            }
+        }
+    }
+
+    trace!("No environment to suggest for {:?}", workspace_folder);
+    None
+}
+
+fn get_uv_project_environment(
+    workspace_folder: &Path,
+    os_environment: &dyn Environment,
+) -> Option<PathBuf> {
+    let path = os_environment.get_env_var("UV_PROJECT_ENVIRONMENT".to_string())?;
+    let path = PathBuf::from(path);
+    // Relative paths are resolved against the project folder.
+    Some(if path.is_absolute() {
+        path
+    } else {
+        workspace_folder.join(path)
+    })
+}
+
+/// Pixi environments of the project, with the `default` environment first.
+fn get_pixi_environments(workspace_folder: &Path) -> Vec<PathBuf> {
+    let envs_dir = workspace_folder.join(".pixi").join("envs");
+    let mut environments: Vec<PathBuf> = fs::read_dir(&envs_dir)
+        .map(|reader| {
+            reader
+                .filter_map(Result::ok)
+                .map(|entry| entry.path())
+                .filter(|path| path.is_dir())
+                .collect()
+        })
+        .unwrap_or_default();
+    environments.sort_by_key(|path| (!path.ends_with("default"), path.clone()));
+    environments
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pet_core::{os_environment::EnvironmentApi, python_environment::PythonEnvironmentKind};
+    use pet_venv::Venv;
+
+    fn create_venv(prefix: &Path) {
+        let bin = if cfg!(windows) {
+            prefix.join("Scripts")
+        } else {
+            prefix.join("bin")
+        };
+        fs::create_dir_all(&bin).unwrap();
+        let exe = if cfg!(windows) {
+            "python.exe"
+        } else {
+            "python"
+        };
+        fs::write(bin.join(exe), "").unwrap();
+        fs::write(prefix.join("pyvenv.cfg"), "version = 3.12.1\n").unwrap();
+    }
+
+    #[test]
+    fn suggests_venv_in_workspace_folder() {
+        let workspace = tempfile::tempdir().unwrap();
+        create_venv(&workspace.path().join("venv"));
+        create_venv(&workspace.path().join(".venv"));
+        let locators: Vec<Arc<dyn Locator>> = vec![Arc::new(Venv::new())];
+
+        let suggestion =
+            suggest_environment(workspace.path(), &locators, &EnvironmentApi::new()).unwrap();
+
+        assert_eq!(suggestion.source, SuggestionSource::WorkspaceEnvironment);
+        assert_eq!(
+            suggestion.environment.kind,
+            Some(PythonEnvironmentKind::Venv)
+        );
+        assert_eq!(
+            suggestion.environment.prefix,
+            Some(workspace.path().join(".venv"))
+        );
+    }
+
+    #[test]
+    fn default_pixi_environment_is_preferred() {
+        let workspace = tempfile::tempdir().unwrap();
+        let envs = workspace.path().join(".pixi").join("envs");
+        for name in ["cuda", "default", "dev"] {
+            fs::create_dir_all(envs.join(name)).unwrap();
+        }
+
+        assert_eq!(
+            get_pixi_environments(workspace.path()),
+            vec![envs.join("default"), envs.join("cuda"), envs.join("dev")]
+        );
+    }
+
+    #[test]
+    fn nothing_suggested_for_empty_folder() {
+        let workspace = tempfile::tempdir().unwrap();
+        let locators: Vec<Arc<dyn Locator>> = vec![Arc::new(Venv::new())];
+
+        assert!(suggest_environment(workspace.path(), &locators, &EnvironmentApi::new()).is_none());
+    }
+}
@@ -0,0 +1,43 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT License.
+
+//! Fixtures shared by the unit tests of this crate.
+
+use pet_core::{
+    os_environment::Environment,
+    python_environment::{PythonEnvironmentBuilder, PythonEnvironmentKind},
+};
+use std::{collections::HashMap, path::PathBuf};
+
+/// Environment with the given home directory & environment variables.
+pub struct TestEnvironment {
+    pub home: PathBuf,
+    pub vars: HashMap<String, String>,
+}
+
+impl Environment for TestEnvironment {
+    fn get_user_home(&self) -> Option<PathBuf> {
+        Some(self.home.clone())
+    }
+    fn get_root(&self) -> Option<PathBuf> {
+        None
+    }
+    fn get_env_var(&self, key: String) -> Option<String> {
+        self.vars.get(&key).cloned()
+    }
+    fn get_know_global_search_locations(&self) -> Vec<PathBuf> {
+        vec![]
+    }
+}
+
+/// Builder for an environment of the given kind, prefix & version,
+/// other fields (manager, executable, name, etc) can be set before it is built.
+pub fn create_env(
+    kind: PythonEnvironmentKind,
+    prefix: impl Into<PathBuf>,
+    version: Option<&str>,
+) -> PythonEnvironmentBuilder {
+    PythonEnvironmentBuilder::new(Some(kind))
+        .prefix(Some(prefix.into()))
+        .version(version.map(|version| version.to_string()))
+}
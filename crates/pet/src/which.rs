@@ -0,0 +1,530 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT License.
+
+//! Answers "which Python will `python` run here?".
+//!
+//! The command is looked up in the PATH (first match wins), then the shims of pyenv, asdf & mise
+//! are resolved using the version selected for the directory (`PYENV_VERSION`, `.python-version`,
+//! `.tool-versions`, `mise.toml`, ...) & symlinks are followed, until the real interpreter is reached.
+//! Python installed by uv (`uv python install --default`) are symlinks in `~/.local/bin`.
+
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use log::trace;
+use pet_core::{
+    env::PythonEnv, os_environment::Environment, python_environment::PythonEnvironment, Locator,
+};
+use pet_env_var_path::get_search_paths_from_env_variables;
+use pet_pyenv::version_selection::{
+    find_installed_version, find_local_version_file, read_version_file, GLOBAL_VERSION_FILE,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::locators::identify_python_environment_using_locators;
+
+/// Guards against symlink loops & shims that point to themselves.
+const MAX_LINKS: usize = 20;
+/// Version name used by pyenv, asdf & mise for the Python found on the PATH outside of the tool.
+const SYSTEM_VERSION: &str = "system";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum LinkKind {
+    /// Executable found in a directory on the PATH.
+    Path,
+    /// Target of the symlink.
+    Symlink,
+    /// Executable the shim runs, based on the version selected for the directory.
+    Shim,
+}
+
+/// Each of the executables that lead to the interpreter, starting with the one found on the PATH.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Link {
+    pub kind: LinkKind,
+    pub path: PathBuf,
+    pub reason: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WhichResult {
+    pub command: String,
+    pub cwd: PathBuf,
+    pub links: Vec<Link>,
+    /// None if the interpreter is not a known Python environment.
+    pub environment: Option<PythonEnvironment>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ShimTool {
+    Pyenv,
+    Asdf,
+    Mise,
+}
+
+/// Resolves the interpreter the command would run in the directory.
+pub fn which(
+    command: &str,
+    cwd: &Path,
+    locators: &[Arc<dyn Locator>],
+    os_environment: &dyn Environment,
+) -> Option<WhichResult> {
+    let links = resolve_command(
+        command,
+        cwd,
+        &get_path_directories(os_environment),
+        os_environment,
+    )?;
+    let executable = links.last()?.path.clone();
+    let env = PythonEnv::new(executable, None, None);
+    let global_env_search_paths = get_search_paths_from_env_variables(os_environment);
+    let environment =
+        identify_python_environment_using_locators(&env, locators, &global_env_search_paths);
+    Some(WhichResult {
+        command: command.to_string(),
+        cwd: cwd.to_path_buf(),
+        links,
+        environment,
+    })
+}
+
+/// Directories of the PATH, in the order the shell searches them.
+/// Unlike the global search paths used to discover environments, well known directories that are not on the PATH are excluded.
+fn get_path_directories(os_environment: &dyn Environment) -> Vec<PathBuf> {
+    os_environment
+        .get_env_var("PATH".to_string())
+        .map(|path| {
+            env::split_paths(&path)
+                .filter(|dir| !dir.as_os_str().is_empty())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+fn resolve_command(
+    command: &str,
+    cwd: &Path,
+    search_paths: &[PathBuf],
+    os_environment: &dyn Environment,
+) -> Option<Vec<Link>> {
+    let (dir, executable) = find_on_path(command, search_paths, None)?;
+    let mut links = vec![Link {
+        kind: LinkKind::Path,
+        path: executable,
+        reason: format!("Found on the PATH in {dir:?}"),
+    }];
+    while links.len() < MAX_LINKS {
+        let current = &links[links.len() - 1].path;
+        let next = match get_shim_tool(current) {
+            Some((tool, root)) => {
+                resolve_shim(tool, &root, current, cwd, search_paths, os_environment)
+            }
+            None => read_symlink(current),
+        };
+        match next {
+            Some(link) => links.push(link),
+            None => break,
+        }
+    }
+    trace!("Resolved {:?} in {:?} to {:?}", command, cwd, links);
+    Some(links)
+}
+
+/// Finds the first executable for the command in the search paths, excluding the given directory.
+fn find_on_path(
+    command: &str,
+    search_paths: &[PathBuf],
+    exclude: Option<&Path>,
+) -> Option<(PathBuf, PathBuf)> {
+    search_paths
+        .iter()
+        .filter(|dir| Some(dir.as_path()) != exclude)
+        .find_map(|dir| {
+            get_command_file_names(command)
+                .into_iter()
+                .map(|name| dir.join(name))
+                .find(|executable| executable.is_file())
+                .map(|executable| (dir.clone(), executable))
+        })
+}
+
+/// File names of the command, on Windows the extension is optional, e.g. `python.exe` or the `python.bat` shim of pyenv-win.
+fn get_command_file_names(command: &str) -> Vec<String> {
+    if cfg!(windows) && Path::new(command).extension().is_none() {
+        ["exe", "bat", "cmd"]
+            .iter()
+            .map(|ext| format!("{command}.{ext}"))
+            .chain([command.to_string()])
+            .collect()
+    } else {
+        vec![command.to_string()]
+    }
+}
+
+/// Shims are in a `shims` directory, next to `versions` for pyenv (& pyenv-win) & `installs` for asdf & mise.
+fn get_shim_tool(executable: &Path) -> Option<(ShimTool, PathBuf)> {
+    let shims_dir = executable.parent()?;
+    if shims_dir.file_name()? != "shims" {
+        return None;
+    }
+    let root = shims_dir.parent()?;
+    if root.join("versions").is_dir() {
+        Some((ShimTool::Pyenv, root.to_path_buf()))
+    } else if root.join("installs").is_dir() {
+        let name = root.file_name()?.to_string_lossy().to_lowercase();
+        let tool = if name.contains("mise") {
+            ShimTool::Mise
+        } else {
+            ShimTool::Asdf
+        };
+        Some((tool, root.to_path_buf()))
+    } else {
+        None
+    }
+}
+
+fn resolve_shim(
+    tool: ShimTool,
+    root: &Path,
+    shim: &Path,
+    cwd: &Path,
+    search_paths: &[PathBuf],
+    os_environment: &dyn Environment,
+) -> Option<Link> {
+    let (versions, source) = get_selected_versions(tool, root, cwd, os_environment)
+        .unwrap_or((vec![SYSTEM_VERSION.to_string()], "default".to_string()));
+    let name = shim.file_stem()?.to_str()?;
+    let installs_dir = match tool {
+        ShimTool::Pyenv => root.join("versions"),
+        ShimTool::Asdf | ShimTool::Mise => root.join("installs").join("python"),
+    };
+    // Like the tools, the first of the selected versions that has the command wins.
+    for version in versions {
+        let executable = if version == SYSTEM_VERSION {
+            find_on_path(name, search_paths, shim.parent()).map(|(_, executable)| executable)
+        } else {
+            find_installed_version(&installs_dir, &version)
+                .and_then(|prefix| find_command_in_prefix(&prefix, name))
+        };
+        if let Some(executable) = executable {
+            return Some(Link {
+                kind: LinkKind::Shim,
+                path: executable,
+                reason: format!("{tool:?} shim, version {version} selected by {source}"),
+            });
+        }
+    }
+    trace!(
+        "No version selected by {:?} has the command {:?}",
+        tool,
+        name
+    );
+    None
+}
+
+/// Versions selected for the directory & where they were selected, in order of precedence of the tool.
+fn get_selected_versions(
+    tool: ShimTool,
+    root: &Path,
+    cwd: &Path,
+    os_environment: &dyn Environment,
+) -> Option<(Vec<String>, String)> {
+    let env_var = match tool {
+        ShimTool::Pyenv => "PYENV_VERSION",
+        ShimTool::Asdf => "ASDF_PYTHON_VERSION",
+        ShimTool::Mise => "MISE_PYTHON_VERSION",
+    };
+    if let Some(value) = os_environment.get_env_var(env_var.to_string()) {
+        let versions: Vec<String> = value
+            .split([':', ' '])
+            .filter(|version| !version.is_empty())
+            .map(|version| version.to_string())
+            .collect();
+        if !versions.is_empty() {
+            return Some((versions, env_var.to_string()));
+        }
+    }
+    let from_file = |file: PathBuf, versions: Vec<String>| {
+        if versions.is_empty() {
+            None
+        } else {
+            Some((versions, format!("{file:?}")))
+        }
+    };
+    let home = os_environment.get_user_home();
+    match tool {
+        ShimTool::Pyenv => {
+            let local = find_local_version_file(cwd);
+            let global = root.join(GLOBAL_VERSION_FILE);
+            local
+                .into_iter()
+                .chain([global])
+                .find_map(|file| from_file(file.clone(), read_version_file(&file)))
+        }
+        ShimTool::Asdf => cwd
+            .ancestors()
+            .map(Path::to_path_buf)
+            .chain(home)
+            .map(|dir| dir.join(".tool-versions"))
+            .find_map(|file| from_file(file.clone(), read_tool_versions(&file))),
+        ShimTool::Mise => {
+            let global = home.map(|home| home.join(".config").join("mise").join("config.toml"));
+            cwd.ancestors()
+                .flat_map(|dir| {
+                    [
+                        "mise.toml",
+                        ".mise.toml",
+                        ".tool-versions",
+                        ".python-version",
+                    ]
+                    .map(|name| dir.join(name))
+                })
+                .chain(global)
+                .find_map(|file| {
+                    let versions = match file.file_name()?.to_str()? {
+                        ".tool-versions" => read_tool_versions(&file),
+                        ".python-version" => read_version_file(&file),
+                        _ => read_mise_config(&file),
+                    };
+                    from_file(file, versions)
+                })
+        }
+    }
+}
+
+/// Python versions in a `.tool-versions` file (asdf & mise), e.g. `python 3.12.1 3.11.7`.
+fn read_tool_versions(file: &Path) -> Vec<String> {
+    fs::read_to_string(file)
+        .unwrap_or_default()
+        .lines()
+        .map(|line| line.split('#').next().unwrap_or_default())
+        .filter_map(|line| {
+            let mut parts = line.split_whitespace();
+            if parts.next()? == "python" {
+                Some(parts.map(|version| version.to_string()).collect::<Vec<_>>())
+            } else {
+                None
+            }
+        })
+        .next()
+        .unwrap_or_default()
+}
+
+/// Python versions in the `[tools]` of a mise config file,
+/// e.g. `python = "3.12"`, `python = ["3.12", "3.11"]` or `python = { version = "3.12" }`.
+fn read_mise_config(file: &Path) -> Vec<String> {
+    let contents = match fs::read_to_string(file) {
+        Ok(contents) => contents,
+        Err(_) => return vec![],
+    };
+    let value = match toml::from_str::<toml::Value>(&contents) {
+        Ok(value) => value,
+        Err(err) => {
+            trace!("Failed to parse mise config {:?}: {}", file, err);
+            return vec![];
+        }
+    };
+    let get_version = |value: &toml::Value| -> Option<String> {
+        match value {
+            toml::Value::String(version) => Some(version.clone()),
+            toml::Value::Table(table) => table.get("version")?.as_str().map(str::to_string),
+            _ => None,
+        }
+    };
+    match value.get("tools").and_then(|tools| tools.get("python")) {
+        Some(toml::Value::Array(versions)) => versions.iter().filter_map(get_version).collect(),
+        Some(version) => get_version(version).into_iter().collect(),
+        None => vec![],
+    }
+}
+
+fn find_command_in_prefix(prefix: &Path, name: &str) -> Option<PathBuf> {
+    let names = get_command_file_names(name);
+    [
+        prefix.join("bin"),
+        prefix.join("Scripts"),
+        prefix.to_path_buf(),
+    ]
+    .iter()
+    .flat_map(|dir| names.iter().map(move |name| dir.join(name)))
+    .find(|executable| executable.is_file())
+}
+
+fn read_symlink(path: &Path) -> Option<Link> {
+    if !fs::symlink_metadata(path).ok()?.file_type().is_symlink() {
+        return None;
+    }
+    let target = fs::read_link(path).ok()?;
+    // Relative targets are relative to the directory of the symlink.
+    let target = if target.is_relative() {
+        path.parent()?.join(target)
+    } else {
+        target
+    };
+    Some(Link {
+        kind: LinkKind::Symlink,
+        path: target,
+        reason: format!("Symlink {path:?}"),
+    })
+}
+
+#[cfg(test)]
+#[cfg(unix)]
+mod tests {
+    use super::*;
+    use crate::test_utils::TestEnvironment;
+    use std::{collections::HashMap, os::unix::fs::symlink};
+
+    fn create_file(path: &Path) -> PathBuf {
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, "").unwrap();
+        path.to_path_buf()
+    }
+
+    fn resolve(
+        command: &str,
+        cwd: &Path,
+        search_paths: &[PathBuf],
+        env: &TestEnvironment,
+    ) -> Vec<(LinkKind, PathBuf)> {
+        resolve_command(command, cwd, search_paths, env)
+            .unwrap()
+            .into_iter()
+            .map(|link| (link.kind, link.path))
+            .collect()
+    }
+
+    #[test]
+    fn pyenv_shims_use_the_selected_version() {
+        let dir = tempfile::tempdir().unwrap();
+        let dir = fs::canonicalize(dir.path()).unwrap();
+        let pyenv = dir.join(".pyenv");
+        let shims = pyenv.join("shims");
+        let shim = create_file(&shims.join("python"));
+        let python312 = create_file(&pyenv.join("versions/3.12.1/bin/python3.12"));
+        symlink("python3.12", pyenv.join("versions/3.12.1/bin/python")).unwrap();
+        create_file(&pyenv.join("versions/3.11.7/bin/python"));
+        fs::write(pyenv.join("version"), "3.11.7\n").unwrap();
+        let system = create_file(&dir.join("usr/bin/python"));
+        let project = dir.join("project");
+        fs::create_dir_all(&project).unwrap();
+        fs::write(project.join(".python-version"), "3.12\n").unwrap();
+        let search_paths = vec![shims.clone(), dir.join("usr/bin")];
+        let mut env = TestEnvironment {
+            home: dir.clone(),
+            vars: HashMap::new(),
+        };
+
+        assert_eq!(
+            resolve("python", &project, &search_paths, &env),
+            vec![
+                (LinkKind::Path, shim.clone()),
+                (LinkKind::Shim, pyenv.join("versions/3.12.1/bin/python")),
+                (LinkKind::Symlink, python312),
+            ]
+        );
+        assert_eq!(
+            resolve("python", &dir, &search_paths, &env)[1],
+            (LinkKind::Shim, pyenv.join("versions/3.11.7/bin/python"))
+        );
+
+        env.vars
+            .insert("PYENV_VERSION".to_string(), "system".to_string());
+        assert_eq!(
+            resolve("python", &project, &search_paths, &env),
+            vec![(LinkKind::Path, shim), (LinkKind::Shim, system)]
+        );
+    }
+
+    #[test]
+    fn asdf_and_mise_shims_use_the_selected_version() {
+        let dir = tempfile::tempdir().unwrap();
+        let dir = fs::canonicalize(dir.path()).unwrap();
+        let project = dir.join("project");
+        fs::create_dir_all(&project).unwrap();
+        let env = TestEnvironment {
+            home: dir.clone(),
+            vars: HashMap::new(),
+        };
+
+        let asdf = dir.join(".asdf");
+        create_file(&asdf.join("shims/python3"));
+        let asdf_python = create_file(&asdf.join("installs/python/3.11.7/bin/python3"));
+        fs::write(
+            project.join(".tool-versions"),
+            "nodejs 20.11.0\npython 3.11.7 # pinned\n",
+        )
+        .unwrap();
+        assert_eq!(
+            resolve("python3", &project, &[asdf.join("shims")], &env)[1],
+            (LinkKind::Shim, asdf_python)
+        );
+
+        let mise = dir.join(".local/share/mise");
+        create_file(&mise.join("shims/python3"));
+        let mise_python = create_file(&mise.join("installs/python/3.12.4/bin/python3"));
+        fs::write(project.join("mise.toml"), "[tools]\npython = \"3.12\"\n").unwrap();
+        assert_eq!(
+            resolve("python3", &project, &[mise.join("shims")], &env)[1],
+            (LinkKind::Shim, mise_python)
+        );
+    }
+
+    #[test]
+    fn commands_are_only_found_on_the_path() {
+        let dir = tempfile::tempdir().unwrap();
+        let dir = fs::canonicalize(dir.path()).unwrap();
+        create_file(&dir.join("not-on-path/python"));
+        let python = create_file(&dir.join("on-path/python"));
+        let mut env = TestEnvironment {
+            home: dir.clone(),
+            vars: HashMap::from([(
+                "PATH".to_string(),
+                env::join_paths([dir.join("empty"), dir.join("not-a-dir")])
+                    .unwrap()
+                    .to_string_lossy()
+                    .to_string(),
+            )]),
+        };
+        // The well known directories (such as `/usr/bin`) are not searched when they are not on the PATH.
+        assert!(which("python", &dir, &[], &env).is_none());
+
+        env.vars.insert(
+            "PATH".to_string(),
+            env::join_paths([dir.join("empty"), dir.join("on-path")])
+                .unwrap()
+                .to_string_lossy()
+                .to_string(),
+        );
+        let result = which("python", &dir, &[], &env).unwrap();
+        assert_eq!(result.links.len(), 1);
+        assert_eq!(result.links[0].path, python);
+    }
+
+    #[test]
+    fn read_mise_config_versions() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("mise.toml");
+        for (contents, expected) in [
+            ("[tools]\npython = \"3.12\"\n", vec!["3.12"]),
+            (
+                "[tools]\npython = [\"3.12\", \"3.11\"]\n",
+                vec!["3.12", "3.11"],
+            ),
+            (
+                "[tools]\npython = { version = \"3.13\", virtualenv = \".venv\" }\n",
+                vec!["3.13"],
+            ),
+            ("[tools]\nnode = \"20\"\n", vec![]),
+        ] {
+            fs::write(&file, contents).unwrap();
+            assert_eq!(read_mise_config(&file), expected);
+        }
+    }
+}
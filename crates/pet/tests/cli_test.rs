@@ -0,0 +1,131 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT License.
+
+use serde_json::Value;
+use std::{fs, path::Path, process::Command};
+
+fn create_venv(prefix: &Path) {
+    let bin = if cfg!(windows) { "Scripts" } else { "bin" };
+    let python = if cfg!(windows) {
+        "python.exe"
+    } else {
+        "python"
+    };
+    fs::create_dir_all(prefix.join(bin)).unwrap();
+    fs::write(prefix.join(bin).join(python), "").unwrap();
+    fs::write(
+        prefix.join("pyvenv.cfg"),
+        "home = /usr/bin\nversion = 3.12.1\n",
+    )
+    .unwrap();
+}
+
+#[test]
+fn find_workspace_environments_of_kinds() {
+    let dir = tempfile::tempdir().unwrap();
+    let workspace = dir.path().join("project");
+    create_venv(&workspace.join(".venv"));
+
+    let output = Command::new(env!("CARGO_BIN_EXE_pet"))
+        .arg("find")
+        .arg(&workspace)
+        .args(["--workspace", "--kind", "Venv", "--kind", "Conda", "--json"])
+        .output()
+        .unwrap();
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    let result: Value = serde_json::from_slice(&output.stdout).unwrap();
+    let environments = result["environments"].as_array().unwrap();
+    assert_eq!(environments.len(), 1, "{environments:?}");
+    assert_eq!(environments[0]["kind"], "Venv");
+    assert_eq!(
+        environments[0]["prefix"],
+        workspace.join(".venv").to_string_lossy().as_ref()
+    );
+}
+
+#[test]
+fn resolve_uses_the_workspace_config_file() {
+    let dir = tempfile::tempdir().unwrap();
+    let workspace = dir.path().join("project");
+    create_venv(&workspace.join(".venv"));
+    let python = if cfg!(windows) {
+        workspace.join(".venv").join("Scripts").join("python.exe")
+    } else {
+        workspace.join(".venv").join("bin").join("python")
+    };
+    let resolve = || {
+        let output = Command::new(env!("CARGO_BIN_EXE_pet"))
+            .current_dir(&workspace)
+            // Ignore the config file of the user running the tests.
+            .env("XDG_CONFIG_HOME", dir.path().join("config"))
+            .arg("resolve")
+            .arg(&python)
+            .arg("--json")
+            .output()
+            .unwrap();
+        assert!(
+            output.status.success(),
+            "{}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+        serde_json::from_slice::<Value>(&output.stdout).unwrap()
+    };
+
+    assert_eq!(resolve()["kind"], "Venv");
+
+    fs::write(
+        workspace.join(".pet.toml"),
+        "disabled-locators = [\"Venv\"]\n",
+    )
+    .unwrap();
+    assert_eq!(resolve(), Value::Null);
+}
+
+#[cfg(unix)]
+#[test]
+fn which_looks_up_the_version_from_the_parents_of_a_relative_cwd() {
+    let dir = tempfile::tempdir().unwrap();
+    let home = fs::canonicalize(dir.path()).unwrap();
+    let pyenv = home.join(".pyenv");
+    for file in [
+        pyenv.join("shims").join("python"),
+        pyenv.join("versions/3.12.1/bin/python"),
+        pyenv.join("versions/3.11.7/bin/python"),
+    ] {
+        fs::create_dir_all(file.parent().unwrap()).unwrap();
+        fs::write(file, "").unwrap();
+    }
+    fs::write(pyenv.join("version"), "3.11.7\n").unwrap();
+    let workspace = home.join("workspace");
+    fs::create_dir_all(workspace.join("project")).unwrap();
+    fs::write(workspace.join(".python-version"), "3.12\n").unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_pet"))
+        .current_dir(workspace.join("project"))
+        .env("HOME", &home)
+        .env("PATH", pyenv.join("shims"))
+        .env("XDG_CONFIG_HOME", home.join("config"))
+        .args(["which", "python", "--cwd", ".", "--json"])
+        .output()
+        .unwrap();
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    let result: Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(
+        result["links"][1]["path"],
+        pyenv
+            .join("versions/3.12.1/bin/python")
+            .to_string_lossy()
+            .as_ref(),
+        "{result}"
+    );
+}
@@ -427,6 +427,12 @@ impl PetClient {
     pub fn startup_time(&self) -> Duration {
         self.start_time.elapsed()
     }
+
+    /// Get the process id of the server
+    #[allow(dead_code)]
+    pub fn pid(&self) -> u32 {
+        self.process.id()
+    }
 }
 
 impl Drop for PetClient {
@@ -761,7 +767,7 @@ fn test_kind_specific_refresh_performance() {
 
             let (result, _) = client
                 .refresh(Some(json!({ "searchKind": kind })))
-                .expect(&format!("Failed to refresh for kind {}", kind));
+                .unwrap_or_else(|_| panic!("Failed to refresh for kind {}", kind));
 
             let environments = client.get_environments();
             server_duration_stats.add(result.duration);
@@ -987,6 +993,77 @@ fn test_refresh_warm_vs_cold_cache() {
     }
 }
 
+/// Number of threads of a process, read from `/proc/<pid>/status`.
+#[allow(dead_code)]
+fn get_thread_count(pid: u32) -> Option<usize> {
+    std::fs::read_to_string(format!("/proc/{}/status", pid))
+        .ok()?
+        .lines()
+        .find_map(|line| line.strip_prefix("Threads:"))
+        .and_then(|count| count.trim().parse().ok())
+}
+
+#[cfg_attr(all(feature = "ci-perf", target_os = "linux"), test)]
+#[allow(dead_code)]
+fn test_refresh_thread_count_is_bounded() {
+    // Discovery threads are limited to `jobs`, however the server has a few other threads,
+    // such as the main thread, request handlers & threads that spawn conda/poetry after a refresh.
+    const JOBS: usize = 2;
+    const OTHER_THREADS: usize = 16;
+
+    let cache_dir = get_test_cache_dir();
+    let workspace_dir = get_workspace_dir();
+
+    println!("=== Refresh Thread Count (jobs = {}) ===", JOBS);
+
+    let mut client = PetClient::spawn().expect("Failed to spawn server");
+    client
+        .configure(json!({
+            "workspaceDirectories": [workspace_dir.clone()],
+            "cacheDirectory": cache_dir.clone(),
+            "jobs": JOBS
+        }))
+        .expect("Failed to configure");
+
+    // Sample the number of threads of the server while refreshing.
+    let pid = client.pid();
+    let done = Arc::new(std::sync::atomic::AtomicBool::new(false));
+    let max_threads = Arc::new(Mutex::new(0usize));
+    let sampler = {
+        let done = done.clone();
+        let max_threads = max_threads.clone();
+        std::thread::spawn(move || {
+            while !done.load(Ordering::SeqCst) {
+                if let Some(count) = get_thread_count(pid) {
+                    let mut max_threads = max_threads.lock().unwrap();
+                    *max_threads = (*max_threads).max(count);
+                }
+                std::thread::sleep(Duration::from_millis(2));
+            }
+        })
+    };
+
+    let (result, _) = client.refresh(None).expect("Failed to refresh");
+    done.store(true, Ordering::SeqCst);
+    sampler.join().unwrap();
+
+    let max_threads = *max_threads.lock().unwrap();
+    println!(
+        "  Refresh: {}ms, envs={}, max threads={}",
+        result.duration,
+        client.get_environments().len(),
+        max_threads
+    );
+
+    assert!(max_threads > 0, "Unable to sample the thread count");
+    assert!(
+        max_threads <= JOBS + OTHER_THREADS,
+        "Server used {} threads, expected at most {}",
+        max_threads,
+        JOBS + OTHER_THREADS
+    );
+}
+
 #[cfg_attr(feature = "ci-perf", test)]
 #[allow(dead_code)]
 fn test_performance_summary() {